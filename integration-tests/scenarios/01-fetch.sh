@@ -20,6 +20,17 @@ wrapped_dedoc ls
 # Should fail due to recent docs.json.
 ! wrapped_dedoc fetch
 
+# Fetch from a local mirror, using the flag, the environment variable and the
+# config.
+MIRROR="file://$(realpath ./data)"
+wrapped_dedoc fetch -f --mirror "$MIRROR" | grep "$MIRROR/docs.json"
+DEDOC_DOCS_JSON_URL="$MIRROR/docs.json" wrapped_dedoc fetch -f | \
+grep "$MIRROR/docs.json"
+echo "docs_json_url = \"$MIRROR\"" > "$DEDOC_HOME/config.toml"
+wrapped_dedoc fetch -f | grep "$MIRROR/docs.json"
+rm "$DEDOC_HOME/config.toml"
+! wrapped_dedoc fetch -f --mirror "file:///nonexistent"
+
 # Test with a different $DEDOC_HOME.
 export DEDOC_HOME="/root/.dedoc2"
 ! wrapped_dedoc ls
@@ -13,6 +13,16 @@ wrapped_dedoc dl docset-1 docset-3~1
 # Non-existent docset.
 ! wrapped_dedoc dl whatever
 
+# With `--json`, having nothing to do is not an error.
+test "$(wrapped_dedoc dl --json docset-1 2> /dev/null)" = \
+     '[{"docset":"docset-1","status":"up_to_date"}]'
+test "$(wrapped_dedoc dl --rollback --json whatever 2> /dev/null)" = \
+     '[{"docset":"whatever","status":"no_previous_version"}]'
+
+# Status of each docset, without progress.
+wrapped_dedoc dl -f --json docset-1 whatever | diff_stdin_to_text \
+'[{"docset":"docset-1","status":"installed"},{"docset":"whatever","status":"not_found"}]'
+
 # Break .mtime files, so dedoc would have to update both docsets.
 rm "$DEDOC_HOME/docsets/docset-1/.dedoc_mtime"
 printf "0" > "$DEDOC_HOME/docsets/docset-3~1/.dedoc_mtime"
@@ -24,9 +34,104 @@ rm "$DEDOC_HOME/docsets/docset-3~1/.dedoc_mtime"
 
 wrapped_dedoc dl -u | grep "1 item was successfully updated."
 
+# Update both docsets at once.
+rm "$DEDOC_HOME/docsets/docset-1/.dedoc_mtime"
+rm "$DEDOC_HOME/docsets/docset-3~1/.dedoc_mtime"
+
+wrapped_dedoc dl -u -j 2 | grep "2 items were successfully updated."
+! wrapped_dedoc dl -j 0 docset-1
+
 # Swap mock docs.json to confuse dedoc with non-existing docsets on update.
 swap_docs_json
 wrapped_dedoc dl -u 2>&1 | grep "Unable to find"
 swap_docs_json
 
+# Download from a local mirror.
+MIRROR="file://$(realpath ./data)"
+wrapped_dedoc dl -f --mirror "$MIRROR" docset-1
+DEDOC_DOCUMENTS_URL="$MIRROR" wrapped_dedoc dl -f docset-1
+! wrapped_dedoc dl -f --mirror "file:///nonexistent" docset-1
+
+# Continue an interrupted download. Leftovers of other versions are removed.
+STAGING="$DEDOC_HOME/staging/docset-3~1"
+mkdir -p "$STAGING"
+head -c 1000 ./data/docset-3~1/db.json > "$STAGING/db.json.1.part"
+touch "$STAGING/db.json.0.part"
+wrapped_dedoc dl -f docset-3~1 | grep "Received 2870 bytes, file 1 of 2..."
+test ! -e "$STAGING"
+
+# A download of a different size than in docs.json does not replace anything.
+BROKEN_MIRROR="$(mktemp -d)"
+cp -r ./data/docset-3~1 "$BROKEN_MIRROR"
+truncate -s 100 "$BROKEN_MIRROR/docset-3~1/db.json"
+wrapped_dedoc dl -f --mirror "file://$BROKEN_MIRROR" docset-3~1 2>&1 \
+  | grep -F 'Received 100 bytes from'
+test -z "$(find "$STAGING" -name "*.part")"
+wrapped_dedoc open docset-3~1 functions/e_borrow_checker > /dev/null
+
+# A docset that fails to build does not replace the current version either.
+printf 'x' > "$BROKEN_MIRROR/docset-3~1/db.json"
+tail -c +2 ./data/docset-3~1/db.json >> "$BROKEN_MIRROR/docset-3~1/db.json"
+! wrapped_dedoc dl -f --mirror "file://$BROKEN_MIRROR" docset-3~1
+wrapped_dedoc open docset-3~1 functions/e_borrow_checker > /dev/null
+rm -r "$BROKEN_MIRROR"
+
+# The replaced version can be restored, and restoring twice brings back the
+# newer one.
+touch "$DEDOC_HOME/docsets/docset-3~1/old-version"
+wrapped_dedoc dl -f docset-3~1
+test ! -e "$DEDOC_HOME/docsets/docset-3~1/old-version"
+wrapped_dedoc dl --rollback docset-3~1 | grep "Rollback has successfully finished."
+test -e "$DEDOC_HOME/docsets/docset-3~1/old-version"
+test "$(wrapped_dedoc dl --rollback --json docset-3~1 whatever 2> /dev/null)" = \
+'[{"docset":"docset-3~1","status":"rolled_back"},{"docset":"whatever","status":"no_previous_version"}]'
+test ! -e "$DEDOC_HOME/docsets/docset-3~1/old-version"
+! wrapped_dedoc dl --rollback whatever
+
+# Names that point outside of the docsets directory are not rolled back.
+! wrapped_dedoc dl --rollback ../docsets
+test -e "$DEDOC_HOME/docsets/docset-3~1"
+test "$(wrapped_dedoc dl --rollback --json ../docsets 2> /dev/null)" = \
+     '[{"docset":"../docsets","status":"forbidden_name"}]'
+test -e "$DEDOC_HOME/docsets/docset-3~1"
+
+# Failed docsets don't stop others and are listed at the end.
+wrapped_dedoc dl -f -j 2 --mirror "file:///nonexistent" docset-1 docset-3~1 2>&1 \
+  | grep -F '2 of 2 docsets failed to download: `docset-1`, `docset-3~1`.'
+wrapped_dedoc dl -f --json --mirror "file:///nonexistent" docset-1 2>/dev/null \
+  | grep -F '"status":"failed","error":"Could not download'
+
+# Docsets can be built from local files, without docs.json or a mirror.
+LOCAL_HOME="$(mktemp -d)"
+DEDOC_HOME="$LOCAL_HOME" wrapped_dedoc dl --from-dir ./data docset-1 docset-3~1 \
+  | grep "2 items were successfully installed."
+DEDOC_HOME="$LOCAL_HOME" wrapped_dedoc open docset-3~1 functions/e_borrow_checker \
+  | grep "The Borrow Checker"
+DEDOC_HOME="$LOCAL_HOME" wrapped_dedoc dl --from-dir ./data/docset-3~1 custom
+test "$(DEDOC_HOME="$LOCAL_HOME" wrapped_dedoc dl --json --from-files mine \
+          ./data/docset-1/db.json ./data/docset-1/index.json)" = \
+'[{"docset":"mine","status":"installed"}]'
+test "$(DEDOC_HOME="$LOCAL_HOME" wrapped_dedoc ls -l)" = \
+'custom [downloaded], docset-1 [downloaded], docset-3~1 [downloaded], mine [downloaded]'
+
+# Refreshing `docs.json` keeps local docsets, and removing them forgets them.
+DEDOC_HOME="$LOCAL_HOME" DEDOC_DOCS_JSON_URL="file://$(realpath ./data/docs.json)" \
+  wrapped_dedoc fetch -f
+test "$(DEDOC_HOME="$LOCAL_HOME" wrapped_dedoc ls -l)" = \
+'custom [downloaded], docset-1 [downloaded], docset-3~1 [downloaded], mine [downloaded]'
+DEDOC_HOME="$LOCAL_HOME" wrapped_dedoc outdated | grep -F "mine "
+DEDOC_HOME="$LOCAL_HOME" wrapped_dedoc export mine -o "$LOCAL_HOME/mine.tar"
+DEDOC_HOME="$LOCAL_HOME" wrapped_dedoc rm mine
+test "$(DEDOC_HOME="$LOCAL_HOME" wrapped_dedoc ls -a)" = \
+'custom [downloaded], docset-1 [downloaded], docset-2, docset-3~1 [downloaded], docset-3~2'
+! DEDOC_HOME="$LOCAL_HOME" wrapped_dedoc dl --from-dir ./data whatever
+DEDOC_HOME="$LOCAL_HOME" wrapped_dedoc dl --from-dir ./data whatever docset-1 2>&1 \
+  | grep -F '1 of 2 docsets failed to install: `whatever`.'
+DEDOC_HOME="$LOCAL_HOME" wrapped_dedoc dl --json --from-dir ./data whatever docset-1 2>/dev/null \
+  | grep -F '{"docset":"whatever","status":"failed","error":"Could not read' \
+  | grep -F '{"docset":"docset-1","status":"installed"}'
+! DEDOC_HOME="$LOCAL_HOME" wrapped_dedoc dl --from-files ../escape ./data/docset-1/db.json \
+  ./data/docset-1/index.json
+rm -r "$LOCAL_HOME"
+
 wrapped_dedoc rm --purge-all
@@ -66,4 +66,26 @@ wrapped_dedoc ls -l --exists docset-1
 ! wrapped_dedoc ls --exists docset-4
 ! wrapped_dedoc ls -l --exists docset-2
 
+# --json, with versions.
+wrapped_dedoc ls -l --json | diff_stdin_to_text \
+'[{"name":"docset-1","version":"1","release":"","mtime":1,"downloaded":true,"local_mtime":1},{"name":"docset-3~1","version":"1","release":"","mtime":1,"downloaded":true,"local_mtime":1}]'
+wrapped_dedoc ls -o --json | diff_stdin_to_text \
+'[{"name":"docset-2","version":"","release":"","mtime":1,"downloaded":false,"local_mtime":null}]'
+
+# --long, with names and releases.
+test "$(wrapped_dedoc ls -a --long)" = \
+'docset-1           Test docset 1  [downloaded]
+docset-2           Test docset 2
+docset-3~1         Test docset 3.1  [downloaded]
+docset-3~2  3.2.0  Test docset 3.2'
+test "$(wrapped_dedoc ls -o --long -s 3)" = 'docset-3~2  3.2.0  Test docset 3.2'
+
+# info.
+wrapped_dedoc info docset-3~2 | grep -F 'Attribution:    © 2024 Test Authors Licensed under the MIT License.'
+wrapped_dedoc info docset-3~2 | grep -F 'Home:           https://example.com/docset-3'
+wrapped_dedoc info docset-3~2 | grep -F 'Installed:      no'
+wrapped_dedoc info docset-1 | grep -F 'Size on disk:'
+wrapped_dedoc info --json docset-1 | grep -F '"name":"Test docset 1","type":"test"'
+! wrapped_dedoc info docset-4
+
 wrapped_dedoc rm --purge-all
@@ -24,3 +24,8 @@ wrapped_dedoc ls -l | diff_stdin_to_text ""
 wrapped_dedoc dl docset-1 docset-3~1
 wrapped_dedoc rm --purge-all
 wrapped_dedoc ls -l | diff_stdin_to_text ""
+
+# Status of each docset.
+wrapped_dedoc dl docset-1
+wrapped_dedoc rm --json docset-1 docset-2 | diff_stdin_to_text \
+'[{"docset":"docset-1","status":"removed"},{"docset":"docset-2","status":"not_installed"}]'
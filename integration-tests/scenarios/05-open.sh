@@ -7,9 +7,61 @@ set -eu
 
 wrapped_dedoc dl docset-1
 
+# Browser only works in a terminal.
+wrapped_dedoc browse docset-1 < /dev/null 2>&1 | grep "needs a terminal"
+
 # Open a page from some docset.
 wrapped_dedoc open docset-1 type-1/1 | diff_stdin_to_text "# test"
 
+# Links are listed as arguments for `open`, or as absolute URLs.
+wrapped_dedoc dl docset-3~1
+wrapped_dedoc open docset-3~1 guides/links | tail -n 4 | diff_stdin_to_text \
+'See the workarounds[1] and E0597[2].
+
+[1]: docset-3~1 functions/e_borrow_checker#workarounds
+[2]: https://doc.rust-lang.org/error_codes/E0597.html'
+
+# Print Markdown.
+wrapped_dedoc open docset-3~1 "tables/e_anime_schema#schema-definition" -m | diff_stdin_to_text \
+'...
+<a id="schema-definition"></a>
+
+| Column        | Type     | Description                 |
+| ------------- | -------- | --------------------------- |
+| `best_waifu`  | `TEXT`   | Objectively correct opinion |
+| `power_level` | `BIGINT` | Always over 9000            |
+...'
+wrapped_dedoc open docset-3~1 guides/links --markdown | head -n 7 | tail -n 3 | \
+diff_stdin_to_text \
+'```rust
+let row = db.query_one("SELECT waifu FROM anime", &[])?;
+```'
+
+# Follow links.
+wrapped_dedoc open docset-3~1 guides/links -f 1 | diff_stdin_to_text \
+'...
+Rust: [IMAGE: NO PERMISSION MEME]
+
+## Common Workarounds
+
+These are terrible ideas:
+* `unsafe { std::mem::transmute(conn) }`
+* Using 137 nested `RefCell`s
+* Switching careers to JavaScript'
+wrapped_dedoc open docset-3~1 guides/links --follow 2 | \
+grep -x "https://doc.rust-lang.org/error_codes/E0597.html"
+! wrapped_dedoc open docset-3~1 guides/links -f 3
+! wrapped_dedoc open docset-3~1 guides/links -f 0
+! wrapped_dedoc open --html "./data/example-page-mayonnaise.html" -f 1
+
+# Print the page with its links as JSON.
+wrapped_dedoc open docset-3~1 guides/links --json | grep -F \
+'"links":[{"page":{"item":"functions/e_borrow_checker","fragment":"workarounds"}},{"external":"https://doc.rust-lang.org/error_codes/E0597.html"}]}'
+wrapped_dedoc open docset-3~1 guides/links --json | grep -F \
+'"text":"# Links\n\n'
+test "$(wrapped_dedoc open docset-3~1 guides/links -f 2 --json)" = \
+     '{"external":"https://doc.rust-lang.org/error_codes/E0597.html"}'
+
 # Check whether dedoc can act as a HTML transpiler. Default width is 80
 # characters.
 wrapped_dedoc open --html "./data/example-page-mayonnaise.html" | \
@@ -96,4 +148,24 @@ diff_stdin_to_text \
    42  
    43  [1]: #somehref1'
 
+# Pre-rendered pages should look the same as pages rendered from HTML.
+PAGES="guides/e_lifetime_errors tables/e_anime_schema"
+for PAGE in $PAGES; do
+  wrapped_dedoc open docset-3~1 "$PAGE" -c 44 > "/tmp/$(basename "$PAGE").txt"
+done
+wrapped_dedoc dl -f --prerender docset-3~1
+for PAGE in $PAGES; do
+  test -f "$DEDOC_HOME/docsets/docset-3~1/$PAGE.prerendered.json"
+  wrapped_dedoc open docset-3~1 "$PAGE" -c 44 | diff -u "/tmp/$(basename "$PAGE").txt" -
+done
+
+# Stale pre-rendered pages are removed on a normal download.
+wrapped_dedoc dl -f docset-3~1
+test ! -f "$DEDOC_HOME/docsets/docset-3~1/guides/e_lifetime_errors.prerendered.json"
+
+# The pager is only used when output is a terminal.
+test "$(PAGER=false wrapped_dedoc open docset-1 type-1/1)" = '# test'
+test "$(wrapped_dedoc open --no-pager docset-1 type-1/1)" = '# test'
+test "$(PAGER=false wrapped_dedoc search docset-1 1 -o 1 --no-pager)" = '# test'
+
 wrapped_dedoc rm --purge-all
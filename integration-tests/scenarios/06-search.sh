@@ -41,6 +41,23 @@ Exact matches in `docset-3~1`:
    2  errors/e_mom_yelling
    3  guides/e_lifetime_errors'
 
+# Fuzzy search, with a typo.
+wrapped_dedoc ss docset-3~1 -z comitfaild | diff_stdin_to_text \
+'Searching for `comitfaild`...
+Exact matches in `docset-3~1`:
+   1  errors/commit_failed'
+
+wrapped_dedoc ss docset-3~1 -z idx rec | diff_stdin_to_text \
+'Searching for `idx rec`...
+Exact matches in `docset-3~1`:
+   1  tables/e_anime_schema, #index-recommendations'
+
+! wrapped_dedoc ss docset-3~1 -z -p idx
+
+# Results as JSON, numbered as for `--open`.
+test "$(wrapped_dedoc ss docset-3~1 -z idx rec --json)" = \
+'{"query":"idx rec","exact":[{"number":1,"docset":"docset-3~1","item":"tables/e_anime_schema","fragment":"index-recommendations","highlights":[22,24,26,28,29,30]}],"vague":[]}'
+
 wrapped_dedoc ss docset-3~1 -p startup | diff_stdin_to_text \
 "Searching for \`startup\`...
 No exact matches in \`docset-3~1\`.
@@ -59,6 +76,38 @@ Mentions in other files from `docset-3~1`:
    1  errors/e_mom_yelling
         ...orking on a startup!"</h3><p>Buys you 3-6 months</p></div></section></d...'
 
+# Every word of the query has to be on a page, and pages that mention them more
+# often come first.
+test "$(wrapped_dedoc ss docset-3~1 -p PostgreSQL mutable --porcelain)" = \
+     "functions/e_borrow_checker"
+test "$(wrapped_dedoc ss docset-3~1 -pi conn --porcelain)" = \
+"guides/e_lifetime_errors
+functions/e_borrow_checker"
+
+# Words of the query match the beginning of words on a page, including markup,
+# and results are the same without the full-text index.
+test -z "$(wrapped_dedoc ss docset-3~1 -p ostgre --porcelain)"
+search_precisely() {
+for QUERY in "Postgre" "-i conn" "-i POSTGRESQL connection" "PostgreSQL mutable" \
+             "-w borrowed" "id='steps'" "<code>" "startup!\"</h3>" "-w you"; do
+  # Flags and words of the query are split on purpose.
+  # shellcheck disable=SC2086
+  wrapped_dedoc ss docset-3~1 -p $QUERY
+done
+}
+search_precisely > /tmp/indexed-search.txt
+grep -F "tables/e_anime_schema" /tmp/indexed-search.txt
+grep -F "guides/e_lifetime_errors" /tmp/indexed-search.txt
+
+# Docsets without a full-text index are still searched, file by file.
+rm "$DEDOC_HOME/docsets/docset-3~1/fulltext_index.json"
+wrapped_dedoc ss docset-3~1 -pi connection 2>&1 | grep "does not have a full-text index"
+search_precisely 2> /dev/null | diff -u /tmp/indexed-search.txt -
+wrapped_dedoc index docset-3~1
+test -f "$DEDOC_HOME/docsets/docset-3~1/fulltext_index.json"
+wrapped_dedoc ix --all | grep "Indexing has successfully finished."
+! wrapped_dedoc index docset-999
+
 # Test if dedoc creates search cache.
 wrapped_dedoc ss docset-3~1 -o 7
 wrapped_cat "$DEDOC_HOME/search_cache.json" | diff_stdin_to_text \
@@ -67,7 +116,7 @@ wrapped_cat "$DEDOC_HOME/search_cache.json" | diff_stdin_to_text \
 ```'
 wrapped_cat "$DEDOC_HOME/search_cache_options.json" | diff_stdin_to_text \
 '```
-{"query":"","docset":"docset-3~1","options":{"case_insensitive":false,"precise":false,"whole":false}}
+{"query":"","docset":"docset-3~1","options":{"case_insensitive":false,"precise":false,"whole":false,"fuzzy":false}}
 ```'
 
 # Open some pages.
@@ -139,4 +188,19 @@ Column       │Type    │Description
 * \`CREATE INDEX idx_tsundere_rage ON waifus (anger_level) WHERE dere_type =
   'tsundere'\`"
 
+# Search several docsets at once. Results are numbered across docsets.
+wrapped_dedoc dl docset-1
+wrapped_dedoc ss docset-3~1,docset-1 -z type | diff_stdin_to_text \
+'Searching for `type`...
+Exact matches in 2 docsets:
+   1  docset-1    type-1/1
+   2  docset-3~1  concepts/null_problems
+   3  docset-3~1  concepts/compile_time'
+
+test "$(wrapped_dedoc ss --all -z type --porcelain | head -n 1)" = "docset-1 type-1/1"
+wrapped_dedoc ss --all -z type -o 1 | diff_stdin_to_text "# test"
+wrapped_dedoc ss docset-1,docset-3~1 e_mom -o 1 | head -n 1 | diff_stdin_to_text \
+"# ERROR: Mom Yelling (╬ Ò﹏Ó)"
+! wrapped_dedoc ss docset-1,docset-999 type
+
 wrapped_dedoc rm --purge-all
@@ -22,4 +22,25 @@ test -e "/root/.dedoc/rendered/docset-3~1/errors/e_mom_yelling.md"
 wrapped_dedoc render --all -d /root/rendered2/all
 test -e "/root/rendered2/all/docset-3~1/errors/e_mom_yelling.md"
 
+# Markdown keeps links between pages.
+wrapped_dedoc render docset-3~1 --markdown -d /root/rendered2/markdown
+grep -F "[the workarounds](../functions/e_borrow_checker.md#workarounds)" \
+"/root/rendered2/markdown/guides/links.md"
+test -e "/root/rendered2/markdown/functions/e_borrow_checker.md"
+
+# Man pages go into one directory, which works with `$MANPATH`.
+wrapped_dedoc render docset-3~1 --man -d /root/rendered2/man
+MAN_PAGE="/root/rendered2/man/man3/docset-3~1.guides.e_lifetime_errors.3"
+grep -F '.TH "docset-3~1.guides.e_lifetime_errors" 3' "$MAN_PAGE"
+grep -F '.SH NAME' "$MAN_PAGE"
+wrapped_dedoc open --man docset-3~1 guides/e_lifetime_errors | diff -u "$MAN_PAGE" -
+
+# A fragment keeps the header of the page.
+wrapped_dedoc open --man docset-3~1 'tables/e_anime_schema#index-recommendations' > \
+/root/rendered2/fragment.3
+grep -F '.SH NAME' /root/rendered2/fragment.3
+grep -F '.SH Recommended Indexes' /root/rendered2/fragment.3
+! grep -F '.TS' /root/rendered2/fragment.3
+! wrapped_dedoc open --man --json docset-3~1 guides/e_lifetime_errors
+
 wrapped_dedoc rm --purge-all
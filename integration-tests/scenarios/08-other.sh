@@ -13,11 +13,19 @@ wrapped_dedoc -c
 wrapped_dedoc --help
 wrapped_dedoc ft --help
 wrapped_dedoc ls --help
+wrapped_dedoc if --help
+wrapped_dedoc od --help
 wrapped_dedoc rm --help
+wrapped_dedoc ex --help
+wrapped_dedoc im --help
+wrapped_dedoc bd --help
 wrapped_dedoc dl --help
 wrapped_dedoc ss --help
 wrapped_dedoc op --help
+wrapped_dedoc br --help
 wrapped_dedoc rr --help
+wrapped_dedoc cf --help
+wrapped_dedoc completions --help
 
 wrapped_dedoc --color=off
 wrapped_dedoc --color=auto
@@ -28,6 +36,58 @@ wrapped_dedoc --color=auto
 ! wrapped_dedoc --color=2
 ! wrapped_dedoc --aaaa
 
+# Completion scripts, and candidates that they get from `complete`.
+wrapped_dedoc completions bash | grep -F 'complete -o default -F _dedoc dedoc'
+wrapped_dedoc completions zsh | grep -F 'compdef _dedoc dedoc'
+wrapped_dedoc completions fish | grep -F "complete -c dedoc -a '(__dedoc_complete)'"
+! wrapped_dedoc completions tcsh
+
+wrapped_dedoc dl docset-1
+test "$(wrapped_dedoc complete --color off ren)" = 'render'
+test "$(wrapped_dedoc complete dl docset-3)" = "$(printf 'docset-3~1\ndocset-3~2')"
+test "$(wrapped_dedoc complete op docset-1 type-1/)" = 'type-1/1'
+test "$(wrapped_dedoc complete ss docset-3~1,docset-)" = 'docset-3~1,docset-1'
+test "$(wrapped_dedoc complete op --html '')" = ''
+wrapped_dedoc rm docset-1
+
+# Language server, talking JSON-RPC with `Content-Length` headers.
+lsp_message() { printf 'Content-Length: %s\r\n\r\n%s' "${#1}" "$1"; }
+lsp_session() {
+  lsp_message '{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}'
+  lsp_message '{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///a","languageId":"lang","version":1,"text":"x = 1"}}}'
+  lsp_message '{"jsonrpc":"2.0","id":2,"method":"textDocument/hover","params":{"textDocument":{"uri":"file:///a"},"position":{"line":0,"character":4}}}'
+  lsp_message '{"jsonrpc":"2.0","id":3,"method":"workspace/symbol","params":{"query":"1"}}'
+  lsp_message '{"jsonrpc":"2.0","id":4,"method":"shutdown"}'
+  lsp_message '{"jsonrpc":"2.0","method":"exit"}'
+}
+
+wrapped_dedoc dl docset-1
+lsp_session | wrapped_dedoc lsp lang:docset-1 | grep -F '"hoverProvider":true'
+lsp_session | wrapped_dedoc lsp lang:docset-1 | grep -F '# test'
+lsp_session | wrapped_dedoc lsp lang:docset-1 | grep -F '"containerName":"docset-1"'
+! lsp_session | wrapped_dedoc lsp | grep -F '# test'
+! wrapped_dedoc lsp docset-1
+
+# Broken messages get an error, and the server keeps going.
+lsp_broken_session() {
+  printf 'Content-Type: text/plain\r\n\r\n'
+  lsp_message '{"jsonrpc":"2.0","id":1,'
+  lsp_session
+}
+lsp_broken_session | wrapped_dedoc lsp lang:docset-1 | grep -F '"code":-32700' | \
+grep -F 'without a valid `Content-Length`'
+lsp_broken_session | wrapped_dedoc lsp lang:docset-1 | grep -F 'Could not parse a message'
+lsp_broken_session | wrapped_dedoc lsp lang:docset-1 | grep -F '# test'
+
+# Paths of pages are percent-encoded in URIs.
+SPACED_HOME="$(mktemp -d)/dedoc home"
+mkdir "$SPACED_HOME"
+DEDOC_HOME="$SPACED_HOME" wrapped_dedoc dl --from-dir ./data docset-1
+lsp_session | DEDOC_HOME="$SPACED_HOME" wrapped_dedoc lsp lang:docset-1 | \
+grep -F '/dedoc%20home/docsets/docset-1/type-1/1.html"'
+rm -r "$(dirname "$SPACED_HOME")"
+wrapped_dedoc rm docset-1
+
 # Program directory should be created if DEDOC_HOME is not set.
 rm -rf "$DEDOC_HOME"
 DEDOC_HOME_BAK="$DEDOC_HOME"
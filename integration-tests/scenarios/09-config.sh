@@ -0,0 +1,81 @@
+#!/bin/sh
+
+# See if dedoc can read and change its config.
+
+set -eu
+. "$(dirname "$0")"/../scenario-utils.sh
+
+wrapped_dedoc dl docset-1 docset-3~1
+
+# Nothing is configured yet.
+wrapped_dedoc config list | diff_stdin_to_text ""
+! wrapped_dedoc config get list.all
+
+# Set a few keys.
+wrapped_dedoc config set list.all true
+wrapped_dedoc config set render.output_dir "/tmp/dedoc-rendered"
+wrapped_dedoc config list | diff_stdin_to_text \
+'list.all = true
+render.output_dir = "/tmp/dedoc-rendered"'
+wrapped_dedoc config get list.all | diff_stdin_to_text "true"
+
+# Config values act as default flags.
+wrapped_dedoc ls | \
+diff_stdin_to_text "docset-1 [downloaded], docset-2, docset-3~1 [downloaded], docset-3~2"
+wrapped_dedoc rr docset-1 | grep "/tmp/dedoc-rendered/docset-1"
+test -f "/tmp/dedoc-rendered/docset-1/type-1/1.md"
+rm -r "/tmp/dedoc-rendered"
+
+# Boolean keys are turned off with `--no-<flag>`.
+wrapped_dedoc ls --no-all | diff_stdin_to_text "docset-1 [downloaded], docset-2"
+wrapped_dedoc config set list.no_labels true
+wrapped_dedoc ls --no-all | diff_stdin_to_text "docset-1, docset-2"
+wrapped_dedoc ls --no-all --labels | grep -F 'docset-1 [downloaded]'
+wrapped_dedoc config unset list.no_labels
+
+# Quotes in values are escaped, so the config can be read again.
+wrapped_dedoc config set pager 'less -P "page %f"'
+test "$(wrapped_dedoc config get pager)" = 'less -P "page %f"'
+wrapped_dedoc config unset pager
+
+# Invalid keys and values are rejected.
+! wrapped_dedoc config set whatever 1
+! wrapped_dedoc config set list.all maybe
+! wrapped_dedoc config set open.columns 5
+
+# Broken config is reported, and can be fixed with `unset`.
+echo 'open.columns = 5' >> "$DEDOC_HOME/config.toml"
+! wrapped_dedoc open docset-1 type-1/1
+wrapped_dedoc config unset open.columns
+wrapped_dedoc open docset-1 type-1/1 | diff_stdin_to_text "# test"
+
+wrapped_dedoc config unset list.all
+wrapped_dedoc config unset render.output_dir
+wrapped_dedoc config list | diff_stdin_to_text ""
+
+# Old `docs.json` is refreshed with `--auto-fetch` or `auto_fetch`.
+UPSTREAM="$(mktemp -d)"
+sed 's/"slug": "docset-2"/"slug": "docset-2~2"/' ./data/docs.json > "$UPSTREAM/docs.json"
+touch -d '2 years ago' "$DEDOC_HOME/docs.json"
+! wrapped_dedoc ls --all | grep -F 'docset-2~2'
+DEDOC_DOCS_JSON_URL="file://$UPSTREAM/docs.json" wrapped_dedoc --auto-fetch ls --all | \
+grep -F 'docset-2~2'
+
+# Unreachable `docs.json` only warns, and the old one is used.
+touch -d '2 years ago' "$DEDOC_HOME/docs.json"
+DEDOC_DOCS_JSON_URL="file://$UPSTREAM/missing.json" wrapped_dedoc --auto-fetch ls 2>&1 | \
+grep -F 'Could not refresh `docs.json`'
+
+wrapped_dedoc config set auto_fetch true
+wrapped_dedoc --no-auto-fetch ls --all | grep -F 'docset-2~2'
+wrapped_dedoc ls --all | grep -F 'docset-2,'
+wrapped_dedoc config unset auto_fetch
+
+# An invalid `auto_fetch` is an error, but the config can still be fixed.
+printf 'auto_fetch = "maybe"\n' >> "$DEDOC_HOME/config.toml"
+! wrapped_dedoc ls
+wrapped_dedoc config unset auto_fetch
+wrapped_dedoc ls
+rm -r "$UPSTREAM"
+
+wrapped_dedoc rm --purge-all
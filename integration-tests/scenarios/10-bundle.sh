@@ -0,0 +1,44 @@
+#!/bin/sh
+
+# See if docsets can be moved to a computer without internet access.
+
+set -eu
+. "$(dirname "$0")"/../scenario-utils.sh
+
+BUNDLE_DIR="$(mktemp -d)"
+OFFLINE_HOME="$(mktemp -d)"
+
+wrapped_dedoc dl docset-1 docset-3~1
+wrapped_dedoc export docset-1 docset-3~1 -o "$BUNDLE_DIR/bundle.tar"
+
+# Nothing but the bundle is needed, not even `docs.json`.
+DEDOC_HOME="$OFFLINE_HOME" wrapped_dedoc import "$BUNDLE_DIR/bundle.tar" \
+  | grep "2 items were successfully imported."
+test "$(DEDOC_HOME="$OFFLINE_HOME" wrapped_dedoc ls -l)" = \
+'docset-1 [downloaded], docset-3~1 [downloaded]'
+DEDOC_HOME="$OFFLINE_HOME" wrapped_dedoc open docset-3~1 functions/e_borrow_checker \
+  | grep "The Borrow Checker"
+
+# Imported docsets are as recent as exported ones.
+! DEDOC_HOME="$OFFLINE_HOME" wrapped_dedoc dl -u
+
+# Importing again keeps the replaced version.
+DEDOC_HOME="$OFFLINE_HOME" wrapped_dedoc import "$BUNDLE_DIR/bundle.tar"
+DEDOC_HOME="$OFFLINE_HOME" wrapped_dedoc dl --rollback docset-1
+
+# A bundle that lists docsets without their files doesn't replace them.
+tar -xf "$BUNDLE_DIR/bundle.tar" -C "$BUNDLE_DIR" dedoc-bundle.json
+tar -cf "$BUNDLE_DIR/empty.tar" -C "$BUNDLE_DIR" dedoc-bundle.json
+DEDOC_HOME="$OFFLINE_HOME" wrapped_dedoc import "$BUNDLE_DIR/empty.tar" 2>&1 \
+  | grep "does not contain the index and pages of \`docset-1\`."
+test ! -e "$OFFLINE_HOME/staging/docset-1"
+DEDOC_HOME="$OFFLINE_HOME" wrapped_dedoc open docset-3~1 functions/e_borrow_checker \
+  | grep "The Borrow Checker"
+
+# Only downloaded docsets can be exported, and only bundles imported.
+! wrapped_dedoc export whatever -o "$BUNDLE_DIR/whatever.tar"
+! wrapped_dedoc import ./data/docs.json
+! wrapped_dedoc import "$BUNDLE_DIR/nonexistent.tar"
+
+rm -r "$BUNDLE_DIR" "$OFFLINE_HOME"
+wrapped_dedoc rm --purge-all
@@ -0,0 +1,37 @@
+#!/bin/sh
+
+# See if Dash docsets work like the ones from DevDocs.
+
+set -eu
+. "$(dirname "$0")"/../scenario-utils.sh
+
+DASH_HOME="$(mktemp -d)"
+DASH_DOCSET="./data/Dash Example.docset"
+
+# Copied docsets don't need `docs.json` either.
+DEDOC_HOME="$DASH_HOME" wrapped_dedoc import "$DASH_DOCSET" \
+  | grep "Import has successfully finished."
+test "$(DEDOC_HOME="$DASH_HOME" wrapped_dedoc ls -l)" = 'dash_example [downloaded]'
+
+# Entries come from the SQLite index, and links to websites are skipped.
+test "$(DEDOC_HOME="$DASH_HOME" wrapped_dedoc search --json dash_example syrup)" = \
+'{"query":"syrup","exact":[{"number":1,"docset":"dash_example","item":"guide/getting started","fragment":"syrup","highlights":[]}],"vague":[]}'
+! DEDOC_HOME="$DASH_HOME" wrapped_dedoc search dash_example website | grep "kapeli"
+
+DEDOC_HOME="$DASH_HOME" wrapped_dedoc open dash_example "guide/getting started" \
+  | grep "Pancakes are made of flour, milk and eggs."
+DEDOC_HOME="$DASH_HOME" wrapped_dedoc search dash_example -p -i pancakes \
+  | grep "guide/getting started"
+DEDOC_HOME="$DASH_HOME" wrapped_dedoc render dash_example -d "$DASH_HOME/rendered"
+grep "Maple syrup goes on top." "$DASH_HOME/rendered/guide/getting started.md"
+
+# Linked docsets are read from where they are.
+DEDOC_HOME="$DASH_HOME" wrapped_dedoc import --link "$DASH_DOCSET"
+test ! -e "$DASH_HOME/docsets/dash_example/Contents"
+DEDOC_HOME="$DASH_HOME" wrapped_dedoc open dash_example index | grep "Dash Example"
+! DEDOC_HOME="$DASH_HOME" wrapped_dedoc export dash_example -o "$DASH_HOME/dash.tar"
+
+DEDOC_HOME="$DASH_HOME" wrapped_dedoc rm dash_example
+test -e "$DASH_DOCSET/Contents/Resources/docSet.dsidx"
+
+rm -r "$DASH_HOME"
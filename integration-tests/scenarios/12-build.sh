@@ -0,0 +1,35 @@
+#!/bin/sh
+
+# See if HTML pages can be made into a docset.
+
+set -eu
+. "$(dirname "$0")"/../scenario-utils.sh
+
+BUILD_HOME="$(mktemp -d)"
+
+DEDOC_HOME="$BUILD_HOME" wrapped_dedoc build waffles ./data/html-pages \
+  | grep "Build has successfully finished."
+test "$(DEDOC_HOME="$BUILD_HOME" wrapped_dedoc ls -l)" = 'waffles [downloaded]'
+
+# Pages are found by their anchors, and are indexed for `--precise`.
+DEDOC_HOME="$BUILD_HOME" wrapped_dedoc search waffles preheat \
+  | grep -F '1  api/iron, #method.preheat'
+DEDOC_HOME="$BUILD_HOME" wrapped_dedoc search waffles -p crispy 2>&1 \
+  | grep -F 'api/iron'
+! DEDOC_HOME="$BUILD_HOME" wrapped_dedoc search waffles -p crispy 2>&1 \
+  | grep "does not have a full-text index"
+DEDOC_HOME="$BUILD_HOME" wrapped_dedoc open waffles "api/iron#method.bake" \
+  | grep "Returns a crispy waffle after"
+test ! -e "$BUILD_HOME/docsets/waffles/_static"
+
+# Existing docsets are only replaced with `--force`.
+! DEDOC_HOME="$BUILD_HOME" wrapped_dedoc build waffles ./data/html-pages
+DEDOC_HOME="$BUILD_HOME" wrapped_dedoc build -f waffles ./data/html-pages/api
+DEDOC_HOME="$BUILD_HOME" wrapped_dedoc open waffles iron | grep "Bakes waffles out of batter."
+DEDOC_HOME="$BUILD_HOME" wrapped_dedoc dl --rollback waffles
+DEDOC_HOME="$BUILD_HOME" wrapped_dedoc open waffles index | grep "Waffle API"
+
+! DEDOC_HOME="$BUILD_HOME" wrapped_dedoc build nothing ./data/html-pages/_static
+! DEDOC_HOME="$BUILD_HOME" wrapped_dedoc build ../escape ./data/html-pages
+
+rm -r "$BUILD_HOME"
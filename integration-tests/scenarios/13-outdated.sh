@@ -0,0 +1,33 @@
+#!/bin/sh
+
+# See if local docsets are compared with docs.json.
+
+set -eu
+. "$(dirname "$0")"/../scenario-utils.sh
+
+wrapped_dedoc dl docset-1 docset-3~1
+
+# Everything is up to date right after downloading.
+wrapped_dedoc outdated | grep -F 'docset-3~1  1970-01-01  1970-01-01'
+
+# A newer `docset-3~1`, and `docset-1` that is now `docset-1~2`.
+UPSTREAM="$(mktemp -d)"
+sed -e 's/"slug": "docset-1"/"slug": "docset-1~2"/' \
+    -e '/"slug": "docset-3~1"/,/"mtime"/s/"mtime": 1/"mtime": 1700000000/' \
+    ./data/docs.json > "$UPSTREAM/docs.json"
+wrapped_dedoc ft -f --mirror "file://$UPSTREAM/docs.json"
+
+! wrapped_dedoc outdated > "$UPSTREAM/outdated.txt" 2>&1
+grep -F 'docset-1    1970-01-01  possibly renamed to `docset-1~2`' "$UPSTREAM/outdated.txt"
+grep -F 'docset-3~1  1970-01-01  2023-11-14' "$UPSTREAM/outdated.txt"
+grep -F '1 docset can be updated.' "$UPSTREAM/outdated.txt"
+
+# Renamed docsets alone don't fail.
+wrapped_dedoc outdated docset-1
+test "$(wrapped_dedoc outdated --json docset-1 docset-3~1 2> /dev/null || true)" = \
+'[{"docset":"docset-1","status":"renamed","local_mtime":1,"mtime":null,"candidates":["docset-1~2"]},{"docset":"docset-3~1","status":"outdated","local_mtime":1,"mtime":1700000000}]'
+! wrapped_dedoc outdated docset-2
+
+rm -r "$UPSTREAM"
+wrapped_dedoc ft -f
+wrapped_dedoc rm --purge-all
@@ -0,0 +1,60 @@
+#!/bin/sh
+
+# See if docsets can be read in a browser.
+
+set -eu
+. "$(dirname "$0")"/../scenario-utils.sh
+
+wrapped_dedoc dl docset-1 docset-3~1
+
+PORT=38917
+URL="http://127.0.0.1:$PORT"
+
+wrapped_dedoc serve --port "$PORT" &
+trap 'pkill -f "serve --port $PORT" || true' EXIT
+
+# Wait until the server is up.
+for _ in 1 2 3 4 5 6 7 8 9 10; do
+  curl -s "$URL/" > /dev/null && break
+  sleep 0.5
+done
+
+# Index of docsets and pages of a docset.
+curl -sf "$URL/" | grep -F '<a href="/docs/docset-3~1/">docset-3~1</a>'
+curl -sf "$URL/docs/docset-1/" | grep -F '<a href="/docs/docset-1/type-1/1">1</a>'
+
+# Pages are put into a document, and links between them still work.
+curl -sf "$URL/docs/docset-1/type-1/1" | grep -F '<h1>test</h1>'
+curl -sf "$URL/docs/docset-3~1/guides/links" | \
+grep -F "href='../functions/e_borrow_checker#workarounds'"
+curl -sf "$URL/docs/docset-3~1/functions/e_borrow_checker" > /dev/null
+
+# JSON API returns the same as `search --json`.
+test "$(curl -sf "$URL/api/search?docset=docset-3~1&query=e&fuzzy=on")" = \
+     "$(wrapped_dedoc search docset-3~1 e -z --json)"
+test "$(curl -sf "$URL/api/search?all=on&query=borrow&precise=on")" = \
+     "$(wrapped_dedoc search --all borrow -p --json)"
+
+# Search page links to results.
+curl -sf "$URL/search?docset=docset-3~1&query=borrow" | \
+grep -F '<a href="/docs/docset-3~1/functions/e_borrow_checker">'
+
+# Errors.
+test "$(curl -s -o /dev/null -w '%{http_code}' "$URL/api/search?docset=docset-2&query=a")" = 400
+test "$(curl -s -o /dev/null -w '%{http_code}' "$URL/docs/docset-2/")" = 404
+test "$(curl -s -o /dev/null -w '%{http_code}' "$URL/docs/docset-1/type-1/2")" = 404
+test "$(curl -s -o /dev/null -w '%{http_code}' --path-as-is "$URL/docs/docset-1/../../docs.json")" = 404
+
+# Absolute paths, also percent-encoded ones, don't lead out of docsets.
+test "$(curl -s -o /dev/null -w '%{http_code}' --path-as-is "$URL/docs/docset-1//etc/hostname")" = 404
+test "$(curl -s -o /dev/null -w '%{http_code}' "$URL/docs/docset-1/%2Fetc%2Fpasswd")" = 404
+test "$(curl -s -o /dev/null -w '%{http_code}' "$URL/docs/docset-1/%2E%2E/%2E%2E/docs.json")" = 404
+! curl -s --path-as-is "$URL/docs/docset-1//etc/passwd" | grep -F "root:"
+
+# Only pages and their assets are served, not other files of docsets.
+test "$(curl -s -o /dev/null -w '%{http_code}' "$URL/docs/docset-3~1/index.json")" = 404
+test "$(curl -s -o /dev/null -w '%{http_code}' "$URL/docs/docset-3~1/fulltext_index.json")" = 404
+curl -sf "$URL/docs/docset-1/type-1/1.html" | grep -F '<h1>test</h1>'
+
+# The port is taken.
+! wrapped_dedoc serve --port "$PORT"
@@ -0,0 +1,824 @@
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::PathBuf;
+
+use html2text::render::RichAnnotation;
+use html2text::render::TaggedLineElement::{FragmentStart, Str};
+
+use toiletcli::common::{is_stdin_a_tty, is_stdout_a_tty};
+use toiletcli::escapes::{Cursor, Erase};
+use toiletcli::flags;
+use toiletcli::flags::*;
+
+use crate::common::{
+  deserialize_docs_json, get_docset_pages_path, get_flag_error, get_tag_style, is_docs_json_exists,
+  is_docset_downloaded, make_sure_docset_is_in_docs, resolve_docset_link,
+  split_to_item_and_fragment, DocsetLink, ResultS, DOC_PAGE_EXTENSION,
+};
+use crate::common::{BOLD, GRAY, GRAYER, GREEN, PROGRAM_NAME, RESET, YELLOW};
+use crate::search::{deserialize_index_json, score_fuzzy_match, IndexEntry};
+
+fn show_browse_help() -> ResultS
+{
+  println!(
+           "\
+{GREEN}USAGE{RESET}
+    {BOLD}{PROGRAM_NAME} browse{RESET} [-OPTIONS] <docset> [page]
+    Interactively search and read pages of a docset.
+
+{GREEN}OPTIONS{RESET}
+        --help                      Display help message.
+
+{GREEN}KEYS{RESET}
+  In the list:
+    <characters>                    Filter pages.
+    Up, Down, Ctrl-P, Ctrl-N        Select a page.
+    Enter                           Open selected page.
+    Tab                             Switch to the page.
+    Esc, Ctrl-C                     Quit.
+
+  In the page:
+    j, k, Up, Down                  Scroll by a line.
+    Space, PageDown, PageUp         Scroll by a screen.
+    g, G, Home, End                 Go to the top or the bottom.
+    Tab, Shift-Tab                  Select the next or the previous link.
+    Enter                           Follow selected link.
+    h, Left, Backspace              Go back.
+    l, Right                        Go forward.
+    /                               Switch to the list.
+    q, Ctrl-C                       Quit."
+  );
+  Ok(())
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Key
+{
+  Char(char),
+  Up,
+  Down,
+  Left,
+  Right,
+  PageUp,
+  PageDown,
+  Home,
+  End,
+  Enter,
+  Tab,
+  BackTab,
+  Backspace,
+  Escape,
+  CtrlC,
+}
+
+// Terminal sends a whole escape sequence at once, so a lone escape byte at the
+// end of the buffer is an actual Escape.
+fn parse_keys(bytes: &[u8]) -> Vec<Key>
+{
+  let mut keys = vec![];
+  let mut i = 0;
+
+  while i < bytes.len() {
+    let byte = bytes[i];
+    i += 1;
+
+    let key = match byte {
+      0x1b if i < bytes.len() && (bytes[i] == b'[' || bytes[i] == b'O') => {
+        i += 1;
+        let params_start = i;
+        while i < bytes.len() && !(0x40..=0x7e).contains(&bytes[i]) {
+          i += 1;
+        }
+        if i >= bytes.len() {
+          break;
+        }
+        let params = &bytes[params_start..i];
+        let final_byte = bytes[i];
+        i += 1;
+
+        match (final_byte, params) {
+          (b'A', _) => Key::Up,
+          (b'B', _) => Key::Down,
+          (b'C', _) => Key::Right,
+          (b'D', _) => Key::Left,
+          (b'H', _) => Key::Home,
+          (b'F', _) => Key::End,
+          (b'Z', _) => Key::BackTab,
+          (b'~', b"1" | b"7") => Key::Home,
+          (b'~', b"4" | b"8") => Key::End,
+          (b'~', b"5") => Key::PageUp,
+          (b'~', b"6") => Key::PageDown,
+          _ => continue,
+        }
+      }
+      0x1b => Key::Escape,
+      0x03 => Key::CtrlC,
+      0x0e => Key::Down,
+      0x10 => Key::Up,
+      b'\t' => Key::Tab,
+      b'\r' | b'\n' => Key::Enter,
+      0x7f | 0x08 => Key::Backspace,
+      b if b < 0x20 => continue,
+      b => {
+        let length = match b {
+          0xf0.. => 4,
+          0xe0.. => 3,
+          0xc0.. => 2,
+          _ => 1,
+        };
+        let end = std::cmp::min(i - 1 + length, bytes.len());
+        let c = std::str::from_utf8(&bytes[i - 1..end]).ok().and_then(|s| s.chars().next());
+        i = end;
+        match c {
+          Some(c) => Key::Char(c),
+          None => continue,
+        }
+      }
+    };
+
+    keys.push(key);
+  }
+
+  keys
+}
+
+// Puts the terminal into raw mode and the alternate screen, and restores
+// everything when dropped, or on a panic, since release builds abort on panics
+// without dropping anything.
+struct RawTerminal
+{
+  original: libc::termios,
+}
+
+impl RawTerminal
+{
+  fn enable() -> Result<Self, String>
+  {
+    let original = unsafe {
+      let mut termios: libc::termios = std::mem::zeroed();
+      if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) != 0 {
+        return Err(format!("Could not get terminal attributes: {}",
+                           std::io::Error::last_os_error()));
+      }
+      let original = termios;
+
+      libc::cfmakeraw(&mut termios);
+      // Wait for input for at most 100ms, to notice terminal resizes.
+      termios.c_cc[libc::VMIN] = 0;
+      termios.c_cc[libc::VTIME] = 1;
+
+      if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &termios) != 0 {
+        return Err(format!("Could not set terminal attributes: {}",
+                           std::io::Error::last_os_error()));
+      }
+      original
+    };
+
+    print!("\x1b[?1049h{}", Cursor::Hide);
+    let _ = std::io::stdout().flush();
+
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+                           restore_terminal(&original);
+                           default_hook(info);
+                         }));
+
+    Ok(RawTerminal { original })
+  }
+
+  fn read_keys(&self) -> Result<Vec<Key>, String>
+  {
+    let mut buffer = [0u8; 64];
+    let size = unsafe {
+      libc::read(libc::STDIN_FILENO, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len())
+    };
+
+    if size < 0 {
+      let err = std::io::Error::last_os_error();
+      if err.kind() == std::io::ErrorKind::Interrupted {
+        return Ok(vec![]);
+      }
+      return Err(format!("Could not read from terminal: {err}"));
+    }
+
+    Ok(parse_keys(&buffer[..size as usize]))
+  }
+}
+
+fn restore_terminal(original: &libc::termios)
+{
+  print!("{RESET}{}\x1b[?1049l", Cursor::Show);
+  let _ = std::io::stdout().flush();
+  unsafe {
+    libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, original);
+  }
+}
+
+impl Drop for RawTerminal
+{
+  fn drop(&mut self)
+  {
+    restore_terminal(&self.original);
+    // The hook of `enable()` is not needed anymore. It can't be changed while
+    // unwinding.
+    if !std::thread::panicking() {
+      let _ = std::panic::take_hook();
+    }
+  }
+}
+
+struct Segment
+{
+  text: String,
+  style: String,
+  link: Option<usize>,
+}
+
+struct Link
+{
+  href: String,
+  line: usize,
+}
+
+struct Page
+{
+  item: String,
+  lines: Vec<Vec<Segment>>,
+  links: Vec<Link>,
+  fragments: Vec<(String, usize)>,
+}
+
+fn render_page(docset_name: &str, item: &str, width: usize) -> Result<Page, String>
+{
+  let pages_path = get_docset_pages_path(docset_name)?;
+  let path = PathBuf::from(pages_path.join(item).display().to_string() + "." + DOC_PAGE_EXTENSION);
+
+  let file =
+    File::open(&path).map_err(|_| format!("No page matching `{item}` in `{docset_name}`."))?;
+  let reader = BufReader::new(file);
+
+  let tagged_lines =
+    html2text::config::rich().do_decorate()
+                             .no_link_wrapping()
+                             .lines_from_read(reader, width)
+                             .map_err(|err| {
+                               format!("Failed to parse `{}`: {err}", path.display())
+                             })?;
+
+  let mut page = Page { item: item.to_owned(), lines: vec![], links: vec![], fragments: vec![] };
+
+  for (line_number, tagged_line) in tagged_lines.iter().enumerate() {
+    let mut segments = vec![];
+    // Consecutive strings with the same target are a single link.
+    let mut previous_href: Option<&str> = None;
+
+    for element in tagged_line.iter() {
+      match element {
+        FragmentStart(fragment) => page.fragments.push((fragment.to_lowercase(), line_number)),
+        Str(tagged_string) => {
+          let href = tagged_string.tag.iter().find_map(|annotation| match annotation {
+                                               RichAnnotation::Link(href) => Some(href.as_str()),
+                                               _ => None,
+                                             });
+
+          let link = match href {
+            Some(href) if previous_href == Some(href) => Some(page.links.len() - 1),
+            Some(href) => {
+              page.links.push(Link { href: href.to_owned(), line: line_number });
+              Some(page.links.len() - 1)
+            }
+            None => None,
+          };
+          previous_href = href;
+
+          segments.push(Segment { text: tagged_string.s.clone(),
+                                  style: get_tag_style(&tagged_string.tag),
+                                  link });
+        }
+      }
+    }
+
+    page.lines.push(segments);
+  }
+
+  Ok(page)
+}
+
+#[derive(Clone)]
+struct Location
+{
+  item: String,
+  fragment: Option<String>,
+  scroll: usize,
+}
+
+#[derive(PartialEq)]
+enum Focus
+{
+  List,
+  Page,
+}
+
+struct Browser
+{
+  docset: String,
+  entries: Vec<IndexEntry>,
+  filter: String,
+  // Indices into `entries` that match the filter.
+  filtered: Vec<usize>,
+  selected_entry: usize,
+  list_offset: usize,
+  page: Option<Page>,
+  scroll: usize,
+  selected_link: Option<usize>,
+  history: Vec<Location>,
+  history_position: usize,
+  focus: Focus,
+  message: Option<String>,
+  width: usize,
+  height: usize,
+}
+
+const MIN_LIST_WIDTH: usize = 20;
+const MAX_LIST_WIDTH: usize = 48;
+
+impl Browser
+{
+  fn list_width(&self) -> usize
+  {
+    (self.width / 3).clamp(MIN_LIST_WIDTH, MAX_LIST_WIDTH)
+  }
+
+  fn page_width(&self) -> usize
+  {
+    // List, separator and a space after it.
+    std::cmp::max(self.width.saturating_sub(self.list_width() + 2), 10)
+  }
+
+  // Everything except the header and the status line.
+  fn body_height(&self) -> usize
+  {
+    self.height.saturating_sub(2)
+  }
+
+  fn update_filter(&mut self)
+  {
+    let query = self.filter.chars().filter(|c| !c.is_whitespace()).collect::<Vec<char>>();
+
+    if query.is_empty() {
+      self.filtered = (0..self.entries.len()).collect();
+    } else {
+      let mut scored = self.entries
+                           .iter()
+                           .enumerate()
+                           .filter_map(|(i, entry)| {
+                             let name = entry.name.chars().collect::<Vec<char>>();
+                             score_fuzzy_match(&query, &name).map(|(score, _)| (score, i))
+                           })
+                           .collect::<Vec<(i64, usize)>>();
+      scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+      self.filtered = scored.into_iter().map(|(_, i)| i).collect();
+    }
+
+    self.selected_entry = 0;
+    self.list_offset = 0;
+  }
+
+  fn move_selection(&mut self, delta: isize)
+  {
+    if self.filtered.is_empty() {
+      return;
+    }
+    let last = self.filtered.len() - 1;
+    self.selected_entry = self.selected_entry.saturating_add_signed(delta).min(last);
+
+    let height = self.body_height();
+    if self.selected_entry < self.list_offset {
+      self.list_offset = self.selected_entry;
+    } else if self.selected_entry >= self.list_offset + height {
+      self.list_offset = self.selected_entry + 1 - height;
+    }
+  }
+
+  fn max_scroll(&self) -> usize
+  {
+    self.page.as_ref().map_or(0, |page| page.lines.len().saturating_sub(self.body_height()))
+  }
+
+  fn scroll_by(&mut self, delta: isize)
+  {
+    self.scroll = self.scroll.saturating_add_signed(delta).min(self.max_scroll());
+  }
+
+  // Loads a page without touching the history.
+  fn load(&mut self, location: &Location) -> ResultS
+  {
+    let page = render_page(&self.docset, &location.item, self.page_width())?;
+
+    self.scroll = location.scroll;
+
+    if let Some(fragment) = &location.fragment {
+      let fragment = fragment.to_lowercase();
+      if let Some((_, line)) = page.fragments.iter().find(|(f, _)| *f == fragment) {
+        self.scroll = *line;
+      } else {
+        self.message = Some(format!("No `#{fragment}` in `{}`.", location.item));
+      }
+    }
+
+    self.page = Some(page);
+    self.selected_link = None;
+    self.scroll = self.scroll.min(self.max_scroll());
+
+    Ok(())
+  }
+
+  fn remember_scroll(&mut self)
+  {
+    if let Some(location) = self.history.get_mut(self.history_position) {
+      location.scroll = self.scroll;
+    }
+  }
+
+  fn navigate(&mut self, item: String, fragment: Option<String>)
+  {
+    let location = Location { item, fragment, scroll: 0 };
+
+    if let Err(err) = self.load(&location) {
+      self.message = Some(err);
+      return;
+    }
+
+    self.remember_scroll();
+    if !self.history.is_empty() {
+      self.history.truncate(self.history_position + 1);
+    }
+    self.history.push(location);
+    self.history_position = self.history.len() - 1;
+    self.focus = Focus::Page;
+  }
+
+  fn go_through_history(&mut self, forward: bool)
+  {
+    let target = if forward {
+      self.history_position + 1
+    } else if let Some(position) = self.history_position.checked_sub(1) {
+      position
+    } else {
+      self.message = Some("Nothing to go back to.".to_string());
+      return;
+    };
+
+    if target >= self.history.len() {
+      self.message = Some("Nothing to go forward to.".to_string());
+      return;
+    }
+
+    self.remember_scroll();
+    // Restore the scroll instead of jumping to the fragment again.
+    let location = Location { fragment: None, ..self.history[target].clone() };
+    match self.load(&location) {
+      Ok(()) => self.history_position = target,
+      Err(err) => self.message = Some(err),
+    }
+  }
+
+  fn select_link(&mut self, forward: bool)
+  {
+    let (link_count, first_visible) = if let Some(page) = &self.page {
+      let first_visible = page.links.iter().position(|link| link.line >= self.scroll);
+      (page.links.len(), first_visible)
+    } else {
+      return;
+    };
+
+    if link_count == 0 {
+      self.message = Some("This page has no links.".to_string());
+      return;
+    }
+
+    let is_selected_visible = self.selected_link.is_some_and(|link| self.is_link_visible(link));
+
+    let next = match (self.selected_link, is_selected_visible) {
+      (Some(link), true) if forward => (link + 1) % link_count,
+      (Some(link), true) => (link + link_count - 1) % link_count,
+      _ => first_visible.unwrap_or(0),
+    };
+
+    self.selected_link = Some(next);
+
+    if !self.is_link_visible(next) {
+      let line = self.page.as_ref().map_or(0, |page| page.links[next].line);
+      self.scroll = line.saturating_sub(self.body_height() / 2).min(self.max_scroll());
+    }
+  }
+
+  fn is_link_visible(&self, link: usize) -> bool
+  {
+    self.page.as_ref().is_some_and(|page| {
+                        let line = page.links[link].line;
+                        line >= self.scroll && line < self.scroll + self.body_height()
+                      })
+  }
+
+  fn follow_selected_link(&mut self)
+  {
+    let (current_item, href) = match (&self.page, self.selected_link) {
+      (Some(page), Some(link)) => (page.item.clone(), page.links[link].href.clone()),
+      _ => {
+        self.message = Some("No link is selected. Use Tab to select one.".to_string());
+        return;
+      }
+    };
+
+    match resolve_docset_link(&current_item, &href) {
+      DocsetLink::Page { item, fragment } => self.navigate(item, fragment),
+      DocsetLink::External(url) => self.message = Some(format!("External link: {url}")),
+    }
+  }
+
+  fn open_selected_entry(&mut self)
+  {
+    if let Some(&i) = self.filtered.get(self.selected_entry) {
+      match split_to_item_and_fragment(self.entries[i].path.clone()) {
+        Ok((item, fragment)) => self.navigate(item, fragment),
+        Err(err) => self.message = Some(err),
+      }
+    }
+  }
+
+  // -> Whether to quit.
+  fn handle_key(&mut self, key: Key) -> bool
+  {
+    self.message = None;
+
+    if key == Key::CtrlC {
+      return true;
+    }
+
+    let screen = self.body_height() as isize;
+
+    match self.focus {
+      Focus::List => match key {
+        Key::Escape => return true,
+        Key::Char(c) => {
+          self.filter.push(c);
+          self.update_filter();
+        }
+        Key::Backspace => {
+          self.filter.pop();
+          self.update_filter();
+        }
+        Key::Up => self.move_selection(-1),
+        Key::Down => self.move_selection(1),
+        Key::PageUp => self.move_selection(-screen),
+        Key::PageDown => self.move_selection(screen),
+        Key::Home => self.move_selection(isize::MIN),
+        Key::End => self.move_selection(isize::MAX),
+        Key::Enter => self.open_selected_entry(),
+        Key::Tab | Key::BackTab if self.page.is_some() => self.focus = Focus::Page,
+        _ => {}
+      },
+      Focus::Page => match key {
+        Key::Char('q') => return true,
+        Key::Char('j') | Key::Down => self.scroll_by(1),
+        Key::Char('k') | Key::Up => self.scroll_by(-1),
+        Key::Char(' ') | Key::PageDown => self.scroll_by(screen),
+        Key::PageUp => self.scroll_by(-screen),
+        Key::Char('g') | Key::Home => self.scroll = 0,
+        Key::Char('G') | Key::End => self.scroll = self.max_scroll(),
+        Key::Tab => self.select_link(true),
+        Key::BackTab => self.select_link(false),
+        Key::Enter => self.follow_selected_link(),
+        Key::Char('h') | Key::Left | Key::Backspace => self.go_through_history(false),
+        Key::Char('l') | Key::Right => self.go_through_history(true),
+        Key::Char('/') | Key::Escape => self.focus = Focus::List,
+        _ => {}
+      },
+    }
+
+    false
+  }
+
+  fn resize(&mut self, width: usize, height: usize)
+  {
+    if width == self.width && height == self.height {
+      return;
+    }
+
+    let is_width_changed = width != self.width;
+    self.width = width;
+    self.height = height;
+
+    // Pages are wrapped to the width, so they have to be rendered again.
+    if is_width_changed {
+      if let Some(location) = self.history.get(self.history_position).cloned() {
+        let location = Location { fragment: None, scroll: self.scroll, ..location };
+        if let Err(err) = self.load(&location) {
+          self.message = Some(err);
+        }
+      }
+    }
+
+    self.scroll = self.scroll.min(self.max_scroll());
+    self.move_selection(0);
+  }
+
+  fn draw(&self) -> String
+  {
+    let list_width = self.list_width();
+    let mut screen = String::new();
+
+    // Header.
+    let header_style = if self.focus == Focus::List { BOLD.to_string() } else { String::new() };
+    let filter = truncate(&format!("> {}", self.filter), list_width);
+    let title = self.history.get(self.history_position).map_or(String::new(), |location| {
+                                                         match &location.fragment {
+                                                           Some(f) => {
+                                                             format!("{}#{f}", location.item)
+                                                           }
+                                                           None => location.item.clone(),
+                                                         }
+                                                       });
+    screen +=
+      &format!("{}{header_style}{filter:<list_width$}{RESET}{GRAYER}│{RESET} {BOLD}{}{RESET}{}",
+               Cursor::Goto(1, 1),
+               truncate(&title, self.page_width()),
+               Erase::LineAfter);
+
+    for row in 0..self.body_height() {
+      screen += &Cursor::Goto(row as u32 + 2, 1).to_string();
+
+      // Left side.
+      let entry_index = self.list_offset + row;
+      if let Some(&i) = self.filtered.get(entry_index) {
+        let name = truncate(&self.entries[i].name, list_width);
+        if entry_index == self.selected_entry {
+          let style = if self.focus == Focus::List { "\x1b[7m" } else { "\x1b[4m" };
+          screen += &format!("{style}{name:<list_width$}{RESET}");
+        } else {
+          screen += &format!("{name:<list_width$}");
+        }
+      } else {
+        screen += &" ".repeat(list_width);
+      }
+
+      screen += &format!("{GRAYER}│{RESET} ");
+
+      // Right side.
+      if let Some(segments) = self.page.as_ref().and_then(|page| page.lines.get(self.scroll + row))
+      {
+        for segment in segments {
+          let is_selected = segment.link.is_some() && segment.link == self.selected_link;
+          let selection = if is_selected { "\x1b[7m" } else { "" };
+          screen += &format!("{}{selection}{}{RESET}", segment.style, segment.text);
+        }
+      }
+
+      screen += &Erase::LineAfter.to_string();
+    }
+
+    // Status line.
+    let status = if let Some(message) = &self.message {
+      format!("{YELLOW}{message}{RESET}")
+    } else if let (Some(page), Some(link)) = (&self.page, self.selected_link) {
+      format!("{GRAY}{}{RESET}", page.links[link].href)
+    } else {
+      let position = if self.filtered.is_empty() { 0 } else { self.selected_entry + 1 };
+      format!("{GRAY}{} {position}/{} | Enter: open, Tab: switch, /: filter, h/l: back/forward, \
+               q: quit{RESET}",
+              self.docset,
+              self.filtered.len())
+    };
+    screen += &format!("{}{}{}", Cursor::Goto(self.height as u32, 1), status, Erase::LineAfter);
+
+    screen
+  }
+}
+
+fn truncate(s: &str, width: usize) -> String
+{
+  if s.chars().count() <= width {
+    return s.to_owned();
+  }
+  let mut truncated = s.chars().take(width.saturating_sub(1)).collect::<String>();
+  truncated.push('…');
+  truncated
+}
+
+fn get_terminal_size() -> (usize, usize)
+{
+  if let Some((terminal_size::Width(w), terminal_size::Height(h))) = terminal_size::terminal_size()
+  {
+    (w as usize, h as usize)
+  } else {
+    (80, 24)
+  }
+}
+
+fn browse_docset(docset: &str, page: Option<String>) -> ResultS
+{
+  let entries = deserialize_index_json(docset)?.entries;
+  let (width, height) = get_terminal_size();
+
+  let mut browser = Browser { docset: docset.to_owned(),
+                              entries,
+                              filter: String::new(),
+                              filtered: vec![],
+                              selected_entry: 0,
+                              list_offset: 0,
+                              page: None,
+                              scroll: 0,
+                              selected_link: None,
+                              history: vec![],
+                              history_position: 0,
+                              focus: Focus::List,
+                              message: None,
+                              width,
+                              height };
+  browser.update_filter();
+
+  if let Some(page) = page {
+    let (item, fragment) = split_to_item_and_fragment(page)?;
+    browser.navigate(item, fragment);
+  }
+
+  let terminal = RawTerminal::enable()?;
+  let mut stdout = std::io::stdout();
+  let mut is_dirty = true;
+
+  loop {
+    let (width, height) = get_terminal_size();
+    if width != browser.width || height != browser.height {
+      browser.resize(width, height);
+      is_dirty = true;
+    }
+
+    if is_dirty {
+      write!(stdout, "{}", browser.draw()).and_then(|_| stdout.flush())
+                                          .map_err(|err| format!("Could not draw: {err}"))?;
+      is_dirty = false;
+    }
+
+    for key in terminal.read_keys()? {
+      if browser.handle_key(key) {
+        return Ok(());
+      }
+      is_dirty = true;
+    }
+  }
+}
+
+pub(crate) fn browse<Args>(mut args: Args) -> ResultS
+  where Args: Iterator<Item = String>
+{
+  let mut flag_help;
+
+  let mut flags = flags![
+    flag_help: BoolFlag, ["--help"]
+  ];
+
+  let args = parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
+  if flag_help || args.is_empty() {
+    return show_browse_help();
+  }
+
+  if !is_docs_json_exists()? {
+    return Err(format!("The list of available documents has not yet been \
+                        downloaded. Please run `{PROGRAM_NAME} fetch` first."));
+  }
+
+  let mut args = args.into_iter();
+  let docset = args.next().expect("args are not empty");
+
+  if !is_docset_downloaded(&docset)? {
+    make_sure_docset_is_in_docs(&docset, &deserialize_docs_json()?)?;
+    return Err(format!("Docset `{docset}` is not downloaded. Try running \
+                        `{PROGRAM_NAME} download {docset}`."));
+  }
+
+  let page = args.next();
+
+  if !is_stdin_a_tty() || !is_stdout_a_tty() {
+    return Err("`browse` needs a terminal. Use `search` and `open` in scripts.".to_string());
+  }
+
+  browse_docset(&docset, page)
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_parse_keys()
+  {
+    assert_eq!(parse_keys(b"ab"), vec![Key::Char('a'), Key::Char('b')]);
+    assert_eq!(parse_keys(b"\x1b[A\x1b[B\x1bOC"), vec![Key::Up, Key::Down, Key::Right]);
+    assert_eq!(parse_keys(b"\x1b[5~\x1b[6~\x1b[Z"), vec![Key::PageUp, Key::PageDown, Key::BackTab]);
+    assert_eq!(parse_keys(b"\x1b"), vec![Key::Escape]);
+    assert_eq!(parse_keys(b"\r\t\x7f\x03"), vec![Key::Enter, Key::Tab, Key::Backspace, Key::CtrlC]);
+    assert_eq!(parse_keys("ё".as_bytes()), vec![Key::Char('ё')]);
+  }
+}
@@ -0,0 +1,332 @@
+use std::fs::{create_dir_all, read_dir, read_to_string, File};
+use std::io::{stdout, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+use toiletcli::flags;
+use toiletcli::flags::*;
+
+use crate::bundle::add_local_docs_entries;
+use crate::common::{
+  get_docset_staging_path, get_flag_error, get_html_text, is_docset_downloaded,
+  remove_dir_if_exists,
+};
+use crate::common::{DocsEntry, ResultS};
+use crate::common::{BOLD, DOC_PAGE_EXTENSION, GREEN, PROGRAM_NAME, RESET};
+use crate::config::get_config_bool;
+use crate::download::{replace_docset_with_staged, sanitize_html_line, write_docset_mtime};
+use crate::index::{write_fulltext_index, FulltextIndex};
+use crate::prerender::{prerender_html, write_prerendered_page};
+use crate::remove::is_name_allowed;
+
+fn show_build_help() -> ResultS
+{
+  println!(
+           "\
+{GREEN}USAGE{RESET}
+    {BOLD}{PROGRAM_NAME} build{RESET} [-OPTIONS] <docset> <html-directory>
+    Make a local docset out of HTML pages, like the ones generated by
+    rustdoc, Sphinx or Doxygen. Pages are found by title and by anchors with
+    `id`, and can be used with `search`, `open` and `render` as any other
+    docset.
+
+{GREEN}OPTIONS{RESET}
+    -f, --force                     Replace a docset that already exists.
+    -p, --prerender                 Pre-render pages, so `open` only has to
+                                    wrap them. Takes more time and space.
+        --no-prerender              Don't pre-render pages, even if
+                                    `download.prerender` is set in the config.
+        --help                      Display help message."
+  );
+  Ok(())
+}
+
+// Same format as `index.json` of DevDocs.
+#[derive(Serialize)]
+struct BuiltIndexEntry
+{
+  name: String,
+  path: String,
+  r#type: String,
+}
+
+#[derive(Serialize)]
+struct BuiltIndexJson
+{
+  entries: Vec<BuiltIndexEntry>,
+  types: Vec<String>,
+}
+
+// Text between `<tag ...>` and `</tag>`.
+fn get_tag_contents<'a>(html: &'a str, tag: &str) -> Option<&'a str>
+{
+  let lowercase_html = html.to_ascii_lowercase();
+
+  let start = lowercase_html.find(&format!("<{tag}"))?;
+  let contents_start = start + lowercase_html[start..].find('>')? + 1;
+  let contents_end = contents_start + lowercase_html[contents_start..].find(&format!("</{tag}>"))?;
+
+  Some(&html[contents_start..contents_end])
+}
+
+// -> `<title>`, or the first `<h1>` if there is no title.
+fn get_page_title(html: &str) -> Option<String>
+{
+  ["title", "h1"].iter()
+                 .filter_map(|tag| get_tag_contents(html, tag))
+                 .map(get_html_text)
+                 .find(|title| !title.is_empty())
+}
+
+// Values of `id` attributes in the order they appear.
+fn get_page_anchors(html: &str) -> Vec<String>
+{
+  let mut anchors = vec![];
+  let mut rest = html;
+
+  while let Some(position) = rest.find("id=") {
+    let before = rest[..position].chars().next_back();
+    rest = &rest[position + 3..];
+
+    // Skips `data-id=` and the like.
+    if !before.is_some_and(char::is_whitespace) {
+      continue;
+    }
+
+    let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+      continue;
+    };
+    let Some(end) = rest[1..].find(quote) else {
+      break;
+    };
+
+    let anchor = &rest[1..end + 1];
+    if !anchor.is_empty() && !anchors.iter().any(|a| a == anchor) {
+      anchors.push(anchor.to_string());
+    }
+    rest = &rest[end + 2..];
+  }
+
+  anchors
+}
+
+fn is_html_file(path: &Path) -> bool
+{
+  path.extension().is_some_and(|extension| extension == DOC_PAGE_EXTENSION || extension == "htm")
+}
+
+struct DocsetBuilder
+{
+  staging_path: PathBuf,
+  prerender: bool,
+  entries: Vec<BuiltIndexEntry>,
+  fulltext_index: FulltextIndex,
+}
+
+impl DocsetBuilder
+{
+  fn add_page(&mut self, html_path: &Path, item: String) -> ResultS
+  {
+    let contents = read_to_string(html_path).map_err(|err| {
+                                              format!("Could not read `{}`: {err}",
+                                                      html_path.display())
+                                            })?;
+
+    let sanitized_contents = sanitize_html_line(contents);
+    let sanitized_contents = sanitized_contents.trim();
+
+    let page_path =
+      PathBuf::from(self.staging_path.join(&item).display().to_string() + "." + DOC_PAGE_EXTENSION);
+    if let Some(parent) = page_path.parent() {
+      create_dir_all(parent).map_err(|err| {
+                              format!("Could not create `{}`: {err}", parent.display())
+                            })?;
+    }
+
+    let file = File::create(&page_path).map_err(|err| {
+                                         format!("Could not create `{}`: {err}",
+                                                 page_path.display())
+                                       })?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(sanitized_contents.as_bytes())
+          .map_err(|err| format!("Could not write to `{}`: {err}", page_path.display()))?;
+
+    if let Some(page) = self.prerender.then(|| prerender_html(sanitized_contents)).flatten() {
+      write_prerendered_page(&page_path, &page)?;
+    }
+
+    let title = get_page_title(sanitized_contents).unwrap_or_else(|| item.clone());
+    self.entries
+        .push(BuiltIndexEntry { name: title.clone(), path: item.clone(), r#type: title.clone() });
+    for anchor in get_page_anchors(sanitized_contents) {
+      self.entries.push(BuiltIndexEntry { name: format!("{title}: {anchor}"),
+                                          path: format!("{item}#{anchor}"),
+                                          r#type: title.clone() });
+    }
+
+    self.fulltext_index.add_page(item, sanitized_contents);
+
+    print!("\rBuilt {} pages...", self.fulltext_index.pages().len());
+    stdout().flush().map_err(|err| format!("Could not flush stdout: {err}"))
+  }
+
+  fn visit_dir(&mut self, html_directory: &Path, path: &Path) -> ResultS
+  {
+    let dir = read_dir(path).map_err(|err| {
+                              format!("Could not read `{}` directory: {err}", path.display())
+                            })?;
+
+    let mut entries = dir.collect::<Result<Vec<_>, _>>()
+                         .map_err(|err| format!("Could not read `{}`: {err}", path.display()))?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+      let entry_path = entry.path();
+
+      if entry_path.is_dir() {
+        self.visit_dir(html_directory, &entry_path)?;
+        continue;
+      }
+
+      if !is_html_file(&entry_path) {
+        continue;
+      }
+
+      // Item is a file path without an extension, with `/` on every system.
+      let item = entry_path.strip_prefix(html_directory)
+                           .map_err(|err| err.to_string())?
+                           .with_extension("")
+                           .components()
+                           .map(|component| component.as_os_str().to_string_lossy())
+                           .collect::<Vec<_>>()
+                           .join("/");
+
+      self.add_page(&entry_path, item)?;
+    }
+
+    Ok(())
+  }
+
+  fn write_index_json(&mut self) -> ResultS
+  {
+    let mut types = self.entries.iter().map(|entry| entry.r#type.clone()).collect::<Vec<_>>();
+    types.sort();
+    types.dedup();
+
+    let index_json_path = self.staging_path.join("index.json");
+    let file = File::create(&index_json_path).map_err(|err| {
+                                               format!("Could not create `{}`: {err}",
+                                                       index_json_path.display())
+                                             })?;
+
+    let index_json = BuiltIndexJson { entries: std::mem::take(&mut self.entries), types };
+    serde_json::to_writer(BufWriter::new(file), &index_json).map_err(|err| {
+                                                              format!("Could not write `{}`: {err}",
+                                                                      index_json_path.display())
+                                                            })
+  }
+}
+
+fn build_docset(docset_name: &str, html_directory: &Path, prerender: bool) -> ResultS
+{
+  if !html_directory.is_dir() {
+    return Err(format!("`{}` is not a directory.", html_directory.display()));
+  }
+
+  let staging_path = get_docset_staging_path(docset_name)?;
+  remove_dir_if_exists(&staging_path)?;
+  create_dir_all(&staging_path).map_err(|err| {
+                                 format!("Cannot create `{}` directory: {err}",
+                                         staging_path.display())
+                               })?;
+
+  let mut builder = DocsetBuilder { staging_path: staging_path.clone(),
+                                    prerender,
+                                    entries: vec![],
+                                    fulltext_index: FulltextIndex::default() };
+
+  builder.visit_dir(html_directory, html_directory)?;
+
+  if builder.fulltext_index.pages().is_empty() {
+    remove_dir_if_exists(&staging_path)?;
+    return Err(format!("There are no HTML pages in `{}`.", html_directory.display()));
+  }
+  println!();
+
+  builder.write_index_json()?;
+  write_fulltext_index(&staging_path, &builder.fulltext_index)?;
+
+  let mtime = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)
+                               .map(|duration| duration.as_secs())
+                               .unwrap_or_default();
+  write_docset_mtime(&staging_path, mtime)?;
+
+  replace_docset_with_staged(docset_name)?;
+
+  // Other subcommands expect the docset to be in `docs.json`, and local docsets
+  // are merged into it.
+  add_local_docs_entries(vec![DocsEntry::new_local(docset_name, mtime, 0)])
+}
+
+pub(crate) fn build<Args>(mut args: Args) -> ResultS
+  where Args: Iterator<Item = String>
+{
+  let mut flag_force;
+  let mut flag_prerender;
+  let mut flag_no_prerender;
+  let mut flag_help;
+
+  let mut flags = flags![
+    flag_force: BoolFlag,        ["-f", "--force"],
+    flag_prerender: BoolFlag,    ["-p", "--prerender"],
+    flag_no_prerender: BoolFlag, ["--no-prerender"],
+    flag_help: BoolFlag,         ["--help"]
+  ];
+
+  let args = parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
+  let [docset_name, html_directory] = args.as_slice() else {
+    return show_build_help();
+  };
+  if flag_help {
+    return show_build_help();
+  }
+
+  flag_prerender |= !flag_no_prerender && get_config_bool("download.prerender")?;
+
+  if !is_name_allowed(docset_name) {
+    return Err(format!("`{docset_name}` contains forbidden characters."));
+  }
+
+  if !flag_force && is_docset_downloaded(docset_name)? {
+    return Err(format!("Docset `{docset_name}` already exists. Use `--force` to replace it."));
+  }
+
+  println!("Building `{docset_name}` from `{html_directory}`...");
+  build_docset(docset_name, Path::new(html_directory), flag_prerender)?;
+
+  println!("{BOLD}Build has successfully finished{RESET}.");
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_get_page_title_and_anchors()
+  {
+    let html = "<html><head><title>Vec &amp; friends</title></head><body>\
+                <h1 id=\"vec\">Vec</h1><div data-id=\"skip\"></div>\
+                <h2 id='method.push'>push</h2><a href=\"#vec\" id=\"vec\">Vec</a></body></html>";
+    assert_eq!(get_page_title(html).as_deref(), Some("Vec & friends"));
+    assert_eq!(get_page_anchors(html), vec!["vec", "method.push"]);
+
+    let html = "<h1>The <code>Vec&lt;T&gt;</code>\n type</h1>";
+    assert_eq!(get_page_title(html).as_deref(), Some("The Vec<T> type"));
+    assert_eq!(get_page_title("<p>No title.</p>"), None);
+  }
+}
@@ -0,0 +1,414 @@
+use std::fs::{create_dir_all, read_dir, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use toiletcli::flags;
+use toiletcli::flags::*;
+
+use crate::common::{
+  deserialize_docs_json, deserialize_local_docs_json, deserialize_upstream_docs_json,
+  find_docset_in_docs, get_docset_mtime, get_docset_path, get_docset_staging_path, get_flag_error,
+  get_local_docsets, get_program_directory, is_docs_json_exists, is_docset_downloaded,
+  remove_dir_if_exists,
+};
+use crate::common::{DocsEntry, ResultS, DOC_PAGE_EXTENSION, LOCAL_DOCS_JSON_FILENAME};
+use crate::common::{BOLD, GREEN, PROGRAM_NAME, RESET, VERSION};
+use crate::dash::{
+  get_dash_docset_path, get_dash_documents_path, import_dash_docset, is_dash_docset,
+};
+use crate::download::replace_docset_with_staged;
+use crate::fetch::serialize_and_overwrite_docs;
+use crate::print_warning;
+use crate::remove::is_name_allowed;
+
+const BUNDLE_MANIFEST_FILENAME: &str = "dedoc-bundle.json";
+const DEFAULT_BUNDLE_FILENAME: &str = "dedoc-bundle.tar";
+
+fn show_export_help() -> ResultS
+{
+  println!(
+           "\
+{GREEN}USAGE{RESET}
+    {BOLD}{PROGRAM_NAME} export{RESET} [-OPTIONS] <docset1> [docset2, ..]
+    Pack local docsets into a tar archive, which can be installed with
+    `import` on a computer without internet access.
+
+    {BOLD}{PROGRAM_NAME} export{RESET} [-OPTIONS] --all
+    Pack all local docsets.
+
+{GREEN}OPTIONS{RESET}
+    -o, --output <file>             Where to write the archive. The default is
+                                    `{DEFAULT_BUNDLE_FILENAME}`.
+        --all                       Export all local docsets.
+        --help                      Display help message."
+  );
+  Ok(())
+}
+
+fn show_import_help() -> ResultS
+{
+  println!(
+           "\
+{GREEN}USAGE{RESET}
+    {BOLD}{PROGRAM_NAME} import{RESET} [-OPTIONS] <bundle1.tar> [bundle2.tar, ..]
+    Install docsets from archives that were made by `export`. Docsets that
+    are missing from `docs.json` are listed as local docsets, so `fetch` is
+    not needed, and does not remove them.
+
+    {BOLD}{PROGRAM_NAME} import{RESET} [-OPTIONS] <Name1.docset> [Name2.docset, ..]
+    Install Dash or Zeal docsets. `Rust Nightly.docset` becomes
+    `rust_nightly`.
+
+{GREEN}OPTIONS{RESET}
+    -l, --link                      Use Dash docsets from where they are
+                                    instead of copying them.
+        --help                      Display help message."
+  );
+  Ok(())
+}
+
+// The first file of a bundle. Pages of each docset are in a directory named
+// after it.
+#[derive(Serialize, Deserialize)]
+struct BundleManifest
+{
+  dedoc_version: String,
+  docsets: Vec<DocsEntry>,
+}
+
+fn write_bundle(path: &Path, manifest: &BundleManifest) -> ResultS
+{
+  let file =
+    File::create(path).map_err(|err| format!("Could not create `{}`: {err}", path.display()))?;
+  let mut builder = tar::Builder::new(BufWriter::new(file));
+
+  let manifest_json =
+    serde_json::to_vec(manifest).map_err(|err| format!("Could not serialize manifest: {err}"))?;
+
+  let mtime = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)
+                               .map(|duration| duration.as_secs())
+                               .unwrap_or_default();
+
+  let mut header = tar::Header::new_gnu();
+  header.set_size(manifest_json.len() as u64);
+  header.set_mode(0o644);
+  header.set_mtime(mtime);
+  builder.append_data(&mut header, BUNDLE_MANIFEST_FILENAME, manifest_json.as_slice())
+         .map_err(|err| format!("Could not write `{}`: {err}", path.display()))?;
+
+  for entry in &manifest.docsets {
+    let docset_name = &entry.slug;
+    println!("Packing `{docset_name}`...");
+    builder.append_dir_all(docset_name, get_docset_path(docset_name)?)
+           .map_err(|err| format!("Could not pack `{docset_name}`: {err}"))?;
+  }
+
+  builder.into_inner()
+         .and_then(|mut writer| writer.flush())
+         .map_err(|err| format!("Could not write `{}`: {err}", path.display()))
+}
+
+pub(crate) fn export<Args>(mut args: Args) -> ResultS
+  where Args: Iterator<Item = String>
+{
+  let mut flag_output;
+  let mut flag_all;
+  let mut flag_help;
+
+  let mut flags = flags![
+    flag_output: StringFlag, ["-o", "--output"],
+    flag_all: BoolFlag,      ["--all"],
+    flag_help: BoolFlag,     ["--help"]
+  ];
+
+  let args = parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
+  if flag_help || (args.is_empty() && !flag_all) {
+    return show_export_help();
+  }
+
+  if !is_docs_json_exists()? {
+    return Err(format!("The list of available documents has not yet been \
+                        downloaded. Please run `{PROGRAM_NAME} fetch` first."));
+  }
+  let docs = deserialize_docs_json()?;
+
+  let docsets = if flag_all {
+    if !args.is_empty() {
+      print_warning!("Arguments are ignored due to `--all` flag.");
+    }
+    get_local_docsets()?
+  } else {
+    args
+  };
+
+  if docsets.is_empty() {
+    return Err("Nothing to do.".to_string());
+  }
+
+  let mut entries = vec![];
+  for docset in &docsets {
+    if !is_docset_downloaded(docset)? {
+      return Err(format!("Docset `{docset}` is not downloaded. Try running \
+                          `{PROGRAM_NAME} download {docset}`."));
+    }
+    let docset_path = get_docset_path(docset)?;
+    if get_dash_docset_path(&docset_path)?.is_some_and(|dash_path| dash_path != docset_path) {
+      return Err(format!("`{docset}` was imported with `--link`, so its files are elsewhere. \
+                          Import it without `--link` to export it."));
+    }
+    let mut entry = find_docset_in_docs(docset, &docs).cloned().ok_or_else(|| {
+                      format!("Unable to find `{docset}` in `docs.json`, which is needed to \
+                               install it elsewhere.")
+                    })?;
+    // `docs.json` might be newer than the local docset.
+    entry.mtime = get_docset_mtime(docset)?;
+    entries.push(entry);
+  }
+
+  let output_path =
+    PathBuf::from(if flag_output.is_empty() { DEFAULT_BUNDLE_FILENAME } else { &flag_output });
+
+  println!("Writing `{}`...", output_path.display());
+  write_bundle(&output_path,
+               &BundleManifest { dedoc_version: VERSION.to_string(), docsets: entries })?;
+
+  println!("{BOLD}Export has successfully finished{RESET}.");
+
+  Ok(())
+}
+
+// Adds docsets that `docs.json` does not know about to the local docsets, and
+// creates an empty `docs.json` if there is none, so the docsets can be used
+// without `fetch`.
+pub(crate) fn add_local_docs_entries(entries: Vec<DocsEntry>) -> ResultS
+{
+  let program_path = get_program_directory()?;
+
+  let docs = if is_docs_json_exists()? {
+    deserialize_upstream_docs_json()?
+  } else {
+    serialize_and_overwrite_docs(program_path.join("docs.json"), vec![])?;
+    vec![]
+  };
+
+  let mut local_docs = deserialize_local_docs_json()?;
+
+  for entry in entries {
+    if find_docset_in_docs(&entry.slug, &docs).is_some() {
+      continue;
+    }
+    local_docs.retain(|e| e.slug != entry.slug);
+    let position = local_docs.iter().position(|e| e.slug > entry.slug).unwrap_or(local_docs.len());
+    local_docs.insert(position, entry);
+  }
+
+  serialize_and_overwrite_docs(program_path.join(LOCAL_DOCS_JSON_FILENAME), local_docs)
+}
+
+// Removed local docsets can't be downloaded again, so they are not listed.
+pub(crate) fn remove_local_docs_entry(docset_name: &str) -> ResultS
+{
+  let mut local_docs = deserialize_local_docs_json()?;
+  let length = local_docs.len();
+
+  local_docs.retain(|entry| entry.slug != docset_name);
+  if local_docs.len() == length {
+    return Ok(());
+  }
+
+  serialize_and_overwrite_docs(get_program_directory()?.join(LOCAL_DOCS_JSON_FILENAME), local_docs)
+}
+
+// Splits a path from the archive into the docset name and a path inside of the
+// docset, refusing anything that could point outside of it.
+fn split_bundle_path(path: &Path) -> Option<(String, PathBuf)>
+{
+  let mut components = path.components();
+
+  let docset_name = match components.next()? {
+    Component::Normal(name) => name.to_str()?.to_string(),
+    _ => return None,
+  };
+
+  let mut docset_path = PathBuf::new();
+  for component in components {
+    match component {
+      Component::Normal(part) => docset_path.push(part),
+      Component::CurDir => {}
+      _ => return None,
+    }
+  }
+
+  Some((docset_name, docset_path))
+}
+
+fn unpack_bundle(path: &Path) -> Result<Vec<DocsEntry>, String>
+{
+  let file = File::open(path).map_err(|err| format!("Could not open `{}`: {err}", path.display()))?;
+  let mut archive = tar::Archive::new(BufReader::new(file));
+
+  let read_error = |err| format!("Could not read `{}`: {err}", path.display());
+  let mut entries = archive.entries().map_err(read_error)?;
+
+  let not_a_bundle_error =
+    format!("`{}` is not a bundle made by `{PROGRAM_NAME} export`.", path.display());
+
+  let mut manifest_entry =
+    entries.next().and_then(|entry| entry.ok()).ok_or_else(|| not_a_bundle_error.clone())?;
+  if manifest_entry.path().map_err(read_error)?.as_os_str() != BUNDLE_MANIFEST_FILENAME {
+    return Err(not_a_bundle_error);
+  }
+
+  let mut manifest_json = String::new();
+  manifest_entry.read_to_string(&mut manifest_json).map_err(read_error)?;
+  let manifest: BundleManifest =
+    serde_json::from_str(&manifest_json).map_err(|err| format!("{not_a_bundle_error} {err}"))?;
+
+  for entry in &manifest.docsets {
+    if !is_name_allowed(&entry.slug) {
+      return Err(format!("`{}` contains forbidden characters.", entry.slug));
+    }
+    let staging_path = get_docset_staging_path(&entry.slug)?;
+    remove_dir_if_exists(&staging_path)?;
+    create_dir_all(&staging_path).map_err(|err| {
+                                   format!("Cannot create `{}` directory: {err}",
+                                           staging_path.display())
+                                 })?;
+  }
+
+  let mut current_docset = String::new();
+
+  for entry in entries {
+    let mut entry = entry.map_err(read_error)?;
+    let entry_path = entry.path().map_err(read_error)?.to_path_buf();
+
+    let (docset_name, docset_file_path) =
+      split_bundle_path(&entry_path).filter(|(name, _)| {
+                                      find_docset_in_docs(name, &manifest.docsets).is_some()
+                                    })
+                                    .ok_or_else(|| {
+                                      format!("Unexpected `{}` in `{}`.",
+                                              entry_path.display(),
+                                              path.display())
+                                    })?;
+
+    // Links could point outside of the docset.
+    let entry_type = entry.header().entry_type();
+    if !entry_type.is_file() && !entry_type.is_dir() {
+      return Err(format!("`{}` in `{}` is not a file or a directory.",
+                         entry_path.display(),
+                         path.display()));
+    }
+
+    if docset_name != current_docset {
+      println!("Unpacking `{docset_name}`...");
+      current_docset = docset_name.clone();
+    }
+
+    let unpack_path = get_docset_staging_path(&docset_name)?.join(docset_file_path);
+    if let Some(parent) = unpack_path.parent() {
+      create_dir_all(parent).map_err(|err| {
+                              format!("Cannot create `{}` directory: {err}", parent.display())
+                            })?;
+    }
+    entry.unpack(&unpack_path)
+         .map_err(|err| format!("Could not unpack `{}`: {err}", unpack_path.display()))?;
+  }
+
+  // A docset that is missing from the archive would replace the installed one
+  // with nothing.
+  for entry in &manifest.docsets {
+    if !is_staged_docset_complete(&get_docset_staging_path(&entry.slug)?)? {
+      for entry in &manifest.docsets {
+        remove_dir_if_exists(&get_docset_staging_path(&entry.slug)?)?;
+      }
+      return Err(format!("`{}` does not contain the index and pages of `{}`.",
+                         path.display(),
+                         entry.slug));
+    }
+  }
+
+  Ok(manifest.docsets)
+}
+
+fn has_pages(path: &Path) -> Result<bool, String>
+{
+  let dir =
+    read_dir(path).map_err(|err| format!("Could not read `{}` directory: {err}", path.display()))?;
+
+  for entry in dir {
+    let entry = entry.map_err(|err| format!("Could not read file: {err}"))?;
+    let entry_path = entry.path();
+
+    if entry_path.is_dir() {
+      if has_pages(&entry_path)? {
+        return Ok(true);
+      }
+    } else if entry_path.extension().is_some_and(|extension| extension == DOC_PAGE_EXTENSION) {
+      return Ok(true);
+    }
+  }
+
+  Ok(false)
+}
+
+// Dash docsets have their own index.
+fn is_staged_docset_complete(staging_path: &Path) -> Result<bool, String>
+{
+  if is_dash_docset(staging_path) {
+    let documents_path = get_dash_documents_path(staging_path);
+    return Ok(documents_path.is_dir() && has_pages(&documents_path)?);
+  }
+
+  Ok(staging_path.join("index.json").is_file() && has_pages(staging_path)?)
+}
+
+pub(crate) fn import<Args>(mut args: Args) -> ResultS
+  where Args: Iterator<Item = String>
+{
+  let mut flag_link;
+  let mut flag_help;
+
+  let mut flags = flags![
+    flag_link: BoolFlag, ["-l", "--link"],
+    flag_help: BoolFlag, ["--help"]
+  ];
+
+  let args = parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
+  if flag_help || args.is_empty() {
+    return show_import_help();
+  }
+
+  let mut imported_amount = 0;
+
+  for bundle in &args {
+    if is_dash_docset(Path::new(bundle)) {
+      println!("Importing `{bundle}`...");
+      let entry = import_dash_docset(Path::new(bundle), flag_link)?;
+      imported_amount += 1;
+      add_local_docs_entries(vec![entry])?;
+      continue;
+    }
+
+    println!("Reading `{bundle}`...");
+    let entries = unpack_bundle(Path::new(bundle))?;
+
+    for entry in &entries {
+      replace_docset_with_staged(&entry.slug)?;
+    }
+
+    imported_amount += entries.len();
+    add_local_docs_entries(entries)?;
+  }
+
+  match imported_amount {
+    0 => return Err("Nothing to do.".to_string()),
+    1 => println!("{BOLD}Import has successfully finished{RESET}."),
+    _ => println!("{BOLD}{imported_amount} items were successfully imported{RESET}."),
+  }
+
+  Ok(())
+}
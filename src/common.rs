@@ -1,15 +1,17 @@
 #![allow(dead_code)]
 
+use std::env::{var, var_os};
 use std::fmt::Display;
-use std::fs::{create_dir_all, read_dir, File};
-use std::io::{BufReader, Read, Write};
+use std::fs::{create_dir_all, read_dir, remove_dir_all, File};
+use std::io::{stdout, BufReader, ErrorKind, IsTerminal, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Once;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
 use std::time::{Duration, SystemTime};
 
-use html2text::render::RichAnnotation;
-use html2text::render::TaggedLine;
-use html2text::render::TaggedLineElement::FragmentStart;
+use html2text::render::{
+  RichAnnotation, RichDecorator, TaggedLine, TaggedLineElement, TextDecorator,
+};
 use html2text::Colour;
 
 use toiletcli::colors::{Color, Style};
@@ -17,6 +19,10 @@ use toiletcli::flags::{FlagError, FlagErrorType};
 
 use serde::{Deserialize, Serialize};
 
+use crate::config::{get_config_columns, get_config_number, get_config_value};
+use crate::dash::{get_dash_docset_path, get_dash_documents_path};
+use crate::prerender::read_prerendered_page;
+
 pub(crate) const PROGRAM_NAME: &str = "dedoc";
 
 pub(crate) const VERSION: &str = concat!(env!("CARGO_PKG_VERSION"));
@@ -27,12 +33,20 @@ pub(crate) const HEAD: Option<&str> = option_env!("CI_VERSION");
 pub(crate) const DEFAULT_DB_JSON_LINK: &str = "https://documents.devdocs.io";
 pub(crate) const DEFAULT_DOCS_JSON_LINK: &str = "https://devdocs.io/docs.json";
 pub(crate) const DEFAULT_PROGRAM_DIR_ENV_VARIABLE: &str = "DEDOC_HOME";
+pub(crate) const DOCS_JSON_URL_ENV_VARIABLE: &str = "DEDOC_DOCS_JSON_URL";
+pub(crate) const DOCUMENTS_URL_ENV_VARIABLE: &str = "DEDOC_DOCUMENTS_URL";
+
+pub(crate) const CONFIG_FILENAME: &str = "config.toml";
+// Docsets that were installed from local files are listed apart from
+// `docs.json`, so `fetch` does not lose them.
+pub(crate) const LOCAL_DOCS_JSON_FILENAME: &str = "local_docs.json";
 
 pub(crate) const DEFAULT_WIDTH: usize = 80;
 pub(crate) const MAX_WIDTH: usize = 144;
 
 pub(crate) const MTIME_FILENAME: &str = ".dedoc_mtime";
 pub(crate) const DOC_PAGE_EXTENSION: &str = "html";
+pub(crate) const PRERENDERED_PAGE_EXTENSION: &str = "prerendered.json";
 
 pub(crate) const RED: Color = Color::Red;
 pub(crate) const CYAN: Color = Color::Cyan;
@@ -88,34 +102,55 @@ fn unknown_version() -> String
   "unknown".to_string()
 }
 
-#[allow(dead_code)]
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize, Default, Clone)]
 pub(crate) struct Links
 {
-  home: String,
-  code: String,
+  #[serde(default)]
+  pub home: String,
+  #[serde(default)]
+  pub code: String,
 }
 
-// Entries from docs.json
-#[allow(dead_code)]
-#[derive(Serialize, Deserialize)]
+// Entries from docs.json. Older versions of dedoc kept only some of the fields,
+// so the rest are optional.
+#[derive(Serialize, Deserialize, Clone)]
 pub(crate) struct DocsEntry
 {
-  #[serde(skip)]
-  name: String,
+  #[serde(default)]
+  pub name: String,
   pub slug: String,
-  #[serde(skip)]
-  doctype: String,
-  #[serde(skip)]
-  links: Links,
+  #[serde(default, rename = "type")]
+  pub doctype: String,
+  #[serde(default)]
+  pub links: Links,
   #[serde(default = "unknown_version")]
   pub version: String,
-  #[serde(skip)]
-  release: String,
+  #[serde(default)]
+  pub release: String,
   pub mtime: u64,
-  db_size: usize,
-  #[serde(skip)]
-  attribution: String,
+  // Size of `db.json` in bytes.
+  pub db_size: u64,
+  // HTML with copyright and license of the documentation.
+  #[serde(default)]
+  pub attribution: String,
+}
+
+impl DocsEntry
+{
+  // An entry for a docset that was built from local files and is not in
+  // `docs.json` of DevDocs.
+  pub(crate) fn new_local(slug: &str, mtime: u64, db_size: u64) -> Self
+  {
+    Self { name: slug.to_string(),
+           slug: slug.to_string(),
+           doctype: String::new(),
+           links: Links::default(),
+           version: unknown_version(),
+           release: String::new(),
+           mtime,
+           db_size,
+           attribution: String::new() }
+  }
 }
 
 // Example entry:
@@ -134,19 +169,49 @@ pub(crate) struct DocsEntry
 //     "attribution": "whatever"
 // }
 
-pub(crate) fn deserialize_docs_json() -> Result<Vec<DocsEntry>, String>
+fn deserialize_docs_file(path: &Path) -> Result<Vec<DocsEntry>, String>
 {
-  let docs_json_path = get_program_directory()?.join("docs.json");
-  let file = File::open(&docs_json_path).map_err(|err| {
-                                          format!("Could not open `{}`: {err}",
-                                                  docs_json_path.display())
-                                        })?;
+  let file = File::open(path).map_err(|err| format!("Could not open `{}`: {err}", path.display()))?;
 
   let reader = BufReader::new(file);
 
-  let docs = serde_json::from_reader(reader).map_err(|err| {
-                                              format!("{err}. Maybe `docs.json` was modified?")
-                                            })?;
+  let docs =
+    serde_json::from_reader(reader).map_err(|err| {
+                                     format!("{err}. Maybe `{}` was modified?",
+                                             path.file_name().unwrap_or_default().to_string_lossy())
+                                   })?;
+
+  Ok(docs)
+}
+
+// -> Entries of docsets that were installed from local files.
+pub(crate) fn deserialize_local_docs_json() -> Result<Vec<DocsEntry>, String>
+{
+  let local_docs_json_path = get_program_directory()?.join(LOCAL_DOCS_JSON_FILENAME);
+  if !local_docs_json_path.exists() {
+    return Ok(vec![]);
+  }
+  deserialize_docs_file(&local_docs_json_path)
+}
+
+// -> Entries of `docs.json` only, without local docsets.
+pub(crate) fn deserialize_upstream_docs_json() -> Result<Vec<DocsEntry>, String>
+{
+  deserialize_docs_file(&get_program_directory()?.join("docs.json"))
+}
+
+// Local docsets are merged into `docs.json`, unless it has docsets with the
+// same names.
+pub(crate) fn deserialize_docs_json() -> Result<Vec<DocsEntry>, String>
+{
+  let mut docs = deserialize_upstream_docs_json()?;
+
+  for entry in deserialize_local_docs_json()? {
+    if find_docset_in_docs(&entry.slug, &docs).is_none() {
+      let position = docs.iter().position(|e| e.slug > entry.slug).unwrap_or(docs.len());
+      docs.insert(position, entry);
+    }
+  }
 
   Ok(docs)
 }
@@ -164,6 +229,15 @@ macro_rules! print_warning
   };
 }
 
+// Output of `--json` is a single line, so it can be read line by line.
+pub(crate) fn print_json<T: Serialize + ?Sized>(value: &T) -> ResultS
+{
+  let json =
+    serde_json::to_string(value).map_err(|err| format!("Could not serialize output: {err}"))?;
+  println!("{json}");
+  Ok(())
+}
+
 pub(crate) fn get_flag_error(flag_error: &FlagError) -> String
 {
   match flag_error.error_type {
@@ -182,15 +256,49 @@ pub(crate) fn get_flag_error(flag_error: &FlagError) -> String
   }
 }
 
-pub(crate) fn get_terminal_width() -> usize
+pub(crate) fn get_terminal_width() -> Result<usize, String>
 {
+  let max_width = get_config_columns("max_width")?.unwrap_or(MAX_WIDTH);
+
   if let Some((terminal_size::Width(w), _)) = terminal_size::terminal_size() {
-    if w < MAX_WIDTH as u16 {
-      return w as usize;
+    return Ok(std::cmp::min(w as usize, max_width));
+  }
+
+  Ok(get_config_columns("default_width")?.unwrap_or(DEFAULT_WIDTH))
+}
+
+// Removes tags and replaces common character references.
+pub(crate) fn get_html_text(html: &str) -> String
+{
+  let mut text = String::new();
+  let mut tag: Option<String> = None;
+
+  for ch in html.chars() {
+    match (ch, &mut tag) {
+      ('<', _) => tag = Some(String::new()),
+      ('>', Some(name)) => {
+        // Lines of attributions are separated by `<br>`.
+        if name.trim_end_matches([' ', '/']).eq_ignore_ascii_case("br") {
+          text.push(' ');
+        }
+        tag = None;
+      }
+      (_, Some(name)) => name.push(ch),
+      (_, None) => text.push(ch),
     }
-    return MAX_WIDTH;
   }
-  DEFAULT_WIDTH
+
+  let text = text.replace("&lt;", "<")
+                 .replace("&gt;", ">")
+                 .replace("&quot;", "\"")
+                 .replace("&#39;", "'")
+                 .replace("&nbsp;", " ")
+                 .replace("&copy;", "©")
+                 .replace("&ndash;", "–")
+                 .replace("&mdash;", "—")
+                 .replace("&amp;", "&");
+
+  text.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
 #[inline]
@@ -209,7 +317,114 @@ pub(crate) fn split_to_item_and_fragment(path: String) -> Result<(String, Option
   Ok((item, fragment))
 }
 
-fn get_tag_style(tagged_string_tags: &Vec<RichAnnotation>) -> String
+// Percent-decodes `path`, leaving invalid sequences as they are.
+pub(crate) fn decode_percent_encoding(path: &str) -> String
+{
+  let bytes = path.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+
+  let mut i = 0;
+  while i < bytes.len() {
+    let hex = bytes.get(i + 1..i + 3)
+                   .and_then(|hex| std::str::from_utf8(hex).ok())
+                   .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+    match (bytes[i], hex) {
+      (b'%', Some(byte)) => {
+        decoded.push(byte);
+        i += 3;
+      }
+      (byte, _) => {
+        decoded.push(byte);
+        i += 1;
+      }
+    }
+  }
+
+  String::from_utf8_lossy(&decoded).to_string()
+}
+
+// Percent-encodes everything but unreserved characters and `/`, so `path` can
+// be a part of a URL.
+pub(crate) fn encode_percent_encoding(path: &str) -> String
+{
+  let mut encoded = String::with_capacity(path.len());
+
+  for byte in path.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+        encoded.push(byte as char)
+      }
+      byte => encoded.push_str(&format!("%{byte:02X}")),
+    }
+  }
+
+  encoded
+}
+
+// `--json` outputs links as `{"page": {"item": .., "fragment": ..}}` or
+// `{"external": ..}`.
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DocsetLink
+{
+  // A page from the same docset, which can be passed to `open`.
+  Page
+  {
+    item: String,
+    fragment: Option<String>,
+  },
+  External(String),
+}
+
+// Resolves `href` from a page at `current_item` to a page of the same docset.
+// Links inside docsets are relative, and have no extension.
+pub(crate) fn resolve_docset_link(current_item: &str, href: &str) -> DocsetLink
+{
+  let is_external = href.contains("://") ||
+                    href.starts_with("mailto:") ||
+                    href.starts_with("javascript:") ||
+                    href.starts_with("//");
+  if is_external {
+    return DocsetLink::External(href.to_owned());
+  }
+
+  let (path, fragment) = match href.split_once('#') {
+    Some((path, fragment)) => (path, Some(fragment.to_owned())),
+    None => (href, None),
+  };
+  let path = path.split('?').next().unwrap_or(path);
+
+  if path.is_empty() {
+    return DocsetLink::Page { item: current_item.to_owned(), fragment };
+  }
+
+  let mut components: Vec<&str> = if path.starts_with('/') {
+    vec![]
+  } else {
+    let mut current = current_item.split('/').collect::<Vec<&str>>();
+    current.pop();
+    current
+  };
+
+  for component in path.split('/') {
+    match component {
+      "" | "." => {}
+      ".." => {
+        components.pop();
+      }
+      other => components.push(other),
+    }
+  }
+
+  let mut item = components.join("/");
+  if let Some(stripped) = item.strip_suffix(&format!(".{DOC_PAGE_EXTENSION}")) {
+    item = stripped.to_owned();
+  }
+
+  DocsetLink::Page { item, fragment }
+}
+
+pub(crate) fn get_tag_style(tagged_string_tags: &Vec<RichAnnotation>) -> String
 {
   let mut style_buffer = String::new();
   let mut temp_style;
@@ -240,23 +455,23 @@ fn get_tag_style(tagged_string_tags: &Vec<RichAnnotation>) -> String
 
 // This function ignores fragment's character case, to support
 // --case-insensitive
-fn get_fragment_bounds(tagged_lines: &[TaggedLine<Vec<RichAnnotation>>],
-                       fragment: &str)
-                       -> (Option<usize>, Option<usize>)
+fn get_fragment_bounds(page_lines: &[PageLine], fragment: &str) -> (Option<usize>, Option<usize>)
 {
   let lowercase_fragment = fragment.to_lowercase();
 
   let mut current_fragment_line = None;
   let mut found_fragment = false;
 
-  for (line_number, tagged_line) in tagged_lines.iter().enumerate() {
-    for tagged_line_element in tagged_line.iter() {
-      match tagged_line_element {
-        FragmentStart(temp_fragment) if temp_fragment.to_lowercase() == lowercase_fragment => {
+  for (line_number, page_line) in page_lines.iter().enumerate() {
+    for element in page_line {
+      match element {
+        PageLineElement::Fragment(temp_fragment)
+          if temp_fragment.to_lowercase() == lowercase_fragment =>
+        {
           current_fragment_line = Some(line_number);
           found_fragment = true;
         }
-        FragmentStart(_) if found_fragment => {
+        PageLineElement::Fragment(_) if found_fragment => {
           let next_fragment_line = Some(line_number);
           return (current_fragment_line, next_fragment_line);
         }
@@ -268,8 +483,174 @@ fn get_fragment_bounds(tagged_lines: &[TaggedLine<Vec<RichAnnotation>>],
   (current_fragment_line, None)
 }
 
+// Formats a link as arguments for `open`, or as an absolute URL if it points
+// outside of the docset.
+pub(crate) fn format_docset_link(docset_name: &str, link: DocsetLink) -> String
+{
+  match link {
+    DocsetLink::Page { item, fragment: Some(fragment) } => {
+      format!("{docset_name} {item}#{fragment}")
+    }
+    DocsetLink::Page { item, fragment: None } => format!("{docset_name} {item}"),
+    DocsetLink::External(url) => get_absolute_url(url),
+  }
+}
+
+// Protocol-relative URLs are assumed to be HTTPS.
+pub(crate) fn get_absolute_url(url: String) -> String
+{
+  if url.starts_with("//") {
+    format!("https:{url}")
+  } else {
+    url
+  }
+}
+
+// A line of a rendered page. Fragments are kept where their anchors were, to
+// find the part of the page `open` should print.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum PageLineElement
+{
+  Text(String, Vec<RichAnnotation>),
+  Fragment(String),
+}
+
+pub(crate) type PageLine = Vec<PageLineElement>;
+
+pub(crate) fn get_page_line_from_tagged_line(tagged_line: TaggedLine<Vec<RichAnnotation>>)
+                                             -> PageLine
+{
+  tagged_line.into_iter()
+             .map(|element| match element {
+               TaggedLineElement::Str(tagged_string) => {
+                 PageLineElement::Text(tagged_string.s, tagged_string.tag)
+               }
+               TaggedLineElement::FragmentStart(fragment) => PageLineElement::Fragment(fragment),
+             })
+             .collect()
+}
+
+pub(crate) fn get_page_line_text(page_line: &PageLine) -> String
+{
+  page_line.iter()
+           .filter_map(|element| match element {
+             PageLineElement::Text(text, _) => Some(text.as_str()),
+             PageLineElement::Fragment(_) => None,
+           })
+           .collect()
+}
+
+// html2text puts link footnotes after the page as `[N]: href` lines. Link
+// wrapping is disabled, so each footnote is exactly one line.
+pub(crate) fn split_off_link_footnotes(page_lines: &mut Vec<PageLine>) -> Vec<String>
+{
+  let links_count = page_lines.last()
+                              .map(get_page_line_text)
+                              .and_then(|s| s.strip_prefix('[')?.split_once("]: ")?.0.parse().ok())
+                              .unwrap_or(0);
+  if links_count == 0 || links_count > page_lines.len() {
+    return vec![];
+  }
+
+  let footnotes_start = page_lines.len() - links_count;
+  let mut links = vec![];
+
+  for (i, line) in page_lines[footnotes_start..].iter().enumerate() {
+    if let Some(href) = get_page_line_text(line).strip_prefix(&format!("[{}]: ", i + 1)) {
+      links.push(href.to_owned());
+    } else {
+      return vec![];
+    }
+  }
+
+  page_lines.truncate(footnotes_start);
+  links
+}
+
+// Finds which footnotes are referenced by `[N]` markers in `lines`.
+fn get_referenced_footnotes(lines: &[PageLine], links_count: usize) -> Vec<usize>
+{
+  let mut referenced = vec![];
+
+  for line in lines {
+    let text = get_page_line_text(line);
+    for (_, rest) in text.match_indices('[').map(|(i, _)| text.split_at(i + 1)) {
+      let number = rest.split_once(']').and_then(|(n, _)| n.parse::<usize>().ok());
+      if let Some(n) = number.filter(|n| (1..=links_count).contains(n)) {
+        if !referenced.contains(&n) {
+          referenced.push(n);
+        }
+      }
+    }
+  }
+
+  referenced
+}
+
+pub(crate) fn get_html_translator_config<D: TextDecorator>(decorator: D)
+                                                           -> html2text::config::Config<D>
+{
+  html2text::config::with_decorator(decorator).do_decorate().link_footnotes(true).no_link_wrapping()
+}
+
+// -> (lines of the page, links from its footnotes)
+//
+// Pages of docsets downloaded with `--prerender` are only wrapped to `width`,
+// unless something on them doesn't fit it.
+fn translate_docset_html_file_to_lines(path: &Path,
+                                       is_docset_page: bool,
+                                       width: usize)
+                                       -> Result<(Vec<PageLine>, Vec<String>), String>
+{
+  if is_docset_page {
+    if let Some(page) = read_prerendered_page(path)? {
+      if let Some(page_lines) = page.wrap_lines(width) {
+        return Ok((page_lines, page.links));
+      }
+    }
+  }
+
+  let file = File::open(path).map_err(|err| format!("Could not open `{}`: {err}", path.display()))?;
+  let reader = BufReader::new(file);
+
+  let translator_config = get_html_translator_config(RichDecorator::new());
+  let mut page_lines =
+    translator_config.lines_from_read(reader, width)
+                     .map_err(|err| format!("Failed to parse `{}`: {err}", path.display()))?
+                     .into_iter()
+                     .map(get_page_line_from_tagged_line)
+                     .collect();
+
+  let links = split_off_link_footnotes(&mut page_lines);
+
+  Ok((page_lines, links))
+}
+
+// Returns links of a page in the order of their footnotes.
+pub(crate) fn get_html_file_links(path: &Path) -> Result<Vec<String>, String>
+{
+  Ok(translate_docset_html_file_to_lines(path, false, MAX_WIDTH)?.1)
+}
+
+pub(crate) fn get_page_links_from_docset(docset_name: &str,
+                                         page: &str)
+                                         -> Result<Vec<DocsetLink>, String>
+{
+  let page_path = get_docset_page_path(docset_name, page)?;
+  let links = match read_prerendered_page(&page_path)? {
+    Some(page) => page.links,
+    None => get_html_file_links(&page_path)?,
+  };
+
+  Ok(links.iter().map(|href| resolve_docset_link(page, href)).collect())
+}
+
 // -> (translated file as a string, whether specified fragment was found)
+//
+// If `docset_page` is a `(docset, page)` the file belongs to, links are printed
+// as addresses for `open`.
 pub(crate) fn translate_docset_html_file_to_text(path: PathBuf,
+                                                 docset_page: Option<(&str, &str)>,
                                                  fragment: Option<&String>,
                                                  width: usize,
                                                  number_lines: bool,
@@ -277,18 +658,12 @@ pub(crate) fn translate_docset_html_file_to_text(path: PathBuf,
                                                  -> Result<(String, bool), String>
 {
   let mut output = String::new();
-  let file =
-    File::open(&path).map_err(|err| format!("Could not open `{}`: {err}", path.display()))?;
-  let reader = BufReader::new(file);
 
   // If we are outputting line numbers, leave 7 columns for ourselves.
   let actual_width = if number_lines { width - 7 } else { width };
 
-  let rich_translator_config =
-    html2text::config::rich().do_decorate().link_footnotes(true).no_link_wrapping();
-  let text_lines =
-    rich_translator_config.lines_from_read(reader, actual_width)
-                          .map_err(|err| format!("Failed to parse `{}`: {err}", path.display()))?;
+  let (text_lines, links) =
+    translate_docset_html_file_to_lines(&path, docset_page.is_some(), actual_width)?;
 
   let mut current_fragment_line = 0;
   let mut next_fragment_line = 0;
@@ -327,7 +702,18 @@ pub(crate) fn translate_docset_html_file_to_text(path: PathBuf,
     }
   }
 
+  let get_line_number_prefix = |line_number: usize| {
+    if !number_lines {
+      "".to_string()
+    } else if use_colors {
+      format!("{GRAYER}{line_number:>5}{RESET}  ")
+    } else {
+      format!("{line_number:>5}  ")
+    }
+  };
+
   let mut skipped_empty_lines = false;
+  let mut last_line_is_empty = false;
   let mut line_number = 0;
 
   for (i, tagged_line) in text_lines.iter().enumerate() {
@@ -338,41 +724,49 @@ pub(crate) fn translate_docset_html_file_to_text(path: PathBuf,
       break;
     }
 
-    let tagged_strings: Vec<_> = tagged_line.tagged_strings().collect();
-
-    let is_only_tag = tagged_strings.len() == 1;
+    let tagged_strings: Vec<_> =
+      tagged_line.iter()
+                 .filter_map(|element| match element {
+                   PageLineElement::Text(text, tags) => Some((text, tags)),
+                   PageLineElement::Fragment(_) => None,
+                 })
+                 .collect();
+
+    // Pad preformat to terminal width for cool background.
+    let is_preformat =
+      |tags: &Vec<RichAnnotation>| matches!(tags.first(), Some(RichAnnotation::Preformat(_)));
+    let preformat_padding = if use_colors &&
+                               !tagged_strings.is_empty() &&
+                               tagged_strings.iter().all(|(_, tags)| is_preformat(tags))
+    {
+      let text_length = tagged_strings.iter().map(|(text, _)| text.len()).sum::<usize>();
+      actual_width.saturating_sub(text_length)
+    } else {
+      0
+    };
 
     let mut line_is_empty = true;
     let mut line_buffer = String::new();
 
     if number_lines {
       line_number += 1;
-      if use_colors {
-        line_buffer += &format!("{GRAYER}{line_number:>5}{RESET}  ");
-      } else {
-        line_buffer += &format!("{line_number:>5}  ");
-      }
+      line_buffer += &get_line_number_prefix(line_number);
     }
 
-    for tagged_string in tagged_strings {
-      let style = if use_colors { get_tag_style(&tagged_string.tag) } else { "".to_string() };
+    let last_string_index = tagged_strings.len().saturating_sub(1);
+
+    for (string_index, (text, tags)) in tagged_strings.into_iter().enumerate() {
+      let style = if use_colors { get_tag_style(tags) } else { "".to_string() };
 
-      if !tagged_string.s.is_empty() {
+      if !text.is_empty() {
         line_is_empty = false;
       }
 
       line_buffer += style.as_str();
-      line_buffer += &tagged_string.s;
+      line_buffer += text;
 
-      if is_only_tag && use_colors {
-        // Pad preformat to terminal width for cool background.
-        if let Some(RichAnnotation::Preformat(_)) = tagged_string.tag.first() {
-          let padding_amount = actual_width.saturating_sub(tagged_string.s.len());
-
-          for _ in 0..padding_amount {
-            line_buffer += " ";
-          }
-        }
+      if string_index == last_string_index {
+        line_buffer += &" ".repeat(preformat_padding);
       }
 
       if use_colors {
@@ -386,6 +780,7 @@ pub(crate) fn translate_docset_html_file_to_text(path: PathBuf,
     if skipped_empty_lines {
       output += &line_buffer;
       output += "\n";
+      last_line_is_empty = line_is_empty;
     }
     line_buffer.clear();
   }
@@ -398,18 +793,117 @@ pub(crate) fn translate_docset_html_file_to_text(path: PathBuf,
     }
   }
 
+  // Only list links from the printed part of the page, numbered as on the
+  // whole page, so `open --follow` works the same with or without fragment.
+  let shown_links = if is_fragment_found {
+    let last_line = if has_next_fragment { next_fragment_line + 1 } else { text_lines.len() };
+    get_referenced_footnotes(&text_lines[current_fragment_line..last_line], links.len())
+  } else {
+    (1..=links.len()).collect()
+  };
+
+  if !shown_links.is_empty() && (has_next_fragment || !last_line_is_empty) {
+    line_number += 1;
+    output += get_line_number_prefix(line_number).trim_end();
+    output += "\n";
+  }
+
+  for n in shown_links {
+    let href = &links[n - 1];
+    let link = if let Some((docset_name, page)) = docset_page {
+      format_docset_link(docset_name, resolve_docset_link(page, href))
+    } else {
+      href.to_owned()
+    };
+
+    line_number += 1;
+    output += &get_line_number_prefix(line_number);
+
+    if use_colors {
+      output += &format!("{GRAYER}[{n}]:{RESET} {link}\n");
+    } else {
+      output += &format!("[{n}]: {link}\n");
+    }
+  }
+
   Ok((output, is_fragment_found))
 }
 
+// Passes `output` to the pager from config or `$PAGER`, `less` by default, if
+// stdout is a terminal. Prints it as is otherwise, or if the pager can't be
+// started.
+pub(crate) fn print_with_pager(output: &str, use_pager: bool) -> ResultS
+{
+  if !use_pager || !stdout().is_terminal() {
+    print!("{output}");
+    return Ok(());
+  }
+
+  let pager = match get_config_value("pager")? {
+    Some(pager) => pager,
+    None => var("PAGER").unwrap_or_default(),
+  };
+  let pager = if pager.trim().is_empty() { "less".to_string() } else { pager };
+
+  let mut command = get_pager_command(&pager);
+  command.stdin(Stdio::piped());
+  // Same as Git does: keep colors, and quit if the page fits on the screen.
+  if var_os("LESS").is_none() {
+    command.env("LESS", "FRX");
+  }
+
+  let Ok(mut child) = command.spawn() else {
+    print!("{output}");
+    return Ok(());
+  };
+
+  if let Some(mut stdin) = child.stdin.take() {
+    // The pager can be closed before it reads the whole page.
+    if let Err(err) = stdin.write_all(output.as_bytes()) {
+      if err.kind() != ErrorKind::BrokenPipe {
+        return Err(format!("Could not write to `{pager}`: {err}"));
+      }
+    }
+  }
+
+  let status = child.wait().map_err(|err| format!("Could not wait for `{pager}`: {err}"))?;
+  // The shell could not find the pager.
+  if status.code() == Some(127) {
+    print!("{output}");
+  }
+  Ok(())
+}
+
+// The pager is a shell command, like in Git, so its arguments can be quoted.
+#[cfg(unix)]
+fn get_pager_command(pager: &str) -> Command
+{
+  let mut command = Command::new("sh");
+  command.arg("-c").arg(pager);
+  command
+}
+
+#[cfg(not(unix))]
+fn get_pager_command(pager: &str) -> Command
+{
+  use std::os::windows::process::CommandExt;
+
+  let mut command = Command::new("cmd");
+  command.arg("/C").raw_arg(pager);
+  command
+}
+
 pub(crate) fn print_docset_file(path: PathBuf,
+                                docset_page: Option<(&str, &str)>,
                                 fragment: Option<&String>,
                                 width: usize,
-                                number_lines: bool)
+                                number_lines: bool,
+                                use_pager: bool)
                                 -> Result<bool, String>
 {
   let (output, ret) =
-    translate_docset_html_file_to_text(path, fragment, width, number_lines, true)?;
-  print!("{}", output);
+    translate_docset_html_file_to_text(path, docset_page, fragment, width, number_lines, true)?;
+  print_with_pager(&output, use_pager)?;
   Ok(ret)
 }
 
@@ -417,12 +911,19 @@ pub(crate) fn print_page_from_docset(docset_name: &str,
                                      page: &str,
                                      fragment: Option<&String>,
                                      width: usize,
-                                     number_lines: bool)
+                                     number_lines: bool,
+                                     use_pager: bool)
                                      -> Result<bool, String>
 {
-  let docset_path = get_docset_path(docset_name)?;
+  let page_path = get_docset_page_path(docset_name, page)?;
+  print_docset_file(page_path, Some((docset_name, page)), fragment, width, number_lines, use_pager)
+}
+
+pub(crate) fn get_docset_page_path(docset_name: &str, page: &str) -> Result<PathBuf, String>
+{
+  let pages_path = get_docset_pages_path(docset_name)?;
 
-  let page_path_string = docset_path.join(page).display().to_string() + "." + DOC_PAGE_EXTENSION;
+  let page_path_string = pages_path.join(page).display().to_string() + "." + DOC_PAGE_EXTENSION;
   let page_path = PathBuf::from(page_path_string);
 
   if !page_path.is_file() {
@@ -430,7 +931,7 @@ pub(crate) fn print_page_from_docset(docset_name: &str,
                         from `search` correctly?"));
   }
 
-  print_docset_file(page_path, fragment, width, number_lines)
+  Ok(page_path)
 }
 
 fn get_home_directory() -> Result<PathBuf, String>
@@ -465,17 +966,10 @@ fn get_home_directory() -> Result<PathBuf, String>
   }
 }
 
-static mut PROGRAM_DIRECTORY: Option<PathBuf> = None;
-static PROGRAM_DIRECTORY_INIT: Once = Once::new();
+static PROGRAM_DIRECTORY: OnceLock<Result<PathBuf, String>> = OnceLock::new();
 
 pub(crate) fn get_program_directory() -> Result<PathBuf, String>
 {
-  unsafe {
-    if let Some(ref program_dir) = PROGRAM_DIRECTORY {
-      return Ok(program_dir.clone());
-    }
-  }
-
   fn internal() -> Result<PathBuf, String>
   {
     if let Ok(path_string) = std::env::var(DEFAULT_PROGRAM_DIR_ENV_VARIABLE) {
@@ -500,20 +994,7 @@ pub(crate) fn get_program_directory() -> Result<PathBuf, String>
     Ok(path.join(dot_program))
   }
 
-  unsafe {
-    let mut err = None;
-    PROGRAM_DIRECTORY_INIT.call_once(|| match internal() {
-                            Ok(d) => PROGRAM_DIRECTORY = Some(d),
-                            Err(e) => err = Some(e),
-                          });
-    if let Some(msg) = err {
-      Err(msg)
-    } else if let Some(ref dir) = PROGRAM_DIRECTORY {
-      Ok(dir.to_path_buf())
-    } else {
-      unreachable!()
-    }
-  }
+  PROGRAM_DIRECTORY.get_or_init(internal).clone()
 }
 
 pub(crate) fn create_program_directory() -> ResultS
@@ -532,7 +1013,14 @@ pub(crate) fn create_program_directory() -> ResultS
   }
 }
 
-const WEEK: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+const DAY_IN_SECONDS: u64 = 60 * 60 * 24;
+const DEFAULT_DOCS_JSON_MAX_AGE: u64 = 7;
+
+#[inline]
+pub(crate) fn get_docs_json_max_age() -> Result<u64, String>
+{
+  Ok(get_config_number("docs_json_max_age")?.unwrap_or(DEFAULT_DOCS_JSON_MAX_AGE))
+}
 
 pub(crate) fn is_docs_json_old() -> Result<bool, String>
 {
@@ -541,7 +1029,8 @@ pub(crate) fn is_docs_json_old() -> Result<bool, String>
   let modified_time = metadata.modified().map_err(|err| err.to_string())?;
   let elapsed_time =
     SystemTime::now().duration_since(modified_time).map_err(|err| err.to_string())?;
-  if elapsed_time > WEEK {
+  let max_age = Duration::from_secs(get_docs_json_max_age()?.saturating_mul(DAY_IN_SECONDS));
+  if elapsed_time > max_age {
     Ok(true)
   } else {
     Ok(false)
@@ -732,12 +1221,121 @@ pub(crate) fn get_docset_path(docset_name: &str) -> Result<PathBuf, String>
   Ok(get_program_directory()?.join("docsets").join(docset_name))
 }
 
+// Where HTML pages of a docset are. Dash docsets keep them deeper inside, or
+// somewhere else entirely if they were imported with `--link`.
+pub(crate) fn get_docset_pages_path(docset_name: &str) -> Result<PathBuf, String>
+{
+  let docset_path = get_docset_path(docset_name)?;
+  match get_dash_docset_path(&docset_path)? {
+    Some(dash_path) => Ok(get_dash_documents_path(&dash_path)),
+    None => Ok(docset_path),
+  }
+}
+
+// Where a docset is downloaded and built before it replaces the current one.
+#[inline]
+pub(crate) fn get_docset_staging_path(docset_name: &str) -> Result<PathBuf, String>
+{
+  Ok(get_program_directory()?.join("staging").join(docset_name))
+}
+
+// Where the version that was replaced by the last download is kept.
+#[inline]
+pub(crate) fn get_previous_docset_path(docset_name: &str) -> Result<PathBuf, String>
+{
+  Ok(get_program_directory()?.join("previous").join(docset_name))
+}
+
+pub(crate) fn remove_dir_if_exists(path: &Path) -> ResultS
+{
+  match remove_dir_all(path) {
+    Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+      Err(format!("Could not remove `{}`: {err}", path.display()))
+    }
+    _ => Ok(()),
+  }
+}
+
 #[inline]
 pub(crate) fn get_default_user_agent() -> String
 {
   format!("{PROGRAM_NAME}/{VERSION}")
 }
 
+// Opens `http://`, `https://` and `file://` URLs. Query strings are dropped
+// for local files, since they are only used to bust caches.
+pub(crate) fn open_url(url: &str) -> Result<Box<dyn Read + Send + Sync>, String>
+{
+  open_url_from_offset(url, 0).map(|(reader, _)| reader)
+}
+
+// Same as `open_url()`, but asks to skip the first `offset` bytes, to resume an
+// interrupted download. Servers are free to ignore the range and send
+// everything.
+//
+// -> (reader, whether the first `offset` bytes were skipped)
+pub(crate) fn open_url_from_offset(url: &str,
+                                   offset: u64)
+                                   -> Result<(Box<dyn Read + Send + Sync>, bool), String>
+{
+  if let Some(path) = url.strip_prefix("file://") {
+    let path = path.split('?').next().unwrap_or(path);
+    let mut file = File::open(path).map_err(|err| err.to_string())?;
+    file.seek(SeekFrom::Start(offset)).map_err(|err| err.to_string())?;
+    return Ok((Box::new(file), true));
+  }
+
+  let request = ureq::get(url).set("User-Agent", &get_default_user_agent());
+
+  // Ranges of compressed responses can't be appended to decompressed bytes.
+  let request = if offset > 0 {
+    request.set("Accept-Encoding", "identity").set("Range", &format!("bytes={offset}-"))
+  } else {
+    request.set("Accept-Encoding", "gzip")
+  };
+
+  let response = request.call().map_err(|err| err.to_string())?;
+  let is_resumed = offset > 0 && response.status() == 206;
+
+  Ok((response.into_reader(), is_resumed))
+}
+
+// The flag has the highest priority, then the environment variable, then the
+// config.
+fn get_url_setting(flag_value: &str,
+                   env_variable: &str,
+                   config_key: &str)
+                   -> Result<Option<String>, String>
+{
+  if !flag_value.is_empty() {
+    return Ok(Some(flag_value.to_owned()));
+  }
+  if let Ok(value) = std::env::var(env_variable) {
+    if !value.is_empty() {
+      return Ok(Some(value));
+    }
+  }
+  get_config_value(config_key)
+}
+
+pub(crate) fn get_docs_json_url(flag_mirror: &str) -> Result<String, String>
+{
+  let url = get_url_setting(flag_mirror, DOCS_JSON_URL_ENV_VARIABLE, "docs_json_url")?;
+
+  match url {
+    // Allow to specify the mirror's root instead of the full path.
+    Some(url) if !url.ends_with(".json") => Ok(format!("{}/docs.json", url.trim_end_matches('/'))),
+    Some(url) => Ok(url),
+    None => Ok(DEFAULT_DOCS_JSON_LINK.to_owned()),
+  }
+}
+
+pub(crate) fn get_documents_url(flag_mirror: &str) -> Result<String, String>
+{
+  let url = get_url_setting(flag_mirror, DOCUMENTS_URL_ENV_VARIABLE, "documents_url")?;
+  Ok(url.map_or(DEFAULT_DB_JSON_LINK.to_owned(), |url| url.trim_end_matches('/').to_owned()))
+}
+
 pub(crate) fn validate_number_of_columns(w: &str) -> Result<usize, String>
 {
   if let Ok(c) = w.parse::<usize>() {
@@ -752,3 +1350,51 @@ pub(crate) fn validate_number_of_columns(w: &str) -> Result<usize, String>
     Err("Invalid number of columns.".to_string())
   }
 }
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn resolve(current_item: &str, href: &str) -> (String, Option<String>)
+  {
+    match resolve_docset_link(current_item, href) {
+      DocsetLink::Page { item, fragment } => (item, fragment),
+      DocsetLink::External(url) => (url, Some("external".to_owned())),
+    }
+  }
+
+  #[test]
+  fn test_resolve_docset_link()
+  {
+    let current = "std/io/struct.bufreader";
+
+    assert_eq!(resolve(current, "#method.new"),
+               (current.to_owned(), Some("method.new".to_owned())));
+    assert_eq!(resolve(current, "struct.bufwriter"), ("std/io/struct.bufwriter".to_owned(), None));
+    assert_eq!(resolve(current, "../vec/struct.vec#method.push"),
+               ("std/vec/struct.vec".to_owned(), Some("method.push".to_owned())));
+    assert_eq!(resolve(current, "./trait.read.html"), ("std/io/trait.read".to_owned(), None));
+    assert_eq!(resolve(current, "/core/index"), ("core/index".to_owned(), None));
+    assert_eq!(resolve(current, "https://www.rust-lang.org/"),
+               ("https://www.rust-lang.org/".to_owned(), Some("external".to_owned())));
+  }
+
+  #[test]
+  fn test_percent_encoding()
+  {
+    let path = "/home/user/my docs/100%/ünïcode~1.html";
+    let encoded = encode_percent_encoding(path);
+
+    assert_eq!(encoded, "/home/user/my%20docs/100%25/%C3%BCn%C3%AFcode~1.html");
+    assert_eq!(decode_percent_encoding(&encoded), path);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_pager_command()
+  {
+    let output = get_pager_command(r#"printf '%s|' "page %f" 'a b' c"#).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "page %f|a b|c|");
+  }
+}
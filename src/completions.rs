@@ -0,0 +1,456 @@
+use toiletcli::flags;
+use toiletcli::flags::*;
+
+use crate::common::{
+  deserialize_docs_json, get_flag_error, get_local_docsets, is_docset_downloaded, ResultS,
+};
+use crate::common::{BOLD, GREEN, PROGRAM_NAME, RESET};
+use crate::config::get_config_keys;
+use crate::search::deserialize_index_json;
+
+fn show_completions_help() -> ResultS
+{
+  println!(
+           "\
+{GREEN}USAGE{RESET}
+    {BOLD}{PROGRAM_NAME} completions{RESET} [-OPTIONS] <bash/zsh/fish>
+    Print a completion script for a shell. Besides subcommands and options,
+    it completes docsets from `docs.json` and pages of local docsets.
+
+    To enable completions, add one of these lines to the config of your
+    shell:
+        eval \"$({PROGRAM_NAME} completions bash)\"
+        source <({PROGRAM_NAME} completions zsh)
+        {PROGRAM_NAME} completions fish | source
+
+{GREEN}OPTIONS{RESET}
+        --help                      Display help message."
+  );
+  Ok(())
+}
+
+// Scripts only pass words of the command line to `dedoc complete`, which
+// prints matching candidates. If there are none, the shell completes paths.
+const BASH_SCRIPT: &str = r#"_dedoc()
+{
+  local IFS=$'\n'
+  COMPREPLY=($(dedoc complete "${COMP_WORDS[@]:1:COMP_CWORD}" 2> /dev/null))
+}
+complete -o default -F _dedoc dedoc
+"#;
+
+const ZSH_SCRIPT: &str = r#"#compdef dedoc
+
+_dedoc()
+{
+  local -a candidates
+  candidates=("${(@f)$(dedoc complete "${(@)words[2,CURRENT]}" 2> /dev/null)}")
+  if (( ${#candidates[1]} )); then
+    compadd -a candidates
+  else
+    _files
+  fi
+}
+
+if [ "$funcstack[1]" = "_dedoc" ]; then
+  _dedoc "$@"
+else
+  compdef _dedoc dedoc
+fi
+"#;
+
+const FISH_SCRIPT: &str = r#"function __dedoc_complete
+  set -l tokens (commandline -opc)
+  set -l current (commandline -ct)
+  dedoc complete $tokens[2..-1] "$current" 2> /dev/null
+end
+
+complete -c dedoc -a '(__dedoc_complete)'
+"#;
+
+#[derive(Clone, Copy)]
+enum ArgumentKind
+{
+  // Completed by the shell, since it's a path or something unknown.
+  Other,
+  Docset,
+  LocalDocset,
+  // Several local docsets separated by commas.
+  LocalDocsets,
+  // Page of the docset from the first argument.
+  Page,
+  ConfigAction,
+  ConfigKey,
+  Shell,
+}
+
+struct SubcommandCompletion
+{
+  names: &'static [&'static str],
+  flags: &'static [&'static str],
+  // Flags that take a value, so the next word is not an argument.
+  value_flags: &'static [&'static str],
+  // Flags that make all arguments paths.
+  path_flags: &'static [&'static str],
+  // The last kind is used for the rest of arguments.
+  arguments: &'static [ArgumentKind],
+}
+
+const GLOBAL_FLAGS: &[&str] = &["--short-version",
+                                "--version",
+                                "--force-colors",
+                                "--color",
+                                "--auto-fetch",
+                                "--no-auto-fetch",
+                                "--help"];
+
+const COLOR_VALUES: &[&str] = &["on", "off", "auto"];
+
+const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+const CONFIG_ACTIONS: &[&str] = &["list", "get", "set", "unset"];
+
+// Keep in sync with flags of subcommands.
+const SUBCOMMANDS: &[SubcommandCompletion] = {
+  use ArgumentKind::*;
+
+  &[SubcommandCompletion { names: &["fetch", "ft"],
+                           flags: &["--force", "--mirror", "--help"],
+                           value_flags: &["-m", "--mirror"],
+                           path_flags: &[],
+                           arguments: &[] },
+    SubcommandCompletion { names: &["list", "ls"],
+                           flags: &["--all",
+                                    "--local",
+                                    "--non-local",
+                                    "--newlines",
+                                    "--no-labels",
+                                    "--search",
+                                    "--porcelain",
+                                    "--exists",
+                                    "--long",
+                                    "--json",
+                                    "--labels",
+                                    "--no-all",
+                                    "--no-newlines",
+                                    "--no-porcelain",
+                                    "--help"],
+                           value_flags: &["-s", "--search", "-e", "--exists"],
+                           path_flags: &[],
+                           arguments: &[] },
+    SubcommandCompletion { names: &["info", "if"],
+                           flags: &["--json", "--help"],
+                           value_flags: &[],
+                           path_flags: &[],
+                           arguments: &[Docset] },
+    SubcommandCompletion { names: &["download", "dl"],
+                           flags: &["--force",
+                                    "--update-all",
+                                    "--mirror",
+                                    "--prerender",
+                                    "--no-prerender",
+                                    "--jobs",
+                                    "--rollback",
+                                    "--from-dir",
+                                    "--from-files",
+                                    "--json",
+                                    "--help"],
+                           value_flags: &["-m", "--mirror", "-j", "--jobs", "--from-dir"],
+                           path_flags: &["--from-files"],
+                           arguments: &[Docset] },
+    SubcommandCompletion { names: &["outdated", "od"],
+                           flags: &["--json", "--help"],
+                           value_flags: &[],
+                           path_flags: &[],
+                           arguments: &[LocalDocset] },
+    SubcommandCompletion { names: &["remove", "rm"],
+                           flags: &["--purge-all", "--json", "--help"],
+                           value_flags: &[],
+                           path_flags: &[],
+                           arguments: &[LocalDocset] },
+    SubcommandCompletion { names: &["index", "ix"],
+                           flags: &["--all", "--help"],
+                           value_flags: &[],
+                           path_flags: &[],
+                           arguments: &[LocalDocset] },
+    SubcommandCompletion { names: &["export", "ex"],
+                           flags: &["--output", "--all", "--help"],
+                           value_flags: &["-o", "--output"],
+                           path_flags: &[],
+                           arguments: &[LocalDocset] },
+    SubcommandCompletion { names: &["import", "im"],
+                           flags: &["--link", "--help"],
+                           value_flags: &[],
+                           path_flags: &[],
+                           arguments: &[Other] },
+    SubcommandCompletion { names: &["build", "bd"],
+                           flags: &["--force", "--prerender", "--no-prerender", "--help"],
+                           value_flags: &[],
+                           path_flags: &[],
+                           arguments: &[Other] },
+    SubcommandCompletion { names: &["search", "ss"],
+                           flags: &["--all",
+                                    "--whole",
+                                    "--precise",
+                                    "--fuzzy",
+                                    "--open",
+                                    "--ignore-case",
+                                    "--columns",
+                                    "--ignore-fragment",
+                                    "--line-numbers",
+                                    "--no-pager",
+                                    "--pager",
+                                    "--porcelain",
+                                    "--json",
+                                    "--no-whole",
+                                    "--no-precise",
+                                    "--no-fuzzy",
+                                    "--no-ignore-case",
+                                    "--no-line-numbers",
+                                    "--no-porcelain",
+                                    "--help"],
+                           value_flags: &["-o", "--open", "-c", "--columns"],
+                           path_flags: &[],
+                           arguments: &[LocalDocsets, Other] },
+    SubcommandCompletion { names: &["open", "op"],
+                           flags: &["--html",
+                                    "--columns",
+                                    "--line-numbers",
+                                    "--follow",
+                                    "--markdown",
+                                    "--man",
+                                    "--no-pager",
+                                    "--pager",
+                                    "--json",
+                                    "--no-line-numbers",
+                                    "--no-markdown",
+                                    "--help"],
+                           value_flags: &["-c", "--columns", "-f", "--follow"],
+                           path_flags: &["-h", "--html"],
+                           arguments: &[LocalDocset, Page] },
+    SubcommandCompletion { names: &["browse", "br"],
+                           flags: &["--help"],
+                           value_flags: &[],
+                           path_flags: &[],
+                           arguments: &[LocalDocset, Other] },
+    SubcommandCompletion { names: &["render", "rr"],
+                           flags: &["--columns",
+                                    "--output-dir",
+                                    "--markdown",
+                                    "--no-markdown",
+                                    "--man",
+                                    "--all",
+                                    "--help"],
+                           value_flags: &["-c", "--columns", "-d", "--output-dir"],
+                           path_flags: &[],
+                           arguments: &[LocalDocset] },
+    SubcommandCompletion { names: &["config", "cf"],
+                           flags: &["--help"],
+                           value_flags: &[],
+                           path_flags: &[],
+                           arguments: &[ConfigAction, ConfigKey, Other] },
+    SubcommandCompletion { names: &["completions"],
+                           flags: &["--help"],
+                           value_flags: &[],
+                           path_flags: &[],
+                           arguments: &[Shell, Other] },
+    SubcommandCompletion { names: &["lsp"],
+                           flags: &["--help"],
+                           value_flags: &[],
+                           path_flags: &[],
+                           arguments: &[] },
+    SubcommandCompletion { names: &["serve"],
+                           flags: &["--port", "--help"],
+                           value_flags: &["-p", "--port"],
+                           path_flags: &[],
+                           arguments: &[] }]
+};
+
+fn to_candidates(values: &[&str]) -> Vec<String>
+{
+  values.iter().map(|value| value.to_string()).collect()
+}
+
+fn get_argument_candidates(kind: ArgumentKind, arguments: &[&String], current: &str)
+                           -> Vec<String>
+{
+  match kind {
+    ArgumentKind::Other => vec![],
+    ArgumentKind::Docset => {
+      deserialize_docs_json().map(|docs| docs.into_iter().map(|entry| entry.slug).collect())
+                             .unwrap_or_default()
+    }
+    ArgumentKind::LocalDocset => get_local_docsets().unwrap_or_default(),
+    ArgumentKind::LocalDocsets => {
+      // Only the last docset is completed, and the others are kept.
+      let (previous, _) = current.rsplit_once(',').unwrap_or_default();
+      let separator = if previous.is_empty() { "" } else { "," };
+      get_local_docsets().unwrap_or_default()
+                         .into_iter()
+                         .map(|docset| format!("{previous}{separator}{docset}"))
+                         .collect()
+    }
+    ArgumentKind::Page => {
+      let Some(docset) = arguments.first() else {
+        return vec![];
+      };
+      if !is_docset_downloaded(docset).unwrap_or_default() {
+        return vec![];
+      }
+
+      let mut pages = deserialize_index_json(docset).map(|index| {
+                                                      index.entries
+                                                           .into_iter()
+                                                           .map(|entry| entry.path)
+                                                           .collect::<Vec<String>>()
+                                                    })
+                                                    .unwrap_or_default();
+      pages.sort();
+      pages.dedup();
+      pages
+    }
+    ArgumentKind::ConfigAction => to_candidates(CONFIG_ACTIONS),
+    ArgumentKind::ConfigKey => {
+      if arguments.first().is_some_and(|action| *action != "list") {
+        get_config_keys().map(str::to_string).collect()
+      } else {
+        vec![]
+      }
+    }
+    ArgumentKind::Shell => to_candidates(SHELLS),
+  }
+}
+
+// `words` are arguments before the one that is completed.
+fn get_candidates(words: &[String], current: &str) -> Vec<String>
+{
+  let mut words = words.iter();
+
+  let mut subcommand = None;
+  while let Some(word) = words.next() {
+    if word == "--color" {
+      if words.next().is_none() {
+        return to_candidates(COLOR_VALUES);
+      }
+    } else if !word.starts_with('-') {
+      subcommand = Some(word);
+      break;
+    }
+  }
+
+  let Some(subcommand) = subcommand else {
+    if current.starts_with('-') {
+      return to_candidates(GLOBAL_FLAGS);
+    }
+    return SUBCOMMANDS.iter().map(|completion| completion.names[0].to_string()).collect();
+  };
+
+  let Some(completion) =
+    SUBCOMMANDS.iter().find(|completion| completion.names.contains(&subcommand.as_str()))
+  else {
+    return vec![];
+  };
+
+  let mut arguments = vec![];
+  let mut are_arguments_paths = false;
+  while let Some(word) = words.next() {
+    if completion.path_flags.contains(&word.as_str()) {
+      are_arguments_paths = true;
+    } else if completion.value_flags.contains(&word.as_str()) {
+      // The value of the flag is being completed.
+      if words.next().is_none() {
+        return vec![];
+      }
+    } else if !word.starts_with('-') {
+      arguments.push(word);
+    }
+  }
+
+  if current.starts_with('-') {
+    return to_candidates(completion.flags);
+  }
+  if are_arguments_paths {
+    return vec![];
+  }
+
+  match completion.arguments.get(arguments.len()).or(completion.arguments.last()) {
+    Some(kind) => get_argument_candidates(*kind, &arguments, current),
+    None => vec![],
+  }
+}
+
+// Hidden subcommand for completion scripts. The last argument is the word that
+// is completed, and can be empty.
+pub(crate) fn complete<Args>(args: Args) -> ResultS
+  where Args: Iterator<Item = String>
+{
+  let mut words = args.collect::<Vec<String>>();
+  let current = words.pop().unwrap_or_default();
+
+  for candidate in get_candidates(&words, &current) {
+    if candidate.starts_with(&current) {
+      println!("{candidate}");
+    }
+  }
+
+  Ok(())
+}
+
+pub(crate) fn completions<Args>(mut args: Args) -> ResultS
+  where Args: Iterator<Item = String>
+{
+  let mut flag_help;
+
+  let mut flags = flags![
+    flag_help: BoolFlag, ["--help"]
+  ];
+
+  let args = parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
+  let [shell] = args.as_slice() else {
+    return show_completions_help();
+  };
+  if flag_help {
+    return show_completions_help();
+  }
+
+  match shell.as_str() {
+    "bash" => print!("{BASH_SCRIPT}"),
+    "zsh" => print!("{ZSH_SCRIPT}"),
+    "fish" => print!("{FISH_SCRIPT}"),
+    other => {
+      return Err(format!("Unknown shell `{other}`. Supported shells are {}.", SHELLS.join(", ")));
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn complete_words(words: &[&str]) -> Vec<String>
+  {
+    let (current, words) = words.split_last().expect("there is a word to complete");
+    let words = words.iter().map(|word| word.to_string()).collect::<Vec<String>>();
+    get_candidates(&words, current).into_iter()
+                                   .filter(|candidate| candidate.starts_with(current))
+                                   .collect()
+  }
+
+  #[test]
+  fn test_get_candidates()
+  {
+    assert_eq!(complete_words(&["--color", "o"]), vec!["on", "off"]);
+    assert_eq!(complete_words(&["-c", "ren"]), vec!["render"]);
+    assert_eq!(complete_words(&["ss", "--no-p"]),
+               vec!["--no-pager", "--no-precise", "--no-porcelain"]);
+    assert_eq!(complete_words(&["op", "-c", ""]), Vec::<String>::new());
+    assert_eq!(complete_words(&["op", "--html", "--ma"]), vec!["--markdown", "--man"]);
+    assert_eq!(complete_words(&["cf", "get", "list."]),
+               vec!["list.all", "list.newlines", "list.no_labels", "list.porcelain"]);
+    assert_eq!(complete_words(&["cf", "list", ""]), Vec::<String>::new());
+    assert_eq!(complete_words(&["completions", "--help", "f"]), vec!["fish"]);
+  }
+}
@@ -0,0 +1,408 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::sync::OnceLock;
+
+use toiletcli::flags;
+use toiletcli::flags::*;
+
+use crate::common::ResultS;
+use crate::common::{
+  create_program_directory, get_flag_error, get_program_directory, validate_number_of_columns,
+};
+use crate::common::{BOLD, CONFIG_FILENAME, GREEN, PROGRAM_NAME, RESET};
+
+fn show_config_help() -> ResultS
+{
+  println!(
+           "\
+{GREEN}USAGE{RESET}
+    {BOLD}{PROGRAM_NAME} config{RESET} [-OPTIONS] list
+    Print configured values.
+
+    {BOLD}{PROGRAM_NAME} config{RESET} [-OPTIONS] get <key>
+    Print a value of a key.
+
+    {BOLD}{PROGRAM_NAME} config{RESET} [-OPTIONS] set <key> <value>
+    Change a value of a key.
+
+    {BOLD}{PROGRAM_NAME} config{RESET} [-OPTIONS] unset <key>
+    Remove a key, restoring its default.
+
+{GREEN}OPTIONS{RESET}
+        --help                      Display help message.
+
+{GREEN}KEYS{RESET}"
+  );
+  for (key, _, description) in CONFIG_KEYS {
+    println!("    {key:<32}{description}");
+  }
+  println!(
+           "
+  Config lives in `~/.dedoc/{CONFIG_FILENAME}`. Boolean keys act as if the flag
+  was always passed, unless it's turned off with `--no-<flag>`, like
+  `--no-fuzzy`, or `--pager` for `no_pager` keys. Other keys are overridden by
+  flags."
+  );
+  Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ConfigKind
+{
+  Bool,
+  Number,
+  Columns,
+  Color,
+  Text,
+}
+
+const CONFIG_KEYS: &[(&str, ConfigKind, &str)] =
+  &[("color", ConfigKind::Color, "Default for `--color`."),
+    ("default_width", ConfigKind::Columns, "Width when terminal's is unknown."),
+    ("max_width", ConfigKind::Columns, "Maximum width of printed pages."),
+    ("docs_json_max_age", ConfigKind::Number, "Days before `docs.json` is old."),
+    ("auto_fetch", ConfigKind::Bool, "Default for `--auto-fetch`."),
+    ("pager", ConfigKind::Text, "Pager for pages, `$PAGER` by default."),
+    ("docs_json_url", ConfigKind::Text, "Default for `fetch --mirror`."),
+    ("documents_url", ConfigKind::Text, "Default for `download --mirror`."),
+    ("download.prerender", ConfigKind::Bool, "Default for `download --prerender`."),
+    ("download.jobs", ConfigKind::Number, "Default for `download --jobs`."),
+    ("list.all", ConfigKind::Bool, "Default for `list --all`."),
+    ("list.newlines", ConfigKind::Bool, "Default for `list --newlines`."),
+    ("list.no_labels", ConfigKind::Bool, "Default for `list --no-labels`."),
+    ("list.porcelain", ConfigKind::Bool, "Default for `list --porcelain`."),
+    ("search.ignore_case", ConfigKind::Bool, "Default for `search --ignore-case`."),
+    ("search.precise", ConfigKind::Bool, "Default for `search --precise`."),
+    ("search.whole", ConfigKind::Bool, "Default for `search --whole`."),
+    ("search.fuzzy", ConfigKind::Bool, "Default for `search --fuzzy`."),
+    ("search.porcelain", ConfigKind::Bool, "Default for `search --porcelain`."),
+    ("search.columns", ConfigKind::Columns, "Default for `search --columns`."),
+    ("search.line_numbers", ConfigKind::Bool, "Default for `search --line-numbers`."),
+    ("search.no_pager", ConfigKind::Bool, "Default for `search --no-pager`."),
+    ("open.columns", ConfigKind::Columns, "Default for `open --columns`."),
+    ("open.line_numbers", ConfigKind::Bool, "Default for `open --line-numbers`."),
+    ("open.markdown", ConfigKind::Bool, "Default for `open --markdown`."),
+    ("open.no_pager", ConfigKind::Bool, "Default for `open --no-pager`."),
+    ("render.columns", ConfigKind::Columns, "Default for `render --columns`."),
+    ("render.output_dir", ConfigKind::Text, "Where `render` puts docsets."),
+    ("render.markdown", ConfigKind::Bool, "Default for `render --markdown`."),
+    ("lsp.docsets", ConfigKind::Text, "Default for `lsp`, like `c:c,cpp rust:rust`."),
+    ("serve.port", ConfigKind::Number, "Default for `serve --port`.")];
+
+pub(crate) fn get_config_keys() -> impl Iterator<Item = &'static str>
+{
+  CONFIG_KEYS.iter().map(|(key, _, _)| *key)
+}
+
+#[inline]
+fn is_config_key_known(key: &str) -> bool
+{
+  CONFIG_KEYS.iter().any(|(k, _, _)| *k == key)
+}
+
+fn validate_config_value(key: &str, value: &str) -> ResultS
+{
+  let kind = if let Some((_, kind, _)) = CONFIG_KEYS.iter().find(|(k, _, _)| *k == key) {
+    *kind
+  } else {
+    return Err(format!("Unknown key `{key}`"));
+  };
+
+  if value.contains('\n') {
+    return Err(format!("Value for `{key}` has to be on a single line"));
+  }
+
+  let is_valid = match kind {
+    ConfigKind::Bool => value == "true" || value == "false",
+    ConfigKind::Number => value.parse::<u64>().is_ok(),
+    ConfigKind::Columns => validate_number_of_columns(value).is_ok(),
+    ConfigKind::Color => matches!(value, "on" | "off" | "auto"),
+    ConfigKind::Text => true,
+  };
+
+  if is_valid {
+    Ok(())
+  } else {
+    let expected = match kind {
+      ConfigKind::Bool => "`true` or `false`",
+      ConfigKind::Number => "a number",
+      ConfigKind::Columns => "a number of columns, 10 or more",
+      ConfigKind::Color => "`on`, `off` or `auto`",
+      ConfigKind::Text => unreachable!(),
+    };
+    Err(format!("Invalid value `{value}` for `{key}`, expected {expected}"))
+  }
+}
+
+// Reads a quoted value that starts after `"`, with `\"` and `\\` escaped.
+// -> None if the string is not terminated.
+fn parse_quoted_value(quoted: &str) -> Option<String>
+{
+  let mut value = String::new();
+  let mut chars = quoted.chars();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '"' => return Some(value),
+      '\\' => match chars.next() {
+        Some(escaped @ ('"' | '\\')) => value.push(escaped),
+        // Other backslashes are kept, so Windows paths work as before.
+        Some(other) => {
+          value.push('\\');
+          value.push(other);
+        }
+        None => return None,
+      },
+      other => value.push(other),
+    }
+  }
+
+  None
+}
+
+// Config is a flat list of `key = value` lines, which is a subset of TOML.
+// Values may be quoted, and `#` starts a comment.
+fn parse_config(contents: &str) -> Result<Vec<(String, String)>, String>
+{
+  let mut entries = vec![];
+
+  for (i, line) in contents.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+
+    let (key, value) = if let Some(pair) = line.split_once('=') {
+      pair
+    } else {
+      return Err(format!("Line {} is not a `key = value` pair", i + 1));
+    };
+
+    let key = key.trim();
+    let value = value.trim();
+
+    let value = if let Some(quoted) = value.strip_prefix('"') {
+      parse_quoted_value(quoted).ok_or(format!("Line {} has an unterminated string", i + 1))?
+    } else if let Some((before_comment, _)) = value.split_once('#') {
+      before_comment.trim_end().to_owned()
+    } else {
+      value.to_owned()
+    };
+
+    validate_config_value(key, &value).map_err(|err| format!("Line {}: {err}", i + 1))?;
+
+    entries.push((key.to_owned(), value));
+  }
+
+  Ok(entries)
+}
+
+fn format_config_line(key: &str, value: &str) -> String
+{
+  if value == "true" || value == "false" || value.parse::<u64>().is_ok() {
+    format!("{key} = {value}")
+  } else {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("{key} = \"{value}\"")
+  }
+}
+
+// Replaces the line with `key`, or appends a new one. Comments and other keys
+// are left as is. `None` removes the key.
+fn update_config_contents(contents: &str, key: &str, value: Option<&str>) -> String
+{
+  let mut result = String::new();
+  let mut is_replaced = false;
+
+  for line in contents.lines() {
+    let line_key = line.split_once('=').map(|(k, _)| k.trim());
+
+    if !line.trim_start().starts_with('#') && line_key == Some(key) {
+      if let (Some(value), false) = (value, is_replaced) {
+        result += &format_config_line(key, value);
+        result.push('\n');
+      }
+      is_replaced = true;
+      continue;
+    }
+
+    result += line;
+    result.push('\n');
+  }
+
+  if let (Some(value), false) = (value, is_replaced) {
+    result += &format_config_line(key, value);
+    result.push('\n');
+  }
+
+  result
+}
+
+fn read_config_file() -> Result<String, String>
+{
+  let config_path = get_program_directory()?.join(CONFIG_FILENAME);
+  let mut contents = String::new();
+
+  if config_path.is_file() {
+    File::open(&config_path).and_then(|mut file| file.read_to_string(&mut contents))
+                            .map_err(|err| {
+                              format!("Could not read `{}`: {err}", config_path.display())
+                            })?;
+  }
+
+  Ok(contents)
+}
+
+static CONFIG: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+fn get_config() -> Result<&'static [(String, String)], String>
+{
+  if let Some(config) = CONFIG.get() {
+    return Ok(config);
+  }
+
+  let entries = parse_config(&read_config_file()?).map_err(|err| {
+                  format!("Invalid `{}`: {err}. Please fix it or run `{PROGRAM_NAME} config \
+                           unset` on the key.",
+                          CONFIG_FILENAME)
+                })?;
+
+  Ok(CONFIG.get_or_init(|| entries))
+}
+
+pub(crate) fn get_config_value(key: &str) -> Result<Option<String>, String>
+{
+  debug_assert!(is_config_key_known(key), "unknown config key {key}");
+  // Later entries override earlier ones.
+  Ok(get_config()?.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v.to_owned()))
+}
+
+#[inline]
+pub(crate) fn get_config_bool(key: &str) -> Result<bool, String>
+{
+  Ok(get_config_value(key)?.is_some_and(|v| v == "true"))
+}
+
+// Values are validated when the config is read, so parsing can't fail here.
+#[inline]
+pub(crate) fn get_config_number(key: &str) -> Result<Option<u64>, String>
+{
+  Ok(get_config_value(key)?.and_then(|v| v.parse().ok()))
+}
+
+#[inline]
+pub(crate) fn get_config_columns(key: &str) -> Result<Option<usize>, String>
+{
+  Ok(get_config_value(key)?.and_then(|v| validate_number_of_columns(&v).ok()))
+}
+
+fn write_config_file(contents: &str) -> ResultS
+{
+  create_program_directory()?;
+  let config_path = get_program_directory()?.join(CONFIG_FILENAME);
+
+  File::create(&config_path).and_then(|mut file| file.write_all(contents.as_bytes()))
+                            .map_err(|err| {
+                              format!("Could not write `{}`: {err}", config_path.display())
+                            })
+}
+
+pub(crate) fn config<Args>(mut args: Args) -> ResultS
+  where Args: Iterator<Item = String>
+{
+  let mut flag_help;
+
+  let mut flags = flags![
+    flag_help: BoolFlag, ["--help"]
+  ];
+
+  let args = parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
+  if flag_help || args.is_empty() {
+    return show_config_help();
+  }
+
+  let mut args = args.into_iter();
+  let action = args.next().expect("args are not empty");
+  let args = args.collect::<Vec<String>>();
+
+  match (action.as_str(), args.as_slice()) {
+    ("list", []) => {
+      for (key, value) in get_config()? {
+        println!("{}", format_config_line(key, value));
+      }
+    }
+    ("get", [key]) => {
+      if !is_config_key_known(key) {
+        return Err(format!("Unknown key `{key}`"));
+      }
+      if let Some(value) = get_config_value(key)? {
+        println!("{value}");
+      } else {
+        return Err(format!("`{key}` is not set."));
+      }
+    }
+    ("set", [key, value]) => {
+      validate_config_value(key, value)?;
+      let contents = read_config_file()?;
+      write_config_file(&update_config_contents(&contents, key, Some(value)))?;
+    }
+    ("unset", [key]) => {
+      let contents = read_config_file()?;
+      write_config_file(&update_config_contents(&contents, key, None))?;
+    }
+    ("list" | "get" | "set" | "unset", _) => {
+      return Err(format!("Invalid number of arguments for `config {action}`"));
+    }
+    (other, _) => return Err(format!("Unknown action `{other}`")),
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_parse_config()
+  {
+    let config = r#"
+# Internal mirror.
+docs_json_url = "http://mirror.local/docs.json"
+documents_url = file:///srv/devdocs # trailing comment
+search.ignore_case = true
+"#;
+
+    let entries = parse_config(config).unwrap();
+
+    assert_eq!(entries[0],
+               ("docs_json_url".to_owned(), "http://mirror.local/docs.json".to_owned()));
+    assert_eq!(entries[1], ("documents_url".to_owned(), "file:///srv/devdocs".to_owned()));
+    assert_eq!(entries[2], ("search.ignore_case".to_owned(), "true".to_owned()));
+
+    assert!(parse_config("documents_url").is_err());
+    assert!(parse_config("documents_url = \"file://").is_err());
+    assert!(parse_config("documents_url = \"file://\\\"").is_err());
+    assert_eq!(parse_config(r#"pager = "C:\less.exe""#).unwrap()[0].1, r"C:\less.exe");
+    assert!(parse_config("search.ignore_case = yes").is_err());
+    assert!(parse_config("whatever = 1").is_err());
+  }
+
+  #[test]
+  fn test_update_config()
+  {
+    let config = "# comment\ncolor = \"on\"\nopen.columns = 100\n";
+
+    assert_eq!(update_config_contents(config, "open.columns", Some("80")),
+               "# comment\ncolor = \"on\"\nopen.columns = 80\n");
+    assert_eq!(update_config_contents(config, "color", None), "# comment\nopen.columns = 100\n");
+    assert_eq!(update_config_contents(config, "list.all", Some("true")),
+               "# comment\ncolor = \"on\"\nopen.columns = 100\nlist.all = true\n");
+
+    // Quotes are escaped, so the value is read back as it was.
+    let pager = r#"less -P "page \"%f\"" C:\less"#;
+    let updated = update_config_contents("", "pager", Some(pager));
+    assert_eq!(parse_config(&updated).unwrap()[0], ("pager".to_owned(), pager.to_owned()));
+  }
+}
@@ -0,0 +1,231 @@
+use std::fs::{copy, create_dir_all, metadata, read_dir, read_to_string, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use rusqlite::{Connection, OpenFlags};
+
+use crate::common::DOC_PAGE_EXTENSION;
+use crate::common::{
+  decode_percent_encoding, get_docset_staging_path, remove_dir_if_exists, DocsEntry, ResultS,
+};
+use crate::download::{replace_docset_with_staged, write_docset_mtime};
+use crate::remove::is_name_allowed;
+
+// Inside of a `.docset` bundle.
+const DASH_INDEX_PATH: &str = "Contents/Resources/docSet.dsidx";
+const DASH_DOCUMENTS_PATH: &str = "Contents/Resources/Documents";
+
+// A docset that was imported with `--link` only has this file, which contains
+// the path to the bundle.
+const DASH_LINK_FILENAME: &str = ".dedoc_dash_link";
+
+pub(crate) fn is_dash_docset(path: &Path) -> bool
+{
+  path.join(DASH_INDEX_PATH).is_file()
+}
+
+// -> None if the docset in `docset_path` is a regular DevDocs docset.
+pub(crate) fn get_dash_docset_path(docset_path: &Path) -> Result<Option<PathBuf>, String>
+{
+  if is_dash_docset(docset_path) {
+    return Ok(Some(docset_path.to_path_buf()));
+  }
+
+  let link_path = docset_path.join(DASH_LINK_FILENAME);
+  if !link_path.is_file() {
+    return Ok(None);
+  }
+
+  let dash_path = PathBuf::from(read_to_string(&link_path).map_err(|err| {
+                                                            format!("Could not read `{}`: {err}",
+                                                                    link_path.display())
+                                                          })?
+                                                          .trim_end());
+
+  if !is_dash_docset(&dash_path) {
+    return Err(format!("`{}` is not a Dash docset anymore. Try importing it again.",
+                       dash_path.display()));
+  }
+
+  Ok(Some(dash_path))
+}
+
+// Where the HTML pages of a Dash docset are.
+pub(crate) fn get_dash_documents_path(dash_path: &Path) -> PathBuf
+{
+  dash_path.join(DASH_DOCUMENTS_PATH)
+}
+
+// Converts a path from the Dash index, like
+// `<dash_entry_name=push>std/vec/struct.Vec.html#method.push`, to an item with
+// an optional fragment, like `std/vec/struct.Vec#method.push`.
+// -> None if the entry does not point to an HTML page inside of the docset.
+fn convert_dash_path_to_item(path: &str) -> Option<String>
+{
+  let mut path = path;
+  while let Some(rest) = path.strip_prefix("<dash_entry_") {
+    path = &rest[rest.find('>')? + 1..];
+  }
+
+  if path.contains("://") {
+    return None;
+  }
+
+  let (page, fragment) = match path.split_once('#') {
+    Some((page, fragment)) => (page, Some(fragment)),
+    None => (path, None),
+  };
+
+  let page = decode_percent_encoding(page);
+  let item = page.strip_suffix(&format!(".{DOC_PAGE_EXTENSION}"))?.trim_start_matches("./");
+
+  match fragment {
+    Some(fragment) => Some(format!("{item}#{fragment}")),
+    None => Some(item.to_string()),
+  }
+}
+
+// -> Vec<(name, item)>
+pub(crate) fn read_dash_index(dash_path: &Path) -> Result<Vec<(String, String)>, String>
+{
+  let index_path = dash_path.join(DASH_INDEX_PATH);
+
+  let connection =
+    Connection::open_with_flags(&index_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+      .map_err(|err| format!("Could not open `{}`: {err}", index_path.display()))?;
+
+  let query_error = |err| format!("Could not read `{}`: {err}", index_path.display());
+
+  let search_index_tables: i64 =
+    connection.query_row("SELECT count(*) FROM sqlite_master WHERE name = 'searchIndex'",
+                         [],
+                         |row| row.get(0))
+              .map_err(query_error)?;
+
+  // Docsets that were generated by Dash itself keep their index in Core Data
+  // tables instead.
+  let query = if search_index_tables > 0 {
+    "SELECT name, path FROM searchIndex"
+  } else {
+    "SELECT ztokenname, zpath || ifnull('#' || zanchor, '') FROM ztoken \
+     JOIN ztokenmetainformation ON ztoken.zmetainformation = ztokenmetainformation.z_pk \
+     JOIN zfilepath ON ztokenmetainformation.zfile = zfilepath.z_pk"
+  };
+
+  let mut statement = connection.prepare(query).map_err(query_error)?;
+  let rows =
+    statement.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+             .map_err(query_error)?;
+
+  let mut entries = vec![];
+  for row in rows {
+    let (name, path) = row.map_err(query_error)?;
+    if let Some(item) = convert_dash_path_to_item(&path) {
+      entries.push((name, item));
+    }
+  }
+
+  Ok(entries)
+}
+
+// `Rust Nightly.docset` -> `rust_nightly`
+pub(crate) fn get_dash_docset_name(path: &Path) -> Result<String, String>
+{
+  let name = path.file_stem()
+                 .map(|stem| stem.to_string_lossy().to_lowercase().replace(' ', "_"))
+                 .unwrap_or_default();
+
+  if name.is_empty() || !is_name_allowed(&name) {
+    return Err(format!("Could not make a docset name from `{}`.", path.display()));
+  }
+
+  Ok(name)
+}
+
+fn copy_directory(from: &Path, to: &Path) -> ResultS
+{
+  create_dir_all(to).map_err(|err| format!("Cannot create `{}` directory: {err}", to.display()))?;
+
+  let dir = read_dir(from).map_err(|err| format!("Could not read `{}`: {err}", from.display()))?;
+
+  for entry in dir {
+    let entry = entry.map_err(|err| format!("Could not read `{}`: {err}", from.display()))?;
+    let entry_path = entry.path();
+    let target_path = to.join(entry.file_name());
+
+    // Follows symlinks, since the copy should not depend on the original.
+    if entry_path.is_dir() {
+      copy_directory(&entry_path, &target_path)?;
+    } else {
+      copy(&entry_path, &target_path).map_err(|err| {
+                                       format!("Could not copy `{}`: {err}", entry_path.display())
+                                     })?;
+    }
+  }
+
+  Ok(())
+}
+
+// Installs a Dash docset by copying it, or by remembering its path when `link`
+// is set. The version of the docset is the modification time of its index.
+// -> entry for `docs.json`
+pub(crate) fn import_dash_docset(path: &Path, link: bool) -> Result<DocsEntry, String>
+{
+  let docset_name = get_dash_docset_name(path)?;
+
+  let mtime = metadata(path.join(DASH_INDEX_PATH)).and_then(|metadata| metadata.modified())
+                                                  .unwrap_or(SystemTime::UNIX_EPOCH)
+                                                  .duration_since(SystemTime::UNIX_EPOCH)
+                                                  .map(|duration| duration.as_secs())
+                                                  .unwrap_or_default();
+
+  let staging_path = get_docset_staging_path(&docset_name)?;
+  remove_dir_if_exists(&staging_path)?;
+
+  if link {
+    let dash_path = path.canonicalize()
+                        .map_err(|err| format!("Could not resolve `{}`: {err}", path.display()))?;
+    create_dir_all(&staging_path).map_err(|err| {
+                                   format!("Cannot create `{}` directory: {err}",
+                                           staging_path.display())
+                                 })?;
+
+    let link_path = staging_path.join(DASH_LINK_FILENAME);
+    let mut link_file = File::create(&link_path).map_err(|err| {
+                                                  format!("Could not create `{}`: {err}",
+                                                          link_path.display())
+                                                })?;
+    link_file.write_all(dash_path.display().to_string().as_bytes())
+             .map_err(|err| format!("Could not write `{}`: {err}", link_path.display()))?;
+  } else {
+    println!("Copying `{}`...", path.display());
+    copy_directory(path, &staging_path)?;
+  }
+
+  write_docset_mtime(&staging_path, mtime)?;
+
+  replace_docset_with_staged(&docset_name)?;
+
+  Ok(DocsEntry::new_local(&docset_name, mtime, 0))
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_convert_dash_path_to_item()
+  {
+    assert_eq!(convert_dash_path_to_item("std/vec/struct.Vec.html#method.push").as_deref(),
+               Some("std/vec/struct.Vec#method.push"));
+    assert_eq!(convert_dash_path_to_item("<dash_entry_name=push><dash_entry_menuDescription=Vec>\
+                                          std/vec/struct.Vec.html").as_deref(),
+               Some("std/vec/struct.Vec"));
+    assert_eq!(convert_dash_path_to_item("Rust%20Guide/index.html").as_deref(),
+               Some("Rust Guide/index"));
+    assert_eq!(convert_dash_path_to_item("https://doc.rust-lang.org/std/index.html"), None);
+    assert_eq!(convert_dash_path_to_item("std/vec/struct.Vec.pdf"), None);
+  }
+}
@@ -1,26 +1,41 @@
-use std::fs::{create_dir_all, remove_file, File};
-use std::io::{stdout, BufReader, BufWriter, Read, Write};
-use std::path::PathBuf;
-
-use ureq::get;
+use std::fs::{
+  copy, create_dir_all, metadata, read_dir, remove_dir_all, remove_file, rename, File, OpenOptions,
+};
+use std::io::{stdout, BufReader, BufWriter, ErrorKind, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
 
 use serde::de::{Error, MapAccess, Visitor};
-use serde::Deserializer;
+use serde::{Deserializer, Serialize};
 
 use toiletcli::flags;
 use toiletcli::flags::*;
 
+use crate::bundle::add_local_docs_entries;
 use crate::common::{
-  deserialize_docs_json, find_docset_in_docs, get_default_user_agent, get_docset_path,
-  get_flag_error, get_local_docsets, is_docs_json_exists, is_docs_json_old, is_docset_downloaded,
-  is_docset_in_docs_or_print_warning, is_docset_old,
+  deserialize_docs_json, find_docset_in_docs, get_docs_json_max_age, get_docset_path,
+  get_docset_staging_path, get_documents_url, get_flag_error, get_local_docsets,
+  get_previous_docset_path, is_docs_json_exists, is_docs_json_old, is_docset_downloaded,
+  is_docset_in_docs_or_print_warning, is_docset_old, open_url_from_offset, print_json,
+  remove_dir_if_exists,
 };
 use crate::common::{DocsEntry, ResultS};
-use crate::common::{BOLD, DEFAULT_DB_JSON_LINK, GREEN, MTIME_FILENAME, PROGRAM_NAME, RESET};
+use crate::common::{BOLD, DOCUMENTS_URL_ENV_VARIABLE, GREEN, MTIME_FILENAME, PROGRAM_NAME, RESET};
+use crate::config::{get_config_bool, get_config_number};
+use crate::index::{write_fulltext_index, FulltextIndex};
+use crate::prerender::{prerender_html, remove_prerendered_page, write_prerendered_page};
 use crate::print_warning;
+use crate::remove::is_name_allowed;
 
 const DOWNLOAD_BUFFER_SIZE: usize = 1024 * 32;
 
+const DOWNLOAD_PART_EXTENSION: &str = ".part";
+
+// How many times a download continues after the connection was broken.
+const DOWNLOAD_RESUME_ATTEMPTS: usize = 5;
+
 fn show_download_help() -> ResultS
 {
   println!(
@@ -33,91 +48,361 @@ fn show_download_help() -> ResultS
     {BOLD}{PROGRAM_NAME} download{RESET} --update-all
     Try to update all downloaded docsets against a recent `docs.json`.
 
+    {BOLD}{PROGRAM_NAME} download{RESET} --from-dir <path> <docset1> [docset2, ..]
+    Build docsets from `<path>/<docset>/db.json` and `index.json`, e.g. from
+    a DevDocs checkout, without `docs.json` or network. `<path>` can also
+    contain the files itself when there is one docset.
+
+    {BOLD}{PROGRAM_NAME} download{RESET} --from-files <docset> <db.json> <index.json>
+    Build a docset from the given files.
+
+    {BOLD}{PROGRAM_NAME} download{RESET} --rollback <docset1> [docset2, ..]
+    Restore the version of a docset that was replaced by the last download.
+    Rolling back again restores the newer version.
+
 {GREEN}OPTIONS{RESET}
     -f, --force                     Force the download and overwrite files.
     -u, --update-all                Try to update all local docsets whose
                                     version is older than in `docs.json`.
-        --help                      Display help message."
+    -m, --mirror <url>              Download docsets from a mirror. Accepts
+                                    `http://`, `https://` and `file://` URLs.
+    -p, --prerender                 Pre-render pages, so `open` only has to
+                                    wrap them. Takes more time and space.
+        --no-prerender              Don't pre-render pages, even if
+                                    `download.prerender` is set in the config.
+    -j, --jobs <number>             Download this many docsets at once.
+        --rollback                  Restore previous versions of docsets.
+        --from-dir <path>           Build docsets from files in a directory.
+        --from-files                Build a docset from the given files.
+        --json                      Print the status of each docset as JSON
+                                    instead of progress.
+        --help                      Display help message.
+
+  The mirror can also be set with ${DOCUMENTS_URL_ENV_VARIABLE} or with
+  `documents_url` in the config."
   );
   Ok(())
 }
 
-fn download_db_and_index_json_with_progress(docset_name: &str, docs: &[DocsEntry]) -> ResultS
+// How often lines of `ProgressBoard` are redrawn while bytes are received or
+// files are unpacked.
+const PROGRESS_BOARD_REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+// Progress of several docsets that are downloaded at once, one line per docset.
+// In a terminal, all lines are redrawn in place. Otherwise only messages are
+// printed, since there is nothing to redraw.
+struct ProgressBoard
 {
-  if let Some(entry) = find_docset_in_docs(docset_name, docs) {
-    let docset_path = get_docset_path(docset_name)?;
-    if !docset_path.try_exists()
-                   .map_err(|err| {
-                     format!("Could not check if {} exists: {err}", docset_path.display())
-                   })?
-    {
-      create_dir_all(&docset_path).map_err(|err| {
-                                    format!("Cannot create `{}` directory: {err}",
-                                            docset_path.display())
-                                  })?;
-    }
+  lines: Mutex<ProgressBoardLines>,
+  is_terminal: bool,
+}
 
-    for (i, file_name) in ["db.json", "index.json"].iter().enumerate() {
-      let file_path = docset_path.join(file_name);
+struct ProgressBoardLines
+{
+  // (docset, message)
+  lines: Vec<(String, String)>,
+  is_drawn: bool,
+  last_draw: Option<Instant>,
+}
+
+impl ProgressBoard
+{
+  fn new(docset_names: &[&str]) -> Self
+  {
+    let lines = docset_names.iter().map(|name| (name.to_string(), "Waiting...".to_string()));
+    Self { lines: Mutex::new(ProgressBoardLines { lines: lines.collect(),
+                                                  is_drawn: false,
+                                                  last_draw: None }),
+           is_terminal: stdout().is_terminal() }
+  }
 
-      let file = File::create(&file_path).map_err(|err| {
-                                           format!("Could not create `{}`: {err}",
-                                                   file_path.display())
-                                         })?;
+  // Replaces the message of a docset. Messages that are not `important` may be
+  // skipped, when they arrive more often than the board is redrawn.
+  fn set(&self, line_number: usize, message: &str, important: bool) -> ResultS
+  {
+    let mut board = self.lines.lock().map_err(|_| "Progress board was poisoned".to_string())?;
+    board.lines[line_number].1 = message.to_string();
 
-      let download_link =
-        format!("{DEFAULT_DB_JSON_LINK}/{docset_name}/{}?{}", file_name, entry.mtime);
+    let name_width = board.lines.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let mut stdout = stdout().lock();
 
-      let response =
-        get(&download_link).set("User-Agent", &get_default_user_agent())
-                           .set("Accept-Encoding", "gzip")
-                           .call()
-                           .map(|x| x.into_reader())
-                           .map_err(|err| {
-                             format!("Could not download `{download_link}`: \
-                                      {err}")
-                           })?;
+    let result = if !self.is_terminal {
+      if !important {
+        return Ok(());
+      }
+      writeln!(stdout, "{:<name_width$}  {message}", board.lines[line_number].0)
+    } else {
+      let is_time_to_redraw =
+        board.last_draw.map_or(true, |time| time.elapsed() >= PROGRESS_BOARD_REDRAW_INTERVAL);
+      if !important && !is_time_to_redraw {
+        return Ok(());
+      }
 
-      let mut file_writer = BufWriter::new(file);
-      let mut response_reader = BufReader::new(response);
+      // Lines that are wider than the terminal would wrap and break moving the
+      // cursor up.
+      let max_width = terminal_size::terminal_size().map_or(usize::MAX, |(w, _)| w.0 as usize);
 
-      let mut buffer = [0; DOWNLOAD_BUFFER_SIZE];
-      let mut file_size = 0;
+      let mut output = String::new();
+      if board.is_drawn {
+        output += &format!("\x1b[{}A", board.lines.len());
+      }
+      for (name, message) in &board.lines {
+        let line = format!("{name:<name_width$}  {message}");
+        output += "\r\x1b[2K";
+        output.extend(line.chars().take(max_width.saturating_sub(1)));
+        output += "\n";
+      }
 
-      while let Ok(size) = response_reader.read(&mut buffer) {
-        if size == 0 {
-          break;
-        }
+      board.is_drawn = true;
+      board.last_draw = Some(Instant::now());
 
-        file_writer.write(&buffer[..size])
-                   .map_err(|err| format!("Could not download file: {err}"))?;
+      write!(stdout, "{output}")
+    };
 
-        file_size += size;
+    result.and_then(|_| stdout.flush()).map_err(|err| format!("Could not write to stdout: {err}"))
+  }
+}
 
-        print!("\rReceived {file_size} bytes, file {} of 2...", i + 1);
+// Where progress of downloading a docset goes.
+enum Progress<'a>
+{
+  // Nothing is printed, e.g. with `--json`.
+  Hidden,
+  // Docsets are downloaded one at a time, updates replace the current line.
+  Line,
+  // Several docsets are downloaded at once, each updates its line on a board.
+  Board(&'a ProgressBoard, usize),
+}
 
-        stdout().flush().map_err(|err| format!("Could not flush stdout: {err}"))?;
+impl Progress<'_>
+{
+  // Prints a step of the download. `short_message` is used on the board, where
+  // the docset name and path are already known.
+  fn message(&self, message: &str, short_message: &str) -> ResultS
+  {
+    match self {
+      Progress::Hidden => Ok(()),
+      Progress::Line => {
+        println!("{message}");
+        Ok(())
       }
+      Progress::Board(board, line_number) => board.set(*line_number, short_message, true),
+    }
+  }
+
+  // Replaces the previous update, like the number of received bytes.
+  fn update(&self, message: &str) -> ResultS
+  {
+    match self {
+      Progress::Hidden => Ok(()),
+      Progress::Line => {
+        print!("\r{message}");
+        stdout().flush().map_err(|err| format!("Could not flush stdout: {err}"))
+      }
+      Progress::Board(board, line_number) => board.set(*line_number, message, false),
+    }
+  }
+
+  // Keeps the last update, so following output starts on a new line.
+  fn finish_line(&self)
+  {
+    if let Progress::Line = self {
       println!();
     }
-    // Create a file that will store current version of the docset.
-    let mtime_path = docset_path.join(MTIME_FILENAME);
-    let mut mtime_file = File::create(&mtime_path).map_err(|err| {
-                                                    format!("Could not open `{}`: {err}",
-                                                            mtime_path.display())
-                                                  })?;
-    write!(mtime_file, "{}", entry.mtime).map_err(|err| {
-                                           format!("Could not write to `{}`: {err}",
-                                                   mtime_path.display())
-                                         })?;
+  }
+}
+
+// -> 0 if the file does not exist.
+fn get_file_size(path: &Path) -> Result<u64, String>
+{
+  match metadata(path) {
+    Ok(metadata) => Ok(metadata.len()),
+    Err(err) if err.kind() == ErrorKind::NotFound => Ok(0),
+    Err(err) => Err(format!("Could not read metadata of `{}`: {err}", path.display())),
+  }
+}
+
+// Appends the response to `part_path`, skipping the bytes that are already
+// there if the server allows it. Received bytes stay in `part_path` when the
+// download breaks.
+fn receive_file_with_progress(url: &str,
+                              part_path: &Path,
+                              file_number: usize,
+                              progress: &Progress)
+                              -> ResultS
+{
+  let offset = get_file_size(part_path)?;
+
+  let (response, is_resumed) =
+    open_url_from_offset(url, offset).map_err(|err| format!("Could not download `{url}`: {err}"))?;
+
+  let mut options = OpenOptions::new();
+  if is_resumed {
+    options.append(true).create(true);
+  } else {
+    options.write(true).create(true).truncate(true);
+  }
+  let file = options.open(part_path)
+                    .map_err(|err| format!("Could not open `{}`: {err}", part_path.display()))?;
+
+  let mut file_writer = BufWriter::new(file);
+  let mut response_reader = BufReader::new(response);
+
+  let mut buffer = [0; DOWNLOAD_BUFFER_SIZE];
+  let mut file_size = if is_resumed { offset } else { 0 };
+
+  loop {
+    let size = match response_reader.read(&mut buffer) {
+      Ok(0) => break,
+      Ok(size) => size,
+      Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+      Err(err) => return Err(format!("Could not download `{url}`: {err}")),
+    };
+
+    file_writer.write_all(&buffer[..size])
+               .map_err(|err| format!("Could not write to `{}`: {err}", part_path.display()))?;
+
+    file_size += size as u64;
+
+    progress.update(&format!("Received {file_size} bytes, file {file_number} of 2..."))?;
+  }
+
+  file_writer.flush().map_err(|err| format!("Could not write to `{}`: {err}", part_path.display()))
+}
+
+// Downloads `url` to `part_path`, continuing a previous download if it was
+// interrupted. `part_path` is removed if it turns out to be broken.
+fn download_file_with_progress(url: &str,
+                               part_path: &Path,
+                               expected_size: Option<u64>,
+                               file_number: usize,
+                               progress: &Progress)
+                               -> ResultS
+{
+  let mut attempts = 0;
+
+  loop {
+    let offset = get_file_size(part_path)?;
+    if expected_size.is_some_and(|size| offset >= size) {
+      break;
+    }
+
+    let result = receive_file_with_progress(url, part_path, file_number, progress);
+
+    let received_size = get_file_size(part_path)?;
+    if received_size != offset {
+      progress.finish_line();
+    }
+    let is_incomplete = expected_size.is_some_and(|size| received_size < size);
+
+    match result {
+      Ok(()) if !is_incomplete => break,
+      // The connection was broken, continue from where it stopped.
+      _ if received_size > offset && attempts < DOWNLOAD_RESUME_ATTEMPTS => attempts += 1,
+      // The server might not accept the range of a previous download, e.g. if
+      // it was already complete. Start over.
+      Err(_) if offset > 0 && attempts < DOWNLOAD_RESUME_ATTEMPTS => {
+        remove_file(part_path).map_err(|err| {
+                                format!("Could not remove `{}`: {err}", part_path.display())
+                              })?;
+        attempts += 1;
+      }
+      Ok(()) => break,
+      Err(err) => return Err(err),
+    }
+  }
+
+  if let Some(expected_size) = expected_size {
+    let received_size = get_file_size(part_path)?;
+    if received_size != expected_size {
+      remove_file(part_path).map_err(|err| {
+                              format!("Could not remove `{}`: {err}", part_path.display())
+                            })?;
+      return Err(format!("Received {received_size} bytes from `{url}`, while `docs.json` \
+                          says it has {expected_size}. Try running `{PROGRAM_NAME} fetch` \
+                          and downloading again."));
+    }
+  }
+
+  Ok(())
+}
+
+// Removes pages of a build that did not finish and unfinished downloads of
+// other versions of the docset.
+fn clean_staging_directory(staging_path: &Path, part_names: &[String]) -> ResultS
+{
+  let dir = read_dir(staging_path).map_err(|err| {
+                                    format!("Could not read `{}` directory: {err}",
+                                            staging_path.display())
+                                  })?;
+
+  for entry in dir {
+    let entry = entry.map_err(|err| format!("Could not read file: {err}"))?;
+    let file_name = entry.file_name().to_string_lossy().to_string();
+
+    if part_names.contains(&file_name) {
+      continue;
+    }
+
+    let entry_path = entry.path();
+    let result = if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+      remove_dir_all(&entry_path)
+    } else {
+      remove_file(&entry_path)
+    };
+    result.map_err(|err| format!("Could not remove `{}`: {err}", entry_path.display()))?;
+  }
+
+  Ok(())
+}
+
+// Downloads `db.json` and `index.json` of a docset into `staging_path`.
+fn download_db_and_index_json_with_progress(entry: &DocsEntry,
+                                            documents_url: &str,
+                                            staging_path: &Path,
+                                            progress: &Progress)
+                                            -> ResultS
+{
+  let docset_name = &entry.slug;
+
+  create_dir_all(staging_path).map_err(|err| {
+                                format!("Cannot create `{}` directory: {err}",
+                                        staging_path.display())
+                              })?;
+
+  // Unfinished downloads are kept per version, so a newer version is not
+  // appended to an older one.
+  let files = [("db.json", Some(entry.db_size)), ("index.json", None)];
+  let part_names =
+    files.map(|(file_name, _)| format!("{file_name}.{}{DOWNLOAD_PART_EXTENSION}", entry.mtime));
+
+  clean_staging_directory(staging_path, &part_names)?;
+
+  for (i, ((file_name, expected_size), part_name)) in files.iter().zip(&part_names).enumerate() {
+    let download_link = format!("{documents_url}/{docset_name}/{}?{}", file_name, entry.mtime);
+    download_file_with_progress(&download_link,
+                                &staging_path.join(part_name),
+                                *expected_size,
+                                i + 1,
+                                progress)?;
+  }
+
+  for ((file_name, _), part_name) in files.iter().zip(&part_names) {
+    let part_path = staging_path.join(part_name);
+    let file_path = staging_path.join(file_name);
+    rename(&part_path, &file_path).map_err(|err| {
+                                    format!("Could not move `{}` to `{}`: {err}",
+                                            part_path.display(),
+                                            file_path.display())
+                                  })?;
   }
 
   Ok(())
 }
 
-// Remove class="...", title="...", data-language="..." attributes from HTML
-// tags to reduce size.
-fn sanitize_html_line(html_line: String) -> String
+// Remove class="..." and title="..." attributes from HTML tags to reduce size.
+// data-language="..." is kept for code blocks in Markdown.
+pub(crate) fn sanitize_html_line(html_line: String) -> String
 {
   enum State
   {
@@ -145,9 +430,6 @@ fn sanitize_html_line(html_line: String) -> String
         sanitized_line_buffer.push(ch);
       }
       State::InTag => match ch {
-        'd' if position + 15 < length && bytes[position..position + 15] == *b"data-language=\"" => {
-          state = State::InKey;
-        }
         't' if position + 7 < length && bytes[position..position + 7] == *b"title=\"" => {
           state = State::InKey;
         }
@@ -178,7 +460,11 @@ fn sanitize_html_line(html_line: String) -> String
   sanitized_line_buffer
 }
 
-fn build_docset_from_map_with_progress<'de, M>(docset_name: &str, mut map: M) -> ResultS
+fn build_docset_from_map_with_progress<'de, M>(docset_path: &Path,
+                                               mut map: M,
+                                               prerender: bool,
+                                               progress: &Progress)
+                                               -> ResultS
   where M: MapAccess<'de>
 {
   #[inline]
@@ -189,7 +475,7 @@ fn build_docset_from_map_with_progress<'de, M>(docset_name: &str, mut map: M) ->
     filename.chars().map(|c| if FORBIDDEN_CHARS.contains(&c) { '_' } else { c }).collect::<String>()
   }
 
-  let docset_path = get_docset_path(docset_name)?;
+  let mut fulltext_index = FulltextIndex::default();
   let mut unpacked_amount = 1;
 
   while let Some((file_path, contents)) =
@@ -197,6 +483,7 @@ fn build_docset_from_map_with_progress<'de, M>(docset_name: &str, mut map: M) ->
   {
     #[cfg(target_family = "windows")]
     let file_path = sanitize_filename_for_windows(file_path);
+    let item = file_path.clone();
     let file_path = PathBuf::from(file_path);
 
     if let Some(parent) = file_path.parent() {
@@ -218,26 +505,38 @@ fn build_docset_from_map_with_progress<'de, M>(docset_name: &str, mut map: M) ->
     let mut writer = BufWriter::new(file);
 
     let sanitized_contents = sanitize_html_line(contents);
+    let sanitized_contents = sanitized_contents.trim();
+
+    fulltext_index.add_page(item, sanitized_contents);
 
-    writer.write_all(sanitized_contents.trim().as_bytes())
+    writer.write_all(sanitized_contents.as_bytes())
           .map_err(|err| format!("Could not write to `{}`: {err}", file_path.display()))?;
 
-    print!("\rUnpacked {unpacked_amount} files...");
-    stdout().flush().map_err(|err| format!("Could not flush stdout:{err}"))?;
+    // Pages from a previous download may be pre-rendered, remove them if they
+    // are not replaced.
+    match prerender.then(|| prerender_html(sanitized_contents)).flatten() {
+      Some(page) => write_prerendered_page(&file_path, &page)?,
+      None => remove_prerendered_page(&file_path)?,
+    }
+
+    progress.update(&format!("Unpacked {unpacked_amount} files..."))?;
 
     unpacked_amount += 1;
   }
-  println!();
+  progress.finish_line();
 
-  Ok(())
+  write_fulltext_index(docset_path, &fulltext_index)
 }
 
-struct FileVisitor
+struct FileVisitor<'a>
 {
-  docset_name: String,
+  docset_name: &'a str,
+  docset_path: &'a Path,
+  prerender: bool,
+  progress: &'a Progress<'a>,
 }
 
-impl<'de> Visitor<'de> for FileVisitor
+impl<'de> Visitor<'de> for FileVisitor<'_>
 {
   type Value = ();
 
@@ -249,7 +548,10 @@ impl<'de> Visitor<'de> for FileVisitor
   fn visit_map<M>(self, map: M) -> Result<Self::Value, M::Error>
     where M: MapAccess<'de>
   {
-    build_docset_from_map_with_progress(&self.docset_name, map).map_err(|err| {
+    build_docset_from_map_with_progress(self.docset_path,
+                                        map,
+                                        self.prerender,
+                                        self.progress).map_err(|err| {
                                                                  Error::custom(format!(
         "Error while building `{}`: {err}",
         self.docset_name
@@ -259,31 +561,396 @@ impl<'de> Visitor<'de> for FileVisitor
   }
 }
 
-fn build_docset_from_db_json(docset_name: &String) -> ResultS
+// Writes pages from `db_json_path` into `docset_path`.
+fn build_docset_from_db_json(docset_name: &str,
+                             db_json_path: &Path,
+                             docset_path: &Path,
+                             prerender: bool,
+                             progress: &Progress)
+                             -> ResultS
 {
-  let docset_path = get_docset_path(docset_name)?;
-  let db_json_path = docset_path.join("db").with_extension("json");
-
-  let file = File::open(&db_json_path).map_err(|err| {
-                                        format!("Could not open `{}`: {err}",
-                                                db_json_path.display())
-                                      })?;
+  let file = File::open(db_json_path).map_err(|err| {
+                                       format!("Could not open `{}`: {err}", db_json_path.display())
+                                     })?;
 
   let reader = BufReader::new(file);
 
   let mut db_json_deserializer = serde_json::Deserializer::from_reader(reader);
 
-  let file_visitor = FileVisitor { docset_name: docset_name.to_owned() };
+  let file_visitor = FileVisitor { docset_name, docset_path, prerender, progress };
   db_json_deserializer.deserialize_map(file_visitor)
                       .map_err(|err| {
                         format!("Could not deserialize `{}`: {err}", db_json_path.display())
-                      })?;
+                      })
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum DownloadStatus
+{
+  Installed,
+  Updated,
+  UpToDate,
+  NotFound,
+  Failed,
+  RolledBack,
+  NoPreviousVersion,
+  ForbiddenName,
+}
+
+#[derive(Serialize)]
+struct JsonDownload<'a>
+{
+  docset: &'a str,
+  status: DownloadStatus,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  error: Option<String>,
+}
+
+pub(crate) fn write_docset_mtime(docset_path: &Path, mtime: u64) -> ResultS
+{
+  let mtime_path = docset_path.join(MTIME_FILENAME);
+  let mut mtime_file = File::create(&mtime_path).map_err(|err| {
+                                                  format!("Could not open `{}`: {err}",
+                                                          mtime_path.display())
+                                                })?;
+  write!(mtime_file, "{mtime}").map_err(|err| {
+                                 format!("Could not write to `{}`: {err}", mtime_path.display())
+                               })
+}
+
+fn move_directory(from: &Path, to: &Path) -> ResultS
+{
+  rename(from, to).map_err(|err| {
+                    format!("Could not move `{}` to `{}`: {err}", from.display(), to.display())
+                  })
+}
+
+fn create_parent_directory(path: &Path) -> ResultS
+{
+  if let Some(parent) = path.parent() {
+    create_dir_all(parent).map_err(|err| {
+                            format!("Cannot create `{}` directory: {err}", parent.display())
+                          })?;
+  }
+  Ok(())
+}
+
+// Puts a built docset in place of the current one. The current one is kept as
+// the previous version for `--rollback`.
+pub(crate) fn replace_docset_with_staged(docset_name: &str) -> ResultS
+{
+  let docset_path = get_docset_path(docset_name)?;
+  let staging_path = get_docset_staging_path(docset_name)?;
+  let previous_path = get_previous_docset_path(docset_name)?;
+
+  create_parent_directory(&docset_path)?;
+
+  let has_current_version = docset_path.exists();
+  if has_current_version {
+    create_parent_directory(&previous_path)?;
+    remove_dir_if_exists(&previous_path)?;
+    move_directory(&docset_path, &previous_path)?;
+  }
+
+  if let Err(err) = move_directory(&staging_path, &docset_path) {
+    if has_current_version {
+      move_directory(&previous_path, &docset_path)?;
+    }
+    return Err(err);
+  }
+
+  Ok(())
+}
+
+// Swaps the current and the previous version of a docset, so rolling back
+// twice restores the newer version. The staging directory is used to hold one
+// of them in between.
+fn rollback_docset(docset_name: &str) -> ResultS
+{
+  if !is_name_allowed(docset_name) {
+    return Err(format!("`{docset_name}` contains forbidden characters."));
+  }
+
+  let docset_path = get_docset_path(docset_name)?;
+  let staging_path = get_docset_staging_path(docset_name)?;
+  let previous_path = get_previous_docset_path(docset_name)?;
+
+  create_parent_directory(&docset_path)?;
+
+  if !docset_path.exists() {
+    return move_directory(&previous_path, &docset_path);
+  }
+
+  create_parent_directory(&staging_path)?;
+  remove_dir_if_exists(&staging_path)?;
+  move_directory(&docset_path, &staging_path)?;
+
+  if let Err(err) = move_directory(&previous_path, &docset_path) {
+    move_directory(&staging_path, &docset_path)?;
+    return Err(err);
+  }
+
+  move_directory(&staging_path, &previous_path)
+}
+
+// Downloads and builds a docset in a staging directory, so the current version
+// stays untouched until the new one is complete.
+fn install_docset(docset_name: &str,
+                  docs: &[DocsEntry],
+                  documents_url: &str,
+                  prerender: bool,
+                  is_update: bool,
+                  progress: &Progress)
+                  -> ResultS
+{
+  let entry = find_docset_in_docs(docset_name, docs).ok_or_else(|| {
+                  format!("Unable to find `{docset_name}` in `docs.json`.")
+                })?;
+  let staging_path = get_docset_staging_path(docset_name)?;
+
+  if is_update {
+    progress.message(&format!("Updating `{docset_name}`..."), "Updating...")?;
+  } else {
+    progress.message(&format!("Downloading `{docset_name}`..."), "Downloading...")?;
+  }
+  download_db_and_index_json_with_progress(entry, documents_url, &staging_path, progress)?;
+
+  let docset_path = get_docset_path(docset_name)?;
+  progress.message(&format!("Extracting to `{}`...", docset_path.display()), "Extracting...")?;
+  let db_json_path = staging_path.join("db.json");
+  build_docset_from_db_json(docset_name, &db_json_path, &staging_path, prerender, progress)?;
 
   remove_file(&db_json_path).map_err(|err| {
                               format!("Could not remove `{}` after building {docset_name}: {err}",
                                       db_json_path.display())
                             })?;
 
+  // Create a file that will store current version of the docset.
+  write_docset_mtime(&staging_path, entry.mtime)?;
+
+  replace_docset_with_staged(docset_name)
+}
+
+// Builds a docset from `db.json` and `index.json` that are already on disk,
+// e.g. from a DevDocs checkout, without `docs.json` or network. The version of
+// the docset is the modification time of `db.json`.
+fn install_docset_from_files(docset_name: &str,
+                             db_json_path: &Path,
+                             index_json_path: &Path,
+                             prerender: bool,
+                             progress: &Progress)
+                             -> ResultS
+{
+  if !is_name_allowed(docset_name) {
+    return Err(format!("`{docset_name}` contains forbidden characters."));
+  }
+
+  let db_json_metadata = metadata(db_json_path).map_err(|err| {
+                                                 format!("Could not read metadata of `{}`: {err}",
+                                                         db_json_path.display())
+                                               })?;
+  let mtime = db_json_metadata.modified()
+                              .unwrap_or(SystemTime::UNIX_EPOCH)
+                              .duration_since(SystemTime::UNIX_EPOCH)
+                              .map(|duration| duration.as_secs())
+                              .unwrap_or_default();
+
+  let staging_path = get_docset_staging_path(docset_name)?;
+  remove_dir_if_exists(&staging_path)?;
+  create_dir_all(&staging_path).map_err(|err| {
+                                 format!("Cannot create `{}` directory: {err}",
+                                         staging_path.display())
+                               })?;
+
+  let staged_index_json_path = staging_path.join("index.json");
+  copy(index_json_path, &staged_index_json_path).map_err(|err| {
+                                                  format!("Could not copy `{}` to `{}`: {err}",
+                                                          index_json_path.display(),
+                                                          staged_index_json_path.display())
+                                                })?;
+
+  progress.message(&format!("Extracting `{}` to `{}`...",
+                            db_json_path.display(),
+                            get_docset_path(docset_name)?.display()),
+                   "Extracting...")?;
+  build_docset_from_db_json(docset_name, db_json_path, &staging_path, prerender, progress)?;
+
+  write_docset_mtime(&staging_path, mtime)?;
+
+  replace_docset_with_staged(docset_name)?;
+
+  // Other subcommands expect the docset to be in `docs.json`, and local docsets
+  // are merged into it.
+  add_local_docs_entries(vec![DocsEntry::new_local(docset_name, mtime, db_json_metadata.len())])
+}
+
+// Finds `db.json` and `index.json` of each docset in `<directory>/<docset>`, or
+// in `directory` itself when there is only one docset.
+fn install_docsets_from_dir(docset_names: &[String],
+                            directory: &Path,
+                            prerender: bool,
+                            is_json: bool)
+                            -> ResultS
+{
+  let progress = if is_json { Progress::Hidden } else { Progress::Line };
+  let mut statuses = vec![];
+  let mut successful_installs = 0;
+  let mut failures = vec![];
+
+  for docset in docset_names {
+    let docset_directory = if docset_names.len() == 1 && directory.join("db.json").exists() {
+      directory.to_path_buf()
+    } else {
+      directory.join(docset)
+    };
+
+    let result = install_docset_from_files(docset,
+                                           &docset_directory.join("db.json"),
+                                           &docset_directory.join("index.json"),
+                                           prerender,
+                                           &progress);
+    match result {
+      Ok(()) => {
+        successful_installs += 1;
+        statuses.push(JsonDownload { docset, status: DownloadStatus::Installed, error: None });
+      }
+      Err(err) => {
+        statuses.push(JsonDownload { docset,
+                                     status: DownloadStatus::Failed,
+                                     error: Some(err.clone()) });
+        failures.push((docset, err));
+      }
+    }
+  }
+
+  if is_json {
+    print_json(&statuses)?;
+  }
+
+  // With `--json`, statuses already tell that there was nothing to do.
+  match successful_installs {
+    _ if is_json => {}
+    0 if failures.is_empty() => return Err("Nothing to do.".to_string()),
+    0 => {}
+    1 => println!("{BOLD}Install has successfully finished{RESET}."),
+    n => println!("{BOLD}{n} items were successfully installed{RESET}."),
+  }
+
+  match failures.as_slice() {
+    [] => Ok(()),
+    [(_, err)] if docset_names.len() == 1 => Err(err.to_owned()),
+    _ => {
+      for (docset, err) in &failures {
+        print_warning!("`{docset}`: {err}");
+      }
+      let failed_docsets =
+        failures.iter().map(|(docset, _)| format!("`{docset}`")).collect::<Vec<_>>();
+      Err(format!("{} of {} docsets failed to install: {}.",
+                  failures.len(),
+                  docset_names.len(),
+                  failed_docsets.join(", ")))
+    }
+  }
+}
+
+// Downloads docsets with `jobs` threads at once.
+//
+// -> Result of each docset, in the same order.
+fn install_docsets(docset_names: &[&str],
+                   docs: &[DocsEntry],
+                   documents_url: &str,
+                   prerender: bool,
+                   is_update: bool,
+                   jobs: usize,
+                   show_progress: bool)
+                   -> Result<Vec<ResultS>, String>
+{
+  let jobs = jobs.clamp(1, docset_names.len().max(1));
+  let board = (show_progress && jobs > 1).then(|| ProgressBoard::new(docset_names));
+  let next_docset = AtomicUsize::new(0);
+
+  let install_next_docsets = || -> Result<Vec<(usize, ResultS)>, String> {
+    let mut results = vec![];
+    loop {
+      let i = next_docset.fetch_add(1, Ordering::Relaxed);
+      let Some(docset_name) = docset_names.get(i) else {
+        return Ok(results);
+      };
+
+      let progress = match &board {
+        Some(board) => Progress::Board(board, i),
+        None if show_progress => Progress::Line,
+        None => Progress::Hidden,
+      };
+
+      let result =
+        install_docset(docset_name, docs, documents_url, prerender, is_update, &progress);
+      if let Some(board) = &board {
+        match &result {
+          Ok(_) if is_update => board.set(i, "Updated.", true)?,
+          Ok(_) => board.set(i, "Installed.", true)?,
+          Err(_) => board.set(i, "Failed.", true)?,
+        }
+      }
+      results.push((i, result));
+    }
+  };
+
+  let mut results = std::thread::scope(|scope| -> Result<Vec<_>, String> {
+    let workers = (0..jobs).map(|_| scope.spawn(install_next_docsets)).collect::<Vec<_>>();
+
+    let mut results = vec![];
+    for worker in workers {
+      let worker_results =
+        worker.join().map_err(|_| "A download thread has panicked".to_string())??;
+      results.extend(worker_results);
+    }
+    Ok(results)
+  })?;
+
+  results.sort_unstable_by_key(|(i, _)| *i);
+  Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+fn rollback_docsets(docset_names: &[String], is_json: bool) -> ResultS
+{
+  let mut statuses = vec![];
+  let mut successful_rollbacks = 0;
+
+  for docset in docset_names {
+    if !is_name_allowed(docset) {
+      print_warning!("`{docset}` contains forbidden characters.");
+      statuses.push(JsonDownload { docset, status: DownloadStatus::ForbiddenName, error: None });
+      continue;
+    }
+
+    if !get_previous_docset_path(docset)?.exists() {
+      print_warning!("There is no previous version of `{docset}` to roll back to.");
+      statuses.push(JsonDownload { docset,
+                                   status: DownloadStatus::NoPreviousVersion,
+                                   error: None });
+      continue;
+    }
+
+    if !is_json {
+      println!("Rolling back `{docset}`...");
+    }
+    rollback_docset(docset)?;
+    successful_rollbacks += 1;
+    statuses.push(JsonDownload { docset, status: DownloadStatus::RolledBack, error: None });
+  }
+
+  if is_json {
+    return print_json(&statuses);
+  }
+
+  match successful_rollbacks {
+    0 => return Err("Nothing to do.".to_string()),
+    1 => println!("{BOLD}Rollback has successfully finished{RESET}."),
+    _ => println!("{BOLD}{successful_rollbacks} items were successfully rolled back{RESET}."),
+  }
+
   Ok(())
 }
 
@@ -292,80 +959,186 @@ pub(crate) fn download<Args>(mut args: Args) -> ResultS
 {
   let mut flag_force;
   let mut flag_update_all;
+  let mut flag_mirror;
+  let mut flag_prerender;
+  let mut flag_no_prerender;
+  let mut flag_jobs;
+  let mut flag_rollback;
+  let mut flag_from_dir;
+  let mut flag_from_files;
+  let mut flag_json;
   let mut flag_help;
 
   let mut flags = flags![
-    flag_force: BoolFlag,      ["-f", "--force"],
-    flag_update_all: BoolFlag, ["-u", "--update-all"],
-    flag_help: BoolFlag,       ["--help"]
+    flag_force: BoolFlag,        ["-f", "--force"],
+    flag_update_all: BoolFlag,   ["-u", "--update-all"],
+    flag_mirror: StringFlag,     ["-m", "--mirror"],
+    flag_prerender: BoolFlag,    ["-p", "--prerender"],
+    flag_no_prerender: BoolFlag, ["--no-prerender"],
+    flag_jobs: StringFlag,       ["-j", "--jobs"],
+    flag_rollback: BoolFlag,     ["--rollback"],
+    flag_from_dir: StringFlag,   ["--from-dir"],
+    flag_from_files: BoolFlag,   ["--from-files"],
+    flag_json: BoolFlag,         ["--json"],
+    flag_help: BoolFlag,         ["--help"]
   ];
 
   let args = parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
+  flag_prerender |= !flag_no_prerender && get_config_bool("download.prerender")?;
+
+  let jobs = if flag_jobs.is_empty() {
+    get_config_number("download.jobs")?.unwrap_or(1) as usize
+  } else {
+    match flag_jobs.parse::<usize>() {
+      Ok(jobs) if jobs > 0 => jobs,
+      _ => return Err("Invalid number of jobs.".to_string()),
+    }
+  };
+
+  if flag_rollback {
+    if flag_help || args.is_empty() {
+      return show_download_help();
+    }
+    return rollback_docsets(&args, flag_json);
+  }
+
+  if !flag_from_dir.is_empty() {
+    if flag_help || args.is_empty() {
+      return show_download_help();
+    }
+    return install_docsets_from_dir(&args, Path::new(&flag_from_dir), flag_prerender, flag_json);
+  }
+
+  if flag_from_files {
+    let [docset, db_json, index_json] = args.as_slice() else {
+      return show_download_help();
+    };
+    let progress = if flag_json { Progress::Hidden } else { Progress::Line };
+    install_docset_from_files(docset,
+                              Path::new(db_json),
+                              Path::new(index_json),
+                              flag_prerender,
+                              &progress)?;
+    if flag_json {
+      print_json(&[JsonDownload { docset, status: DownloadStatus::Installed, error: None }])?;
+    } else {
+      println!("{BOLD}Install has successfully finished{RESET}.");
+    }
+    return Ok(());
+  }
+
+  let documents_url = get_documents_url(&flag_mirror)?;
   let docs = deserialize_docs_json()?;
-  let mut successful_downloads = 0;
+  let local_docsets = if flag_update_all { get_local_docsets()? } else { vec![] };
+  let mut statuses = vec![];
 
   if flag_update_all {
     if is_docs_json_old()? {
-      print_warning!("Your `docs.json` was updated more than a week ago. Run \
+      print_warning!("Your `docs.json` was updated more than {} days ago. Run \
                       `{PROGRAM_NAME} fetch` to retrieve a new list of \
-                      available docsets.");
+                      available docsets.",
+                     get_docs_json_max_age()?);
     }
     if !args.is_empty() {
       print_warning!("Arguments are ignored due to `--update-all` flag.");
     }
-    for ref docset in get_local_docsets()? {
+    for docset in &local_docsets {
       if is_docset_old(docset, &docs)? {
-        println!("Updating `{docset}`...");
-        download_db_and_index_json_with_progress(docset, &docs)?;
-        println!("Extracting to `{}`...", get_docset_path(docset)?.display());
-        build_docset_from_db_json(docset)?;
-        successful_downloads += 1;
+        statuses.push(JsonDownload { docset, status: DownloadStatus::Updated, error: None });
+      } else {
+        statuses.push(JsonDownload { docset, status: DownloadStatus::UpToDate, error: None });
       }
     }
+  } else {
+    if flag_help || args.is_empty() {
+      return show_download_help();
+    }
 
-    match successful_downloads {
-      0 => return Err("Nothing to do.".to_string()),
-      1 => println!("{BOLD}{successful_downloads} item was successfully updated{RESET}."),
-      _ => println!("{BOLD}{successful_downloads} items were successfully updated{RESET}."),
+    if !is_docs_json_exists()? {
+      return Err(format!("The list of available documents has not yet been \
+                          downloaded. Please run `{PROGRAM_NAME} fetch` first."));
     }
-    return Ok(());
-  }
 
-  if flag_help || args.is_empty() {
-    return show_download_help();
-  }
+    for docset in args.iter() {
+      // Don't print warnings when using with ls -n
+      if docset == "[downloaded]" {
+        continue;
+      }
 
-  if !is_docs_json_exists()? {
-    return Err(format!("The list of available documents has not yet been \
-                        downloaded. Please run `{PROGRAM_NAME} fetch` first."));
+      if !flag_force && is_docset_downloaded(docset)? && !is_docset_old(docset, &docs)? {
+        print_warning!("Docset `{docset}` is already downloaded and is of recent \
+                        version. If you still want to re-download it, re-run \
+                        this command with `--force`");
+        statuses.push(JsonDownload { docset, status: DownloadStatus::UpToDate, error: None });
+      } else if is_docset_in_docs_or_print_warning(docset, &docs) {
+        statuses.push(JsonDownload { docset, status: DownloadStatus::Installed, error: None });
+      } else {
+        statuses.push(JsonDownload { docset, status: DownloadStatus::NotFound, error: None });
+      }
+    }
   }
 
-  for docset in args.iter() {
-    // Don't print warnings when using with ls -n
-    if docset == "[downloaded]" {
-      continue;
-    }
+  let success_status =
+    if flag_update_all { DownloadStatus::Updated } else { DownloadStatus::Installed };
+
+  let mut pending_statuses =
+    statuses.iter_mut().filter(|status| status.status == success_status).collect::<Vec<_>>();
+  let pending_docsets = pending_statuses.iter().map(|status| status.docset).collect::<Vec<_>>();
+
+  let results = install_docsets(&pending_docsets,
+                                &docs,
+                                &documents_url,
+                                flag_prerender,
+                                flag_update_all,
+                                jobs,
+                                !flag_json)?;
 
-    if !flag_force && is_docset_downloaded(docset)? && !is_docset_old(docset, &docs)? {
-      print_warning!("Docset `{docset}` is already downloaded and is of recent \
-                      version. If you still want to re-download it, re-run \
-                      this command with `--force`");
-    } else if is_docset_in_docs_or_print_warning(docset, &docs) {
-      println!("Downloading `{docset}`...");
-      download_db_and_index_json_with_progress(docset, &docs)?;
-      println!("Extracting to `{}`...", get_docset_path(docset)?.display());
-      build_docset_from_db_json(docset)?;
-      successful_downloads += 1;
+  let mut successful_downloads = 0;
+  let mut failures = vec![];
+
+  for (status, result) in pending_statuses.iter_mut().zip(results) {
+    match result {
+      Ok(()) => successful_downloads += 1,
+      Err(err) => {
+        status.status = DownloadStatus::Failed;
+        status.error = Some(err.clone());
+        failures.push((status.docset, err));
+      }
     }
   }
 
+  if flag_json {
+    print_json(&statuses)?;
+  }
+
+  // With `--json`, statuses already tell that there was nothing to do.
   match successful_downloads {
-    0 => return Err("Nothing to do.".to_string()),
+    _ if flag_json => {}
+    0 if failures.is_empty() => return Err("Nothing to do.".to_string()),
+    0 => {}
+    1 if flag_update_all => println!("{BOLD}1 item was successfully updated{RESET}."),
+    _ if flag_update_all => {
+      println!("{BOLD}{successful_downloads} items were successfully updated{RESET}.")
+    }
     1 => println!("{BOLD}Install has successfully finished{RESET}."),
     _ => println!("{BOLD}{successful_downloads} items were successfully installed{RESET}."),
   }
 
-  Ok(())
+  match failures.as_slice() {
+    [] => Ok(()),
+    [(_, err)] if pending_docsets.len() == 1 => Err(err.to_owned()),
+    _ => {
+      for (docset, err) in &failures {
+        print_warning!("`{docset}`: {err}");
+      }
+      let failed_docsets =
+        failures.iter().map(|(docset, _)| format!("`{docset}`")).collect::<Vec<_>>();
+      Err(format!("{} of {} docsets failed to download: {}.",
+                  failures.len(),
+                  pending_docsets.len(),
+                  failed_docsets.join(", ")))
+    }
+  }
 }
 
 #[cfg(test)]
@@ -400,7 +1173,7 @@ mod tests
                 const: 1.39.0
             </span>
         </span>
-        <pre  >
+        <pre  data-language="rust">
             pub const fn new() -> Vec<T, Global>;
         </pre>
     </section>
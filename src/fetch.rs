@@ -1,18 +1,17 @@
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{BufWriter, Read};
 use std::path::PathBuf;
 
 use toiletcli::flags;
 use toiletcli::flags::*;
 
 use crate::common::{
-  create_program_directory, get_default_user_agent, get_flag_error, get_program_directory,
-  is_docs_json_exists, is_docs_json_old, write_to_logfile,
+  create_program_directory, get_docs_json_max_age, get_docs_json_url, get_flag_error,
+  get_program_directory, is_docs_json_exists, is_docs_json_old, open_url, write_to_logfile,
 };
 use crate::common::{DocsEntry, ResultS};
-use crate::common::{BOLD, DEFAULT_DOCS_JSON_LINK, GREEN, PROGRAM_NAME, RESET};
-
-use ureq::get;
+use crate::common::{BOLD, DOCS_JSON_URL_ENV_VARIABLE, GREEN, PROGRAM_NAME, RESET};
+use crate::print_warning;
 
 fn show_fetch_help() -> ResultS
 {
@@ -25,23 +24,24 @@ fn show_fetch_help() -> ResultS
 {GREEN}OPTIONS{RESET}
     -f, --force                     Force the download and overwrite
                                     `docs.json`.
-        --help                      Display help message."
+    -m, --mirror <url>              Fetch `docs.json` from a mirror. Accepts
+                                    `http://`, `https://` and `file://` URLs.
+        --help                      Display help message.
+
+  The mirror can also be set with ${DOCS_JSON_URL_ENV_VARIABLE} or with
+  `docs_json_url` in the config."
   );
   Ok(())
 }
 
-fn fetch_docs() -> Result<Vec<DocsEntry>, String>
+fn fetch_docs(docs_json_url: &str) -> Result<Vec<DocsEntry>, String>
 {
-  let response =
-    get(DEFAULT_DOCS_JSON_LINK).set("User-Agent", &get_default_user_agent())
-                               .set("Accept-Encoding", "gzip")
-                               .call()
-                               .map_err(|err| {
-                                 format!("Could not fetch `{DEFAULT_DOCS_JSON_LINK}`: {err}")
-                               })?;
+  let mut response =
+    open_url(docs_json_url).map_err(|err| format!("Could not fetch `{docs_json_url}`: {err}"))?;
 
-  let body =
-    response.into_string().map_err(|err| format!("Unable to read response body: {err}"))?;
+  let mut body = String::new();
+  response.read_to_string(&mut body)
+          .map_err(|err| format!("Unable to read response body: {err}"))?;
 
   let docs: Vec<DocsEntry> =
     serde_json::from_str(body.as_str()).map_err(|err| {
@@ -56,7 +56,9 @@ fn fetch_docs() -> Result<Vec<DocsEntry>, String>
   Ok(docs)
 }
 
-fn serialize_and_overwrite_docs(path: PathBuf, docs: Vec<DocsEntry>) -> Result<(), String>
+pub(crate) fn serialize_and_overwrite_docs(path: PathBuf,
+                                           docs: Vec<DocsEntry>)
+                                           -> Result<(), String>
 {
   let file =
     File::create(&path).map_err(|err| format!("Could not create `{}`: {err}", path.display()))?;
@@ -70,15 +72,41 @@ fn serialize_and_overwrite_docs(path: PathBuf, docs: Vec<DocsEntry>) -> Result<(
   Ok(())
 }
 
+// Replaces `docs.json` if it's missing or old. Network errors are only warned
+// about, so commands can go on with the old `docs.json`.
+pub(crate) fn auto_fetch_docs_json() -> ResultS
+{
+  if is_docs_json_exists()? && !is_docs_json_old()? {
+    return Ok(());
+  }
+
+  let docs_json_url = get_docs_json_url("")?;
+  let docs = match fetch_docs(&docs_json_url) {
+    Ok(docs) => docs,
+    Err(err) => {
+      print_warning!("Could not refresh `docs.json`: {err}");
+      return Ok(());
+    }
+  };
+
+  let program_path = get_program_directory()?;
+  if !program_path.exists() {
+    create_program_directory()?;
+  }
+  serialize_and_overwrite_docs(program_path.join("docs.json"), docs)
+}
+
 pub(crate) fn fetch<Args>(mut args: Args) -> ResultS
   where Args: Iterator<Item = String>
 {
   let mut flag_force;
+  let mut flag_mirror;
   let mut flag_help;
 
   let mut flags = flags![
-    flag_force: BoolFlag, ["-f", "--force"],
-    flag_help: BoolFlag,  ["--help"]
+    flag_force: BoolFlag,    ["-f", "--force"],
+    flag_mirror: StringFlag, ["-m", "--mirror"],
+    flag_help: BoolFlag,     ["--help"]
   ];
 
   parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
@@ -87,13 +115,15 @@ pub(crate) fn fetch<Args>(mut args: Args) -> ResultS
     return show_fetch_help();
   }
   if !flag_force && is_docs_json_exists()? && !is_docs_json_old()? {
-    return Err("It seems that your `docs.json` was updated less than a week \
-                ago. Run `fetch --force` to ignore this warning."
-                                                                 .to_string());
+    return Err(format!("It seems that your `docs.json` was updated less than {} \
+                        days ago. Run `fetch --force` to ignore this warning.",
+                       get_docs_json_max_age()?));
   }
 
-  println!("Fetching `{DEFAULT_DOCS_JSON_LINK}`...");
-  let docs = fetch_docs()?;
+  let docs_json_url = get_docs_json_url(&flag_mirror)?;
+
+  println!("Fetching `{docs_json_url}`...");
+  let docs = fetch_docs(&docs_json_url)?;
 
   let program_path = get_program_directory()?;
   if !program_path.exists() {
@@ -0,0 +1,320 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{read_dir, File};
+use std::io::{stdout, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use toiletcli::flags;
+use toiletcli::flags::*;
+
+use crate::common::ResultS;
+use crate::common::{
+  deserialize_docs_json, get_docset_pages_path, get_docset_path, get_flag_error, get_local_docsets,
+  is_docs_json_exists, is_docset_downloaded, make_sure_docset_is_in_docs,
+};
+use crate::common::{BOLD, DOC_PAGE_EXTENSION, GREEN, PROGRAM_NAME, RESET};
+use crate::print_warning;
+
+pub(crate) const FULLTEXT_INDEX_FILENAME: &str = "fulltext_index.json";
+
+fn show_index_help() -> ResultS
+{
+  println!(
+           "\
+{GREEN}USAGE{RESET}
+    {BOLD}{PROGRAM_NAME} index{RESET} [-OPTIONS] <docset1> [docset2, ..]
+    Rebuild the full-text index that is used by `search --precise`. Docsets
+    are indexed when they are downloaded.
+
+    {BOLD}{PROGRAM_NAME} index{RESET} --all
+    Rebuild indexes of all local docsets.
+
+{GREEN}OPTIONS{RESET}
+        --all                       Index all local docsets.
+        --help                      Display help message."
+  );
+  Ok(())
+}
+
+// Maps lowercase words from the HTML of docset pages to pages they appear on.
+// Markup is indexed too, because `search --precise` looks inside of it.
+#[derive(Serialize, Deserialize, Default)]
+pub(crate) struct FulltextIndex
+{
+  pages: Vec<String>,
+  // (page number, number of occurrences on the page)
+  words: BTreeMap<String, Vec<(u32, u32)>>,
+}
+
+// Splits text into lowercase words made of alphanumeric characters and `_`.
+pub(crate) fn get_text_words(text: &str) -> Vec<String>
+{
+  text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+      .filter(|word| !word.is_empty())
+      .map(|word| word.to_lowercase())
+      .collect()
+}
+
+impl FulltextIndex
+{
+  pub(crate) fn add_page(&mut self, item: String, html: &str)
+  {
+    let page_number = self.pages.len() as u32;
+    self.pages.push(item);
+
+    let mut counts = HashMap::<String, u32>::new();
+    for word in get_text_words(html) {
+      *counts.entry(word).or_default() += 1;
+    }
+
+    for (word, count) in counts {
+      self.words.entry(word).or_default().push((page_number, count));
+    }
+  }
+
+  pub(crate) fn pages(&self) -> &[String]
+  {
+    &self.pages
+  }
+
+  // Finds pages that contain every word, which should be lowercase. Words match
+  // the beginning of words on a page, or only whole words if `whole` is set.
+  //
+  // -> (item, total number of occurrences)
+  pub(crate) fn find_pages(&self, words: &[String], whole: bool) -> Vec<(&str, u32)>
+  {
+    let mut scores: Option<HashMap<u32, u32>> = None;
+
+    for word in words {
+      let mut word_scores = HashMap::<u32, u32>::new();
+
+      let occurrences: Box<dyn Iterator<Item = &Vec<(u32, u32)>>> = if whole {
+        Box::new(self.words.get(word).into_iter())
+      } else {
+        Box::new(self.words
+                     .range(word.to_owned()..)
+                     .take_while(|(indexed_word, _)| indexed_word.starts_with(word.as_str()))
+                     .map(|(_, pages)| pages))
+      };
+
+      for &(page_number, count) in occurrences.flatten() {
+        *word_scores.entry(page_number).or_default() += count;
+      }
+
+      scores =
+        Some(match scores {
+               None => word_scores,
+               Some(previous) => previous.into_iter()
+                                         .filter_map(|(page_number, score)| {
+                                           word_scores.get(&page_number)
+                                                      .map(|count| (page_number, score + count))
+                                         })
+                                         .collect(),
+             });
+    }
+
+    match scores {
+      Some(scores) => {
+        scores.into_iter()
+              .map(|(page_number, score)| (self.pages[page_number as usize].as_str(), score))
+              .collect()
+      }
+      None => self.pages.iter().map(|item| (item.as_str(), 0)).collect(),
+    }
+  }
+}
+
+// Same as `FulltextIndex::find_pages()` for a single page, so that docsets
+// without an index are searched the same way.
+//
+// -> total number of occurrences, or None if some word is not on the page
+pub(crate) fn score_page(html: &str, words: &[String], whole: bool) -> Option<u32>
+{
+  let mut index = FulltextIndex::default();
+  index.add_page(String::new(), html);
+  index.find_pages(words, whole).first().map(|(_, score)| *score)
+}
+
+pub(crate) fn write_fulltext_index(docset_path: &Path, index: &FulltextIndex) -> ResultS
+{
+  let index_path = docset_path.join(FULLTEXT_INDEX_FILENAME);
+
+  let file = File::create(&index_path).map_err(|err| {
+                                        format!("Could not create `{}`: {err}",
+                                                index_path.display())
+                                      })?;
+
+  let writer = BufWriter::new(file);
+  serde_json::to_writer(writer, index).map_err(|err| {
+                                        format!("Could not write `{}`: {err}", index_path.display())
+                                      })
+}
+
+// -> None if the docset was not indexed.
+pub(crate) fn read_fulltext_index(docset_name: &str) -> Result<Option<FulltextIndex>, String>
+{
+  let index_path = get_docset_path(docset_name)?.join(FULLTEXT_INDEX_FILENAME);
+
+  let file = match File::open(&index_path) {
+    Ok(file) => file,
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+    Err(err) => return Err(format!("Could not open `{}`: {err}", index_path.display())),
+  };
+
+  let reader = BufReader::new(file);
+  serde_json::from_reader(reader).map(Some).map_err(|err| {
+                                             format!("Could not deserialize `{}`: {err}. Try \
+                                                       running `{PROGRAM_NAME} index \
+                                                       {docset_name}`.",
+                                                     index_path.display())
+                                           })
+}
+
+fn build_fulltext_index_with_progress(docset_name: &str) -> ResultS
+{
+  fn visit_dir(docset_path: &Path,
+               path: &Path,
+               index: &mut FulltextIndex,
+               docset_name: &str)
+               -> ResultS
+  {
+    let dir = read_dir(path).map_err(|err| {
+                              format!("Could not read `{}` directory: {err}", path.display())
+                            })?;
+
+    for entry in dir {
+      let entry = entry.map_err(|err| format!("Could not read file: {err}"))?;
+      let entry_path = entry.path();
+
+      if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+        visit_dir(docset_path, &entry_path, index, docset_name)?;
+        continue;
+      }
+
+      if entry_path.extension().unwrap_or_default() != DOC_PAGE_EXTENSION {
+        continue;
+      }
+
+      let html = std::fs::read_to_string(&entry_path).map_err(|err| {
+                                                       format!("Could not read `{}`: {err}",
+                                                               entry_path.display())
+                                                     })?;
+
+      let item = entry_path.strip_prefix(docset_path)
+                           .map_err(|err| err.to_string())?
+                           .with_extension("")
+                           .display()
+                           .to_string();
+
+      index.add_page(item, &html);
+
+      print!("\rIndexed {} files from `{docset_name}`...", index.pages().len());
+      stdout().flush().map_err(|err| format!("Could not flush stdout: {err}"))?;
+    }
+
+    Ok(())
+  }
+
+  let pages_path = get_docset_pages_path(docset_name)?;
+  let mut index = FulltextIndex::default();
+
+  visit_dir(&pages_path, &pages_path, &mut index, docset_name)?;
+  println!();
+
+  write_fulltext_index(&get_docset_path(docset_name)?, &index)
+}
+
+pub(crate) fn index<Args>(mut args: Args) -> ResultS
+  where Args: Iterator<Item = String>
+{
+  let mut flag_all;
+  let mut flag_help;
+
+  let mut flags = flags![
+    flag_all: BoolFlag,  ["--all"],
+    flag_help: BoolFlag, ["--help"]
+  ];
+
+  let args = parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
+  if flag_help || (args.is_empty() && !flag_all) {
+    return show_index_help();
+  }
+
+  let docsets = if flag_all {
+    if !args.is_empty() {
+      print_warning!("Arguments are ignored due to `--all` flag.");
+    }
+    get_local_docsets()?
+  } else {
+    if !is_docs_json_exists()? {
+      return Err(format!("The list of available documents has not yet been \
+                          downloaded. Please run `{PROGRAM_NAME} fetch` first."));
+    }
+    for docset in &args {
+      if !is_docset_downloaded(docset)? {
+        make_sure_docset_is_in_docs(docset, &deserialize_docs_json()?)?;
+        return Err(format!("Docset `{docset}` is not downloaded. Try running \
+                            `{PROGRAM_NAME} download {docset}`."));
+      }
+    }
+    args
+  };
+
+  if docsets.is_empty() {
+    return Err("Nothing to do.".to_string());
+  }
+
+  for docset in &docsets {
+    build_fulltext_index_with_progress(docset)?;
+  }
+
+  println!("{BOLD}Indexing has successfully finished{RESET}.");
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_fulltext_index()
+  {
+    let mut index = FulltextIndex::default();
+    index.add_page("a".to_string(),
+                   "<p class=\"word\">Vec::push &amp; Vec::pop</p><p>push&nbsp;again</p>");
+    index.add_page("b".to_string(), "<h1>pushing</h1><code>vec_deque</code>");
+
+    let find = |query: &str, whole: bool| {
+      let mut pages = index.find_pages(&get_text_words(query), whole);
+      pages.sort_unstable();
+      pages
+    };
+
+    // Markup is searched too.
+    assert_eq!(find("class=\"word", false), vec![("a", 2)]);
+    assert_eq!(find("&amp;", false), vec![("a", 1)]);
+    assert_eq!(find("code", false), vec![("b", 2)]);
+
+    // Words match the beginning of words, and are counted.
+    assert_eq!(find("PUSH", false), vec![("a", 2), ("b", 1)]);
+    assert_eq!(find("push", true), vec![("a", 2)]);
+    assert_eq!(find("vec", false), vec![("a", 2), ("b", 1)]);
+    assert_eq!(find("ush", false), vec![]);
+    assert_eq!(find("nothing", false), vec![]);
+
+    // Every word has to be on a page.
+    assert_eq!(find("vec pop", false), vec![("a", 3)]);
+    assert_eq!(find("vec_deque push", false), vec![("b", 2)]);
+    assert_eq!(find("pop pushing", false), vec![]);
+    assert_eq!(find(" :: ", false), vec![("a", 0), ("b", 0)]);
+
+    // Pages are scored the same way without an index.
+    let html = "<p>Vec::push, Vec::pop, vector</p>";
+    assert_eq!(score_page(html, &get_text_words("vec push"), false), Some(4));
+    assert_eq!(score_page(html, &get_text_words("vec"), true), Some(2));
+    assert_eq!(score_page(html, &get_text_words("vec deque"), false), None);
+  }
+}
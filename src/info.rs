@@ -0,0 +1,249 @@
+use std::fs::read_dir;
+use std::path::Path;
+
+use serde::Serialize;
+
+use toiletcli::flags;
+use toiletcli::flags::*;
+
+use crate::common::{
+  deserialize_docs_json, find_docset_in_docs, get_docset_mtime, get_docset_path, get_flag_error,
+  get_html_text, is_docs_json_exists, is_docset_downloaded, make_sure_docset_is_in_docs,
+  print_json,
+};
+use crate::common::{DocsEntry, ResultS};
+use crate::common::{BOLD, GREEN, PROGRAM_NAME, RESET, YELLOW};
+
+fn show_info_help() -> ResultS
+{
+  println!(
+           "\
+{GREEN}USAGE{RESET}
+    {BOLD}{PROGRAM_NAME} info{RESET} [-OPTIONS] <docset1> [docset2, ..]
+    Show the name, release, links and attribution of docsets from
+    `docs.json`, and how the local version compares to the latest one.
+
+{GREEN}OPTIONS{RESET}
+        --json                      Print information as JSON.
+        --help                      Display help message."
+  );
+  Ok(())
+}
+
+// 1536 -> 1.5 KiB
+fn format_size(bytes: u64) -> String
+{
+  const UNITS: &[&str] = &["KiB", "MiB", "GiB"];
+
+  if bytes < 1024 {
+    return format!("{bytes} B");
+  }
+
+  let mut size = bytes as f64 / 1024.0;
+  let mut unit = 0;
+  while size >= 1024.0 && unit + 1 < UNITS.len() {
+    size /= 1024.0;
+    unit += 1;
+  }
+
+  format!("{size:.1} {}", UNITS[unit])
+}
+
+// Unix time -> `YYYY-MM-DD` in UTC.
+pub(crate) fn format_date(mtime: u64) -> String
+{
+  // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+  let days = (mtime / (60 * 60 * 24)) as i64 + 719468;
+  let era = days.div_euclid(146097);
+  let day_of_era = days.rem_euclid(146097);
+  let year_of_era =
+    (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let shifted_month = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+  let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+  let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+  format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn get_directory_size(path: &Path) -> Result<u64, String>
+{
+  let dir =
+    read_dir(path).map_err(|err| format!("Could not read `{}` directory: {err}", path.display()))?;
+
+  let mut size = 0;
+  for entry in dir {
+    let entry = entry.map_err(|err| format!("Could not read `{}`: {err}", path.display()))?;
+    let metadata = entry.metadata()
+                        .map_err(|err| {
+                          format!("Could not read metadata of `{}`: {err}", entry.path().display())
+                        })?;
+
+    size += if metadata.is_dir() { get_directory_size(&entry.path())? } else { metadata.len() };
+  }
+
+  Ok(size)
+}
+
+#[derive(Serialize)]
+struct JsonLinks<'a>
+{
+  home: &'a str,
+  code: &'a str,
+}
+
+#[derive(Serialize)]
+struct JsonInfo<'a>
+{
+  slug: &'a str,
+  // These are `None` for local docsets which are not in `docs.json`.
+  name: Option<&'a str>,
+  r#type: Option<&'a str>,
+  version: Option<&'a str>,
+  release: Option<&'a str>,
+  links: Option<JsonLinks<'a>>,
+  // Without HTML tags.
+  attribution: Option<String>,
+  db_size: Option<u64>,
+  mtime: Option<u64>,
+  downloaded: bool,
+  // These are `None` if the docset is not downloaded.
+  local_mtime: Option<u64>,
+  disk_size: Option<u64>,
+}
+
+fn get_json_info<'a>(slug: &'a str, entry: Option<&'a DocsEntry>) -> Result<JsonInfo<'a>, String>
+{
+  let downloaded = is_docset_downloaded(&slug.to_string())?;
+
+  let (local_mtime, disk_size) = if downloaded {
+    (Some(get_docset_mtime(slug)?), Some(get_directory_size(&get_docset_path(slug)?)?))
+  } else {
+    (None, None)
+  };
+
+  let non_empty = |s: &'a String| Some(s.as_str()).filter(|s| !s.is_empty());
+
+  Ok(JsonInfo { slug,
+                name: entry.and_then(|e| non_empty(&e.name)),
+                r#type: entry.and_then(|e| non_empty(&e.doctype)),
+                version: entry.map(|e| e.version.as_str()),
+                release: entry.and_then(|e| non_empty(&e.release)),
+                links: entry.map(|e| JsonLinks { home: &e.links.home, code: &e.links.code }),
+                attribution: entry.map(|e| get_html_text(&e.attribution))
+                                  .filter(|s| !s.is_empty()),
+                db_size: entry.map(|e| e.db_size),
+                mtime: entry.map(|e| e.mtime),
+                downloaded,
+                local_mtime,
+                disk_size })
+}
+
+fn print_info(info: &JsonInfo)
+{
+  const WIDTH: usize = 16;
+
+  let print_field = |label: &str, value: &str| {
+    if !value.is_empty() {
+      println!("    {:<WIDTH$}{value}", format!("{label}:"));
+    }
+  };
+
+  match info.name {
+    Some(name) => println!("{BOLD}{name}{RESET} ({})", info.slug),
+    None => println!("{BOLD}{}{RESET}", info.slug),
+  }
+
+  print_field("Release", info.release.unwrap_or_default());
+  if let Some(links) = &info.links {
+    print_field("Home", links.home);
+    print_field("Code", links.code);
+  }
+  print_field("Attribution", info.attribution.as_deref().unwrap_or_default());
+  if let Some(db_size) = info.db_size.filter(|size| *size > 0) {
+    print_field("Download size", &format_size(db_size));
+  }
+  if let Some(mtime) = info.mtime {
+    print_field("Latest version", &format_date(mtime));
+  }
+
+  match (info.local_mtime, info.mtime) {
+    (Some(local_mtime), Some(mtime)) if local_mtime < mtime => {
+      print_field("Installed",
+                  &format!("{} {YELLOW}(update with `{PROGRAM_NAME} download {}`){RESET}",
+                           format_date(local_mtime),
+                           info.slug));
+    }
+    // Docsets from older versions of dedoc don't know their version.
+    (Some(0), _) => print_field("Installed", "yes"),
+    (Some(local_mtime), _) => print_field("Installed", &format_date(local_mtime)),
+    (None, _) => print_field("Installed", "no"),
+  }
+  if let Some(disk_size) = info.disk_size {
+    print_field("Size on disk", &format_size(disk_size));
+  }
+}
+
+pub(crate) fn info<Args>(mut args: Args) -> ResultS
+  where Args: Iterator<Item = String>
+{
+  let mut flag_json;
+  let mut flag_help;
+
+  let mut flags = flags![
+    flag_json: BoolFlag, ["--json"],
+    flag_help: BoolFlag, ["--help"]
+  ];
+
+  let args = parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
+  if flag_help || args.is_empty() {
+    return show_info_help();
+  }
+
+  if !is_docs_json_exists()? {
+    return Err(format!("The list of available documents has not yet been \
+                        downloaded. Please run `{PROGRAM_NAME} fetch` first."));
+  }
+  let docs = deserialize_docs_json()?;
+
+  let mut infos = vec![];
+  for docset in &args {
+    let entry = find_docset_in_docs(docset, &docs);
+    if entry.is_none() && !is_docset_downloaded(docset)? {
+      make_sure_docset_is_in_docs(docset, &docs)?;
+    }
+    infos.push(get_json_info(docset, entry)?);
+  }
+
+  if flag_json {
+    return print_json(&infos);
+  }
+
+  for (i, info) in infos.iter().enumerate() {
+    if i > 0 {
+      println!();
+    }
+    print_info(info);
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_format_size_and_date()
+  {
+    assert_eq!(format_size(34), "34 B");
+    assert_eq!(format_size(2726), "2.7 KiB");
+    assert_eq!(format_size(46313067), "44.2 MiB");
+
+    assert_eq!(format_date(0), "1970-01-01");
+    assert_eq!(format_date(951782400), "2000-02-29");
+    assert_eq!(format_date(1792268850), "2026-10-17");
+  }
+}
@@ -1,11 +1,15 @@
+use serde::Serialize;
+
 use toiletcli::flags;
 use toiletcli::flags::*;
 
 use crate::common::{
-  deserialize_docs_json, get_flag_error, get_local_docsets, is_docs_json_exists,
+  deserialize_docs_json, find_docset_in_docs, get_docset_mtime, get_flag_error, get_local_docsets,
+  is_docs_json_exists, print_json,
 };
-use crate::common::{is_docset_downloaded, make_sure_docset_is_in_docs, ResultS};
+use crate::common::{is_docset_downloaded, make_sure_docset_is_in_docs, DocsEntry, ResultS};
 use crate::common::{BOLD, GREEN, PROGRAM_NAME, RESET};
+use crate::config::get_config_bool;
 use crate::print_warning;
 
 fn show_list_help() -> ResultS
@@ -27,14 +31,85 @@ fn show_list_help() -> ResultS
     -n, --newlines                  Print each docset on a separate line.
     -d, --no-labels                 Don't print `[downloaded]` labels.
         --porcelain                 Same as -nd.
+        --labels                    Print labels, even if `list.no_labels` is
+                                    set in the config.
+        --long                      Print each docset on a separate line
+                                    with its release and name.
+        --json                      Print docsets with their versions as
+                                    JSON.
     -s, --search <query>            Filter docsets based on a query.
     -e, --exists <docset>           Fail and print message with cause if 
                                     specified docset does not exist.
+        --no-<option>               Turn off an option that is set in the
+                                    config, like `--no-all`.
         --help                      Display help message."
   );
   Ok(())
 }
 
+#[derive(Serialize)]
+struct JsonDocset<'a>
+{
+  name: &'a str,
+  // These are `None` for local docsets which are not in `docs.json`.
+  version: Option<&'a str>,
+  release: Option<&'a str>,
+  mtime: Option<u64>,
+  downloaded: bool,
+  // Version of the local docset, `None` if it's not downloaded.
+  local_mtime: Option<u64>,
+}
+
+fn get_json_docset<'a>(name: &'a str,
+                       docs: &'a [DocsEntry],
+                       local_docsets: &[String])
+                       -> Result<JsonDocset<'a>, String>
+{
+  let entry = find_docset_in_docs(name, docs);
+  let downloaded = local_docsets.iter().any(|docset| docset == name);
+
+  Ok(JsonDocset { name,
+                  version: entry.map(|e| e.version.as_str()),
+                  release: entry.map(|e| e.release.as_str()),
+                  mtime: entry.map(|e| e.mtime),
+                  downloaded,
+                  local_mtime: if downloaded { Some(get_docset_mtime(name)?) } else { None } })
+}
+
+// One docset per line, with columns aligned.
+fn print_long_list(json_docsets: &[JsonDocset], docs: &[DocsEntry], no_labels: bool) -> ResultS
+{
+  if json_docsets.is_empty() {
+    return Err("Nothing to do.".to_string());
+  }
+
+  let slug_width = json_docsets.iter().map(|docset| docset.name.len()).max().unwrap_or_default();
+  let release_width = json_docsets.iter()
+                                  .map(|docset| docset.release.unwrap_or_default().len())
+                                  .max()
+                                  .unwrap_or_default();
+
+  for docset in json_docsets {
+    let name =
+      find_docset_in_docs(docset.name, docs).map(|entry| entry.name.as_str()).unwrap_or_default();
+    let release = docset.release.unwrap_or_default();
+
+    let mut line = format!("{:<slug_width$}  {release:<release_width$}  {name}", docset.name);
+    if docset.downloaded && !no_labels {
+      let is_old = docset.mtime.zip(docset.local_mtime).is_some_and(|(mtime, local)| local < mtime);
+      line += if is_old { "  [outdated]" } else { "  [downloaded]" };
+    }
+
+    if docset.downloaded {
+      println!("{GREEN}{}{RESET}", line.trim_end());
+    } else {
+      println!("{}", line.trim_end());
+    }
+  }
+
+  Ok(())
+}
+
 pub(crate) fn list<Args>(mut args: Args) -> ResultS
   where Args: Iterator<Item = String>
 {
@@ -46,18 +121,30 @@ pub(crate) fn list<Args>(mut args: Args) -> ResultS
   let mut flag_search;
   let mut flag_porcelain;
   let mut flag_exists;
+  let mut flag_long;
+  let mut flag_json;
+  let mut flag_no_all;
+  let mut flag_no_newlines;
+  let mut flag_no_porcelain;
+  let mut flag_force_labels;
   let mut flag_help;
 
   let mut flags = flags![
-    flag_all: BoolFlag,       ["-a", "--all"],
-    flag_local: BoolFlag,     ["-l", "--local"],
-    flag_nonlocal: BoolFlag,  ["-o", "--non-local"],
-    flag_newlines: BoolFlag,  ["-n", "--newlines"],
-    flag_labels: BoolFlag,    ["-d", "--no-labels"],
-    flag_search: StringFlag,  ["-s", "--search"],
-    flag_porcelain: BoolFlag, ["--porcelain"],
-    flag_exists: StringFlag,  ["-e", "--exists"],
-    flag_help: BoolFlag,      ["--help"]
+    flag_all: BoolFlag,          ["-a", "--all"],
+    flag_local: BoolFlag,        ["-l", "--local"],
+    flag_nonlocal: BoolFlag,     ["-o", "--non-local"],
+    flag_newlines: BoolFlag,     ["-n", "--newlines"],
+    flag_labels: BoolFlag,       ["-d", "--no-labels"],
+    flag_search: StringFlag,     ["-s", "--search"],
+    flag_porcelain: BoolFlag,    ["--porcelain"],
+    flag_exists: StringFlag,     ["-e", "--exists"],
+    flag_long: BoolFlag,         ["--long"],
+    flag_json: BoolFlag,         ["--json"],
+    flag_no_all: BoolFlag,       ["--no-all"],
+    flag_no_newlines: BoolFlag,  ["--no-newlines"],
+    flag_no_porcelain: BoolFlag, ["--no-porcelain"],
+    flag_force_labels: BoolFlag, ["--labels"],
+    flag_help: BoolFlag,         ["--help"]
   ];
 
   let args = parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
@@ -68,6 +155,11 @@ pub(crate) fn list<Args>(mut args: Args) -> ResultS
     print_warning!("Arguments were not used.");
   }
 
+  flag_all |= !flag_no_all && get_config_bool("list.all")?;
+  flag_newlines |= !flag_no_newlines && get_config_bool("list.newlines")?;
+  flag_labels |= !flag_force_labels && get_config_bool("list.no_labels")?;
+  flag_porcelain |= !flag_no_porcelain && get_config_bool("list.porcelain")?;
+
   if !is_docs_json_exists()? {
     return Err(format!(
       "The list of available documents has not yet been downloaded. \
@@ -108,6 +200,35 @@ pub(crate) fn list<Args>(mut args: Args) -> ResultS
     return Ok(());
   }
 
+  if flag_json || flag_long {
+    let docs = deserialize_docs_json()?;
+    let mut json_docsets = vec![];
+
+    let names: Vec<&str> = if flag_local {
+      local_docsets.iter().map(String::as_str).collect()
+    } else {
+      docs.iter().map(|entry| entry.slug.as_str()).collect()
+    };
+
+    for name in names {
+      if should_filter && !name.contains(&flag_search) {
+        continue;
+      }
+      if !flag_local && !flag_all && name.contains('~') {
+        continue;
+      }
+      if flag_nonlocal && local_docsets.iter().any(|docset| docset == name) {
+        continue;
+      }
+      json_docsets.push(get_json_docset(name, &docs, &local_docsets)?);
+    }
+
+    if flag_json {
+      return print_json(&json_docsets);
+    }
+    return print_long_list(&json_docsets, &docs, flag_labels);
+  }
+
   let mut first_result = true;
 
   if flag_local {
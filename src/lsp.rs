@@ -0,0 +1,501 @@
+use std::collections::HashMap;
+use std::io::{stdin, stdout, BufRead, Write};
+
+use serde_json::{json, Value};
+
+use toiletcli::flags;
+use toiletcli::flags::*;
+
+use crate::common::ResultS;
+use crate::common::{
+  encode_percent_encoding, get_docset_page_path, get_flag_error, get_local_docsets,
+  is_docset_downloaded, split_to_item_and_fragment, translate_docset_html_file_to_text,
+};
+use crate::common::{BOLD, DEFAULT_WIDTH, GREEN, PROGRAM_NAME, RESET};
+use crate::config::get_config_value;
+use crate::print_warning;
+use crate::search::{deserialize_index_json, IndexEntry};
+
+fn show_lsp_help() -> ResultS
+{
+  println!(
+           "\
+{GREEN}USAGE{RESET}
+    {BOLD}{PROGRAM_NAME} lsp{RESET} [-OPTIONS] [language:docset1,docset2 ..]
+    Run a language server on stdin and stdout. It shows documentation of the
+    symbol under the cursor on hover, and finds pages as workspace symbols.
+
+    Languages are IDs from the editor, like `rust` or `cpp`. Languages which
+    have no docsets from arguments or `lsp.docsets` config use local docsets
+    with the same name, like `rust` or `python~3.12` for `python`.
+
+{GREEN}OPTIONS{RESET}
+        --help                      Display help message."
+  );
+  Ok(())
+}
+
+// Long pages are cut in hovers.
+const MAX_HOVER_LINES: usize = 60;
+const MAX_WORKSPACE_SYMBOLS: usize = 100;
+
+// See https://microsoft.github.io/language-server-protocol/specification.
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INTERNAL_ERROR: i64 = -32603;
+const TEXT_DOCUMENT_SYNC_FULL: i64 = 1;
+const SYMBOL_KIND_FILE: i64 = 1;
+
+// `rust:rust c:c,cpp` -> [(rust, [rust]), (c, [c, cpp])]
+fn parse_language_docsets<'a>(arguments: impl Iterator<Item = &'a str>)
+                              -> Result<Vec<(String, Vec<String>)>, String>
+{
+  let mut language_docsets = vec![];
+
+  for argument in arguments {
+    let Some((language, docsets)) = argument.split_once(':') else {
+      return Err(format!("Invalid docsets for a language `{argument}`, expected \
+                          `language:docset1,docset2`."));
+    };
+    let docsets =
+      docsets.split(',').filter(|docset| !docset.is_empty()).map(str::to_string).collect();
+    language_docsets.push((language.to_string(), docsets));
+  }
+
+  Ok(language_docsets)
+}
+
+// -> (symbol with `::` and `.` before it, the word itself)
+//
+// `character` is in UTF-16 code units, as LSP wants it.
+fn get_symbol_at_position(line: &str, character: usize) -> Option<(String, String)>
+{
+  let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+
+  let chars = line.chars().collect::<Vec<char>>();
+
+  let mut utf16_position = 0;
+  let mut cursor = chars.len();
+  for (i, c) in chars.iter().enumerate() {
+    if utf16_position >= character {
+      cursor = i;
+      break;
+    }
+    utf16_position += c.len_utf16();
+  }
+
+  // The cursor can be right after the word.
+  if !chars.get(cursor).is_some_and(|c| is_word_char(*c)) &&
+     cursor > 0 &&
+     is_word_char(chars[cursor - 1])
+  {
+    cursor -= 1;
+  }
+  if !chars.get(cursor).is_some_and(|c| is_word_char(*c)) {
+    return None;
+  }
+
+  let mut start = cursor;
+  while start > 0 && is_word_char(chars[start - 1]) {
+    start -= 1;
+  }
+  let mut end = cursor;
+  while end < chars.len() && is_word_char(chars[end]) {
+    end += 1;
+  }
+
+  let word = chars[start..end].iter().collect::<String>();
+
+  let mut qualified_start = start;
+  loop {
+    let separator =
+      if qualified_start >= 2 && chars[qualified_start - 2..qualified_start] == [':', ':'] {
+        2
+      } else if qualified_start >= 1 && chars[qualified_start - 1] == '.' {
+        1
+      } else {
+        break;
+      };
+
+    let mut new_start = qualified_start - separator;
+    while new_start > 0 && is_word_char(chars[new_start - 1]) {
+      new_start -= 1;
+    }
+    if new_start == qualified_start - separator {
+      break;
+    }
+    qualified_start = new_start;
+  }
+
+  let qualified = chars[qualified_start..end].iter().collect::<String>();
+
+  Some((qualified, word))
+}
+
+// `Vec::new()` -> `vec::new`
+fn normalize_symbol_name(name: &str) -> String
+{
+  name.trim().trim_end_matches("()").to_lowercase()
+}
+
+// Whether `name` is `symbol` with something like `std::` or `str.` before it.
+fn ends_with_symbol(name: &str, symbol: &str) -> bool
+{
+  name.strip_suffix(symbol)
+      .is_some_and(|rest| rest.ends_with(|c: char| !c.is_alphanumeric() && c != '_'))
+}
+
+struct LanguageServer
+{
+  language_docsets: Vec<(String, Vec<String>)>,
+  local_docsets: Vec<String>,
+  // uri -> (language, text)
+  documents: HashMap<String, (String, String)>,
+  indexes: HashMap<String, Vec<IndexEntry>>,
+}
+
+impl LanguageServer
+{
+  fn get_language_docsets(&self, language: &str) -> Vec<String>
+  {
+    if let Some((_, docsets)) = self.language_docsets.iter().find(|(l, _)| l == language) {
+      return docsets.clone();
+    }
+
+    let versioned_prefix = format!("{language}~");
+    self.local_docsets
+        .iter()
+        .filter(|docset| *docset == language || docset.starts_with(&versioned_prefix))
+        .cloned()
+        .collect()
+  }
+
+  fn get_index(&mut self, docset: &str) -> &[IndexEntry]
+  {
+    if !self.indexes.contains_key(docset) {
+      let entries = match deserialize_index_json(docset) {
+        Ok(index) => index.entries,
+        Err(err) => {
+          print_warning!("{err}");
+          vec![]
+        }
+      };
+      self.indexes.insert(docset.to_string(), entries);
+    }
+
+    &self.indexes[docset]
+  }
+
+  // -> (docset, entry) that matches best, checking the symbol with `::` and `.`
+  // first.
+  fn find_symbol(&mut self,
+                 docsets: &[String],
+                 qualified: &str,
+                 word: &str)
+                 -> Option<(String, String, String)>
+  {
+    let qualified = normalize_symbol_name(qualified);
+    let word = normalize_symbol_name(word);
+
+    let is_matches: [&dyn Fn(&str) -> bool; 4] = [&|name| name == qualified,
+                                                  &|name| name == word,
+                                                  &|name| ends_with_symbol(name, &qualified),
+                                                  &|name| ends_with_symbol(name, &word)];
+
+    for is_match in is_matches {
+      for docset in docsets {
+        if let Some(entry) =
+          self.get_index(docset).iter().find(|entry| is_match(&normalize_symbol_name(&entry.name)))
+        {
+          return Some((docset.clone(), entry.name.clone(), entry.path.clone()));
+        }
+      }
+    }
+
+    None
+  }
+
+  fn hover(&mut self, params: &Value) -> Result<Value, String>
+  {
+    let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+    let line = params["position"]["line"].as_u64().unwrap_or_default() as usize;
+    let character = params["position"]["character"].as_u64().unwrap_or_default() as usize;
+
+    let Some((language, text)) = self.documents.get(uri) else {
+      return Ok(Value::Null);
+    };
+    let Some((qualified, word)) =
+      text.lines().nth(line).and_then(|line| get_symbol_at_position(line, character))
+    else {
+      return Ok(Value::Null);
+    };
+
+    let docsets = self.get_language_docsets(language);
+    let Some((docset, name, path)) = self.find_symbol(&docsets, &qualified, &word) else {
+      return Ok(Value::Null);
+    };
+
+    let (item, fragment) = split_to_item_and_fragment(path)?;
+    let page_path = get_docset_page_path(&docset, &item)?;
+    let (page, _) = translate_docset_html_file_to_text(page_path,
+                                                       Some((&docset, &item)),
+                                                       fragment.as_ref(),
+                                                       DEFAULT_WIDTH,
+                                                       false,
+                                                       false)?;
+
+    let mut lines = page.trim_end().lines().take(MAX_HOVER_LINES + 1).collect::<Vec<&str>>();
+    if lines.len() > MAX_HOVER_LINES {
+      lines[MAX_HOVER_LINES] = "...";
+    }
+
+    let markdown = format!("**{name}** from `{docset}`\n\n```text\n{}\n```", lines.join("\n"));
+
+    Ok(json!({ "contents": { "kind": "markdown", "value": markdown } }))
+  }
+
+  fn workspace_symbol(&mut self, params: &Value) -> Result<Value, String>
+  {
+    let query = params["query"].as_str().unwrap_or_default().to_lowercase();
+
+    let mut docsets = self.language_docsets
+                          .iter()
+                          .flat_map(|(_, docsets)| docsets.clone())
+                          .collect::<Vec<String>>();
+    if docsets.is_empty() {
+      docsets = self.local_docsets.clone();
+    }
+    docsets.dedup();
+
+    let mut symbols = vec![];
+    for docset in &docsets {
+      let entries = self.get_index(docset)
+                        .iter()
+                        .filter(|entry| normalize_symbol_name(&entry.name).contains(&query))
+                        .map(|entry| (entry.name.clone(), entry.path.clone()))
+                        .collect::<Vec<(String, String)>>();
+
+      for (name, path) in entries {
+        if symbols.len() >= MAX_WORKSPACE_SYMBOLS {
+          return Ok(Value::Array(symbols));
+        }
+
+        let (item, _) = split_to_item_and_fragment(path)?;
+        let Ok(page_path) = get_docset_page_path(docset, &item) else {
+          continue;
+        };
+
+        symbols.push(json!({
+                       "name": name,
+                       "kind": SYMBOL_KIND_FILE,
+                       "containerName": docset,
+                       "location": {
+                         "uri": format!("file://{}",
+                                        encode_percent_encoding(&page_path.to_string_lossy())),
+                         "range": { "start": { "line": 0, "character": 0 },
+                                    "end": { "line": 0, "character": 0 } }
+                       }
+                     }));
+      }
+    }
+
+    Ok(Value::Array(symbols))
+  }
+
+  // -> None if the method is not supported.
+  fn handle_request(&mut self, method: &str, params: &Value) -> Option<Result<Value, String>>
+  {
+    let result = match method {
+      "initialize" => Ok(json!({
+                           "capabilities": {
+                             "textDocumentSync": TEXT_DOCUMENT_SYNC_FULL,
+                             "hoverProvider": true,
+                             "workspaceSymbolProvider": true
+                           },
+                           "serverInfo": { "name": PROGRAM_NAME }
+                         })),
+      "shutdown" => Ok(Value::Null),
+      "textDocument/hover" => self.hover(params),
+      "workspace/symbol" => self.workspace_symbol(params),
+      _ => return None,
+    };
+
+    Some(result)
+  }
+
+  fn handle_notification(&mut self, method: &str, params: &Value)
+  {
+    let document = &params["textDocument"];
+    let uri = document["uri"].as_str().unwrap_or_default().to_string();
+
+    match method {
+      "textDocument/didOpen" => {
+        let language = document["languageId"].as_str().unwrap_or_default().to_string();
+        let text = document["text"].as_str().unwrap_or_default().to_string();
+        self.documents.insert(uri, (language, text));
+      }
+      "textDocument/didChange" => {
+        // Changes are always the full text.
+        let text = params["contentChanges"].as_array()
+                                           .and_then(|changes| changes.last())
+                                           .and_then(|change| change["text"].as_str());
+        if let (Some(text), Some((_, document_text))) = (text, self.documents.get_mut(&uri)) {
+          *document_text = text.to_string();
+        }
+      }
+      "textDocument/didClose" => {
+        self.documents.remove(&uri);
+      }
+      _ => {}
+    }
+  }
+}
+
+// -> None at the end of input, or an error for a message that could not be
+// parsed, so the server can keep going.
+fn read_message(reader: &mut impl BufRead) -> Result<Option<Result<Value, String>>, String>
+{
+  let mut content_length = None;
+
+  loop {
+    let mut line = String::new();
+    if reader.read_line(&mut line).map_err(|err| format!("Could not read stdin: {err}"))? == 0 {
+      return Ok(None);
+    }
+
+    let line = line.trim_end();
+    if line.is_empty() {
+      break;
+    }
+    if let Some(length) = line.strip_prefix("Content-Length:") {
+      content_length = length.trim().parse::<usize>().ok();
+    }
+  }
+
+  let Some(content_length) = content_length else {
+    return Ok(Some(Err("Received a message without a valid `Content-Length`.".to_string())));
+  };
+
+  let mut content = vec![0; content_length];
+  reader.read_exact(&mut content).map_err(|err| format!("Could not read stdin: {err}"))?;
+
+  Ok(Some(serde_json::from_slice(&content).map_err(|err| {
+                                            format!("Could not parse a message: {err}")
+                                          })))
+}
+
+fn write_message(writer: &mut impl Write, message: &Value) -> ResultS
+{
+  let content = message.to_string();
+  write!(writer, "Content-Length: {}\r\n\r\n{content}", content.len())
+    .and_then(|_| writer.flush())
+    .map_err(|err| format!("Could not write to stdout: {err}"))
+}
+
+pub(crate) fn lsp<Args>(mut args: Args) -> ResultS
+  where Args: Iterator<Item = String>
+{
+  let mut flag_help;
+
+  let mut flags = flags![
+    flag_help: BoolFlag, ["--help"]
+  ];
+
+  let args = parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
+  if flag_help {
+    return show_lsp_help();
+  }
+
+  let language_docsets = if args.is_empty() {
+    parse_language_docsets(get_config_value("lsp.docsets")?.unwrap_or_default().split_whitespace())?
+  } else {
+    parse_language_docsets(args.iter().map(String::as_str))?
+  };
+
+  for docset in language_docsets.iter().flat_map(|(_, docsets)| docsets) {
+    if !is_docset_downloaded(docset)? {
+      print_warning!("Docset `{docset}` is not downloaded.");
+    }
+  }
+
+  let mut server = LanguageServer { language_docsets,
+                                    local_docsets: get_local_docsets()?,
+                                    documents: HashMap::new(),
+                                    indexes: HashMap::new() };
+
+  let mut reader = stdin().lock();
+  let mut writer = stdout().lock();
+
+  while let Some(message) = read_message(&mut reader)? {
+    let message = match message {
+      Ok(message) => message,
+      Err(err) => {
+        let response = json!({
+          "jsonrpc": "2.0",
+          "id": null,
+          "error": { "code": PARSE_ERROR, "message": err }
+        });
+        write_message(&mut writer, &response)?;
+        continue;
+      }
+    };
+
+    let method = message["method"].as_str().unwrap_or_default();
+    let params = &message["params"];
+
+    if method == "exit" {
+      break;
+    }
+
+    // Only requests have an `id`, and need a response.
+    let Some(id) = message.get("id") else {
+      server.handle_notification(method, params);
+      continue;
+    };
+
+    let response = match server.handle_request(method, params) {
+      Some(Ok(result)) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+      Some(Err(err)) => json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": INTERNAL_ERROR, "message": err }
+      }),
+      None => json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": METHOD_NOT_FOUND, "message": format!("Unknown method `{method}`") }
+      }),
+    };
+
+    write_message(&mut writer, &response)?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_get_symbol_at_position()
+  {
+    let line = "  let v = Vec::new();";
+    assert_eq!(get_symbol_at_position(line, 16), Some(("Vec::new".into(), "new".into())));
+    assert_eq!(get_symbol_at_position(line, 10), Some(("Vec".into(), "Vec".into())));
+    assert_eq!(get_symbol_at_position(line, 13), Some(("Vec".into(), "Vec".into())));
+    assert_eq!(get_symbol_at_position(line, 0), None);
+    assert_eq!(get_symbol_at_position("ß.äpfel", 3), Some(("ß.äpfel".into(), "äpfel".into())));
+    assert_eq!(get_symbol_at_position("🦀 os.path", 8), Some(("os.path".into(), "path".into())));
+
+    assert!(ends_with_symbol("std::vec::vec::new", "vec::new"));
+    assert!(!ends_with_symbol("std::vec::vec::renew", "new"));
+    assert_eq!(normalize_symbol_name("str.split()"), "str.split");
+
+    assert_eq!(parse_language_docsets(["c:c,cpp", "rust:rust"].into_iter()),
+               Ok(vec![("c".into(), vec!["c".into(), "cpp".into()]),
+                       ("rust".into(), vec!["rust".into()])]));
+    assert!(parse_language_docsets(["rust"].into_iter()).is_err());
+  }
+}
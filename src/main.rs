@@ -10,21 +10,47 @@ use common::get_flag_error;
 use common::ResultS;
 use common::{BOLD, BUILD_TYPE, GREEN, HEAD, PROGRAM_NAME, RED, RESET, UNDERLINE, VERSION};
 
+#[cfg(unix)]
+mod browse;
+mod build;
+mod bundle;
+mod completions;
+mod config;
+mod dash;
 mod download;
 mod fetch;
+mod index;
+mod info;
 mod list;
+mod lsp;
+mod man;
+mod markdown;
 mod open;
+mod outdated;
+mod prerender;
 mod remove;
 mod render;
 mod search;
+mod serve;
 
+#[cfg(unix)]
+use browse::browse;
+use build::build;
+use bundle::{export, import};
+use completions::{complete, completions};
+use config::{config, get_config_bool, get_config_value};
 use download::download;
-use fetch::fetch;
+use fetch::{auto_fetch_docs_json, fetch};
+use index::index;
+use info::info;
 use list::list;
+use lsp::lsp;
 use open::open;
+use outdated::outdated;
 use remove::remove;
 use render::render;
 use search::search;
+use serve::serve;
 
 #[cfg(debug_assertions)]
 use common::FLAG_INTEGRATION_TEST;
@@ -33,6 +59,11 @@ use common::FLAG_INTEGRATION_TEST;
 #[cfg(not(windows))]
 const OS: ! = panic!("Temple OS is not supported.");
 
+// Subcommands that read `docs.json`, which `--auto-fetch` refreshes first.
+const DOCS_JSON_SUBCOMMANDS: &[&str] = &["ls", "list", "if", "info", "dl", "download", "od",
+                                         "outdated", "ix", "index", "ex", "export", "ss",
+                                         "search", "op", "open", "br", "browse", "rr", "render"];
+
 fn show_short_version() -> ResultS
 {
   let commit = HEAD.unwrap_or("unknown commit");
@@ -69,18 +100,33 @@ fn show_help() -> ResultS
 {GREEN}SUBCOMMANDS{RESET}
     ft, fetch                       Fetch a list of available docsets.
     ls, list                        Display docsets from the fetched list.
+    if, info                        Show details about docsets.
     dl, download                    Download or update a docset from the list.
+    od, outdated                    Check which local docsets can be updated.
     rm, remove                      Delete local docsets.
+    ix, index                       Rebuild full-text indexes of docsets.
+    ex, export                      Pack local docsets into an archive.
+    im, import                      Install docsets from an archive.
+    bd, build                       Make a docset out of HTML pages.
     ss, search                      List or display docset pages that match a
                                     query.
     op, open                        Display docset pages.
+    br, browse                      Interactively search and read a docset.
     rr, render                      Render entire docsets to text.
+    cf, config                      View or change default options.
+        completions                 Print a completion script for a shell.
+        lsp                         Run a language server for editors.
+        serve                       Serve local docsets to a browser.
 
   Each subcommand has its own `--help` option. Upon the first usage, please run
   `dedoc fetch`.
 
 {GREEN}OPTIONS{RESET}
     -c, --force-colors              Forcefully enable colors.
+        --auto-fetch                Fetch `docs.json` before commands that use
+                                    it when it's missing or old.
+        --no-auto-fetch             Turn off `auto_fetch` that is set in the
+                                    config.
         --color <on/off/auto>       Control output colors.
     -V, --short-version             Display short version.
     -v, --version                   Display version and license.
@@ -104,6 +150,8 @@ fn entry<Args>(mut args: Args) -> ResultS
   let mut flag_version;
   let mut flag_color;
   let mut flag_color_force;
+  let mut flag_auto_fetch;
+  let mut flag_no_auto_fetch;
   let mut flag_help;
 
   let mut flags = flags![
@@ -111,6 +159,8 @@ fn entry<Args>(mut args: Args) -> ResultS
     flag_version: BoolFlag,       ["-v", "--version"],
     flag_color_force: BoolFlag,   ["-c", "--force-colors"],
     flag_color: StringFlag,       ["--color"],
+    flag_auto_fetch: BoolFlag,    ["--auto-fetch"],
+    flag_no_auto_fetch: BoolFlag, ["--no-auto-fetch"],
     flag_help: BoolFlag,          ["--help"]
   ];
 
@@ -125,6 +175,12 @@ fn entry<Args>(mut args: Args) -> ResultS
     parse_flags_until_subcommand(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?
                                                        .to_lowercase();
 
+  // A broken config is reported by subcommands that use it, so `config` can
+  // still be used to fix it.
+  if flag_color.is_empty() {
+    flag_color = get_config_value("color").unwrap_or_default().unwrap_or_default();
+  }
+
   if flag_color_force {
     unsafe { overwrite_should_use_colors(true) }
   } else if !flag_color.is_empty() {
@@ -147,14 +203,38 @@ fn entry<Args>(mut args: Args) -> ResultS
     return show_help();
   }
 
+  if DOCS_JSON_SUBCOMMANDS.contains(&subcommand.as_str()) {
+    flag_auto_fetch |= !flag_no_auto_fetch && get_config_bool("auto_fetch")?;
+
+    if flag_auto_fetch {
+      auto_fetch_docs_json()?;
+    }
+  }
+
   match subcommand.as_str() {
     "ft" | "fetch" => fetch(args),
     "ls" | "list" => list(args),
+    "if" | "info" => info(args),
     "dl" | "download" => download(args),
+    "od" | "outdated" => outdated(args),
     "rm" | "remove" => remove(args),
+    "ix" | "index" => index(args),
+    "ex" | "export" => export(args),
+    "im" | "import" => import(args),
+    "bd" | "build" => build(args),
     "ss" | "search" => search(args),
     "op" | "open" => open(args),
+    #[cfg(unix)]
+    "br" | "browse" => browse(args),
+    #[cfg(not(unix))]
+    "br" | "browse" => Err("`browse` is only supported on Unix-like systems.".to_string()),
     "rr" | "render" => render(args),
+    "cf" | "config" => config(args),
+    "completions" => completions(args),
+    "lsp" => lsp(args),
+    "serve" => serve(args),
+    // Used by completion scripts.
+    "complete" => complete(args),
     other => Err(format!("Unknown subcommand `{other}`")),
   }
 }
@@ -0,0 +1,454 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::common::get_docset_page_path;
+use crate::markdown::{
+  collapse_whitespace, collect_raw_text, is_block_element, join_inline_lines, parse_html, Element,
+  Node, SKIPPED_ELEMENTS,
+};
+
+// Docset pages are library documentation, which is section 3.
+pub(crate) const MAN_SECTION: &str = "3";
+
+// `rust`, `std/vec/struct.vec` -> `rust.std.vec.struct.vec`
+pub(crate) fn get_man_page_name(docset_name: &str, item: &str) -> String
+{
+  format!("{docset_name}.{}", item.replace('/', ".").replace(' ', "_"))
+}
+
+fn escape_roff(text: &str) -> String
+{
+  // Plain `-` may be rendered as a hyphen, which breaks copying of code.
+  text.replace('\\', "\\e").replace('-', "\\-")
+}
+
+// Lines which start with `.` or `'` are requests.
+fn escape_line_start(line: &str) -> String
+{
+  if line.starts_with(['.', '\'']) {
+    format!("\\&{line}")
+  } else {
+    line.to_owned()
+  }
+}
+
+// Macro arguments can't contain quotes.
+fn escape_macro_argument(text: &str) -> String
+{
+  text.replace('"', "\\(dq")
+}
+
+fn format_anchor(id: &str) -> String
+{
+  format!(".\\\" #{id}")
+}
+
+// Moves surrounding whitespace out of font changes, like Markdown emphasis.
+fn wrap_font(text: &str, font: char, output: &mut String)
+{
+  let trimmed = text.trim();
+  if trimmed.is_empty() {
+    *output += text;
+    return;
+  }
+
+  if text.starts_with(char::is_whitespace) {
+    output.push(' ');
+  }
+  *output += &format!("\\f{font}{trimmed}\\fR");
+  if text.ends_with(char::is_whitespace) {
+    output.push(' ');
+  }
+}
+
+fn write_inline(node: &Node, output: &mut String)
+{
+  let element = match node {
+    Node::Text(text) => {
+      *output += &escape_roff(&collapse_whitespace(text));
+      return;
+    }
+    Node::Element(e) => e,
+  };
+
+  if SKIPPED_ELEMENTS.contains(&element.name.as_str()) {
+    return;
+  }
+
+  let mut inner = String::new();
+
+  match element.name.as_str() {
+    "br" => output.push('\n'),
+    "code" | "kbd" | "samp" | "tt" => {
+      collect_raw_text(&element.children, &mut inner);
+      wrap_font(&escape_roff(&collapse_whitespace(&inner)), 'B', output);
+    }
+    "img" => {
+      *output += &escape_roff(element.get_attribute("alt").unwrap_or_default());
+    }
+    "strong" | "b" => {
+      write_inline_children(&element.children, &mut inner);
+      wrap_font(&inner, 'B', output);
+    }
+    "em" | "i" => {
+      write_inline_children(&element.children, &mut inner);
+      wrap_font(&inner, 'I', output);
+    }
+    name if is_block_element(name) => {
+      output.push('\n');
+      write_inline_children(&element.children, output);
+      output.push('\n');
+    }
+    _ => write_inline_children(&element.children, output),
+  }
+}
+
+fn write_inline_children(nodes: &[Node], output: &mut String)
+{
+  for node in nodes {
+    write_inline(node, output);
+  }
+}
+
+fn get_inline_text(nodes: &[Node]) -> String
+{
+  let mut inline = String::new();
+  write_inline_children(nodes, &mut inline);
+  join_inline_lines(&inline, " ")
+}
+
+fn flush_paragraph(inline: &mut String, blocks: &mut Vec<String>)
+{
+  let paragraph = join_inline_lines(inline, "\n").lines()
+                                                 .map(escape_line_start)
+                                                 .collect::<Vec<String>>()
+                                                 .join("\n.br\n");
+  if !paragraph.is_empty() {
+    blocks.push(format!(".PP\n{paragraph}"));
+  }
+  inline.clear();
+}
+
+fn write_blocks(nodes: &[Node], blocks: &mut Vec<String>)
+{
+  let mut inline = String::new();
+
+  for node in nodes {
+    match node {
+      Node::Element(e) if is_block_element(&e.name) => {
+        flush_paragraph(&mut inline, blocks);
+        write_block(e, blocks);
+      }
+      _ => write_inline(node, &mut inline),
+    }
+  }
+
+  flush_paragraph(&mut inline, blocks);
+}
+
+fn get_blocks(nodes: &[Node]) -> Vec<String>
+{
+  let mut blocks = vec![];
+  write_blocks(nodes, &mut blocks);
+  blocks
+}
+
+fn indent_blocks(blocks: &[String], indent: usize) -> String
+{
+  format!(".RS {indent}\n{}\n.RE", blocks.join("\n"))
+}
+
+fn write_block(element: &Element, blocks: &mut Vec<String>)
+{
+  if let Some(id) = element.get_attribute("id") {
+    blocks.push(format_anchor(id));
+  }
+
+  match element.name.as_str() {
+    "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+      let text = get_inline_text(&element.children);
+      if !text.is_empty() {
+        let level = if matches!(element.name.as_str(), "h1" | "h2") { "SH" } else { "SS" };
+        blocks.push(format!(".{level} {}", escape_macro_argument(&text)));
+      }
+    }
+    "pre" => blocks.push(format_code_block(element)),
+    "ul" | "ol" => {
+      if let Some(list) = format_list(element) {
+        blocks.push(list);
+      }
+    }
+    "blockquote" | "dd" => {
+      let inner = get_blocks(&element.children);
+      if !inner.is_empty() {
+        blocks.push(indent_blocks(&inner, 4));
+      }
+    }
+    "table" => {
+      if let Some(table) = format_table(element) {
+        blocks.push(table);
+      }
+    }
+    "hr" => {}
+    "dt" => {
+      let text = get_inline_text(&element.children);
+      if !text.is_empty() {
+        blocks.push(format!(".PP\n\\fB{}\\fR", escape_line_start(&text)));
+      }
+    }
+    _ => write_blocks(&element.children, blocks),
+  }
+}
+
+fn format_code_block(pre: &Element) -> String
+{
+  let mut code = String::new();
+  collect_raw_text(&pre.children, &mut code);
+
+  // A newline right after `<pre>` is ignored by browsers.
+  let code = code.strip_prefix('\n').unwrap_or(&code).trim_end();
+  let lines =
+    code.lines().map(|line| escape_line_start(&escape_roff(line))).collect::<Vec<String>>();
+
+  format!(".PP\n.RS 4\n.nf\n{}\n.fi\n.RE", lines.join("\n"))
+}
+
+fn format_list(list: &Element) -> Option<String>
+{
+  let is_ordered = list.name == "ol";
+  let mut number = list.get_attribute("start").and_then(|s| s.parse::<usize>().ok()).unwrap_or(1);
+
+  let mut items = vec![];
+
+  for node in &list.children {
+    let Node::Element(item) = node else {
+      continue;
+    };
+    if item.name != "li" {
+      continue;
+    }
+
+    let (marker, indent) =
+      if is_ordered { (format!("{number}."), 4) } else { ("\\(bu".to_owned(), 2) };
+    number += 1;
+
+    let mut formatted = vec![format!(".IP {marker} {indent}")];
+    let mut blocks = get_blocks(&item.children).into_iter().peekable();
+
+    // The first paragraph goes right after the marker, anything else is
+    // indented to the same level.
+    if let Some(paragraph) = blocks.next_if(|block| block.starts_with(".PP\n")) {
+      formatted.push(paragraph[4..].to_owned());
+    }
+    let rest = blocks.collect::<Vec<String>>();
+    if !rest.is_empty() {
+      formatted.push(indent_blocks(&rest, indent));
+    }
+
+    items.push(formatted.join("\n"));
+  }
+
+  if items.is_empty() {
+    return None;
+  }
+
+  Some(items.join("\n"))
+}
+
+// Tables are laid out by `tbl`, which `man` runs because of the first line of
+// the page.
+fn format_table(table: &Element) -> Option<String>
+{
+  fn collect_rows<'a>(element: &'a Element, is_header: bool, rows: &mut Vec<(&'a Element, bool)>)
+  {
+    for node in &element.children {
+      if let Node::Element(e) = node {
+        match e.name.as_str() {
+          "tr" => rows.push((e, is_header)),
+          "thead" => collect_rows(e, true, rows),
+          "tbody" | "tfoot" => collect_rows(e, false, rows),
+          _ => {}
+        }
+      }
+    }
+  }
+
+  let mut row_elements = vec![];
+  collect_rows(table, false, &mut row_elements);
+
+  let mut rows = vec![];
+  let mut has_header = false;
+
+  for (i, (row, is_header)) in row_elements.into_iter().enumerate() {
+    let cells = row.children
+                   .iter()
+                   .filter_map(|n| match n {
+                     Node::Element(e) if e.name == "td" || e.name == "th" => Some(e),
+                     _ => None,
+                   })
+                   .collect::<Vec<&Element>>();
+
+    if i == 0 {
+      has_header = is_header || (!cells.is_empty() && cells.iter().all(|c| c.name == "th"));
+    }
+
+    rows.push(cells.iter()
+                   .map(|c| {
+                     format!("T{{\n{}\nT}}", escape_line_start(&get_inline_text(&c.children)))
+                   })
+                   .collect::<Vec<String>>());
+  }
+
+  let column_count = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+  if column_count == 0 {
+    return None;
+  }
+
+  for row in rows.iter_mut() {
+    row.resize(column_count, "T{\nT}".to_owned());
+  }
+
+  let mut lines = vec![".TS".to_owned(), "allbox;".to_owned()];
+  if has_header {
+    lines.push(vec!["lb"; column_count].join(" "));
+  }
+  lines.push(vec!["l"; column_count].join(" ") + ".");
+  for row in rows {
+    lines.push(row.join("\t"));
+  }
+  lines.push(".TE".to_owned());
+
+  Some(lines.join("\n"))
+}
+
+// -> text of the first `<h1>`
+fn get_page_title(nodes: &[Node]) -> Option<String>
+{
+  for node in nodes {
+    let Node::Element(element) = node else {
+      continue;
+    };
+
+    if element.name == "h1" {
+      let mut title = String::new();
+      collect_raw_text(&element.children, &mut title);
+      let title = collapse_whitespace(&title).trim().to_owned();
+      if !title.is_empty() {
+        return Some(title);
+      }
+    } else if let Some(title) = get_page_title(&element.children) {
+      return Some(title);
+    }
+  }
+
+  None
+}
+
+fn html_to_man(html: String, name: &str, docset_name: &str) -> String
+{
+  let root = parse_html(html);
+  let title = get_page_title(&root.children).unwrap_or_else(|| name.to_owned());
+
+  let mut lines = vec![// Tells `man` to run `tbl`.
+                       "'\\\" t".to_owned(),
+                       format!(".TH \"{}\" {MAN_SECTION} \"\" \"dedoc\" \"{}\"",
+                               escape_macro_argument(name),
+                               escape_macro_argument(docset_name)),
+                       ".SH NAME".to_owned(),
+                       format!("{} \\- {}", escape_roff(name), escape_roff(&title)),];
+  lines.extend(get_blocks(&root.children));
+
+  lines.join("\n") + "\n"
+}
+
+// -> (translated file as a string, whether specified fragment was found)
+//
+// `docset_page` is `None` for HTML files which are not from a docset. Like the
+// text output, a fragment shows everything until the next fragment.
+pub(crate) fn translate_docset_html_file_to_man(path: &Path,
+                                                docset_page: Option<(&str, &str)>,
+                                                fragment: Option<&String>)
+                                                -> Result<(String, bool), String>
+{
+  let mut html = String::new();
+  File::open(path).and_then(|mut file| file.read_to_string(&mut html))
+                  .map_err(|err| format!("Could not read `{}`: {err}", path.display()))?;
+
+  let (name, docset_name) = match docset_page {
+    Some((docset_name, item)) => (get_man_page_name(docset_name, item), docset_name),
+    None => {
+      (path.file_stem().unwrap_or_default().to_string_lossy().to_string(), Default::default())
+    }
+  };
+
+  let man = html_to_man(html, &name, docset_name);
+
+  if let Some(fragment) = fragment {
+    // ASCII lowercase keeps byte offsets the same.
+    let lowercase_man = man.to_ascii_lowercase();
+    let anchor = format_anchor(fragment).to_ascii_lowercase() + "\n";
+
+    if let Some(start) = lowercase_man.find(&anchor) {
+      // Keep the header, so the result is still a man page.
+      let header_end = lowercase_man.find("\n.sh name\n").map_or(0, |i| i + 10);
+      let header_end = header_end + lowercase_man[header_end..].find('\n').map_or(0, |i| i + 1);
+      let mut output = man[..header_end].to_owned();
+
+      let rest = &lowercase_man[start + anchor.len()..];
+      if let Some(end) = rest.find(&format_anchor("")) {
+        output += &man[start..start + anchor.len() + end];
+      } else {
+        output += &man[start..];
+      }
+
+      return Ok((output, true));
+    }
+  }
+
+  Ok((man, false))
+}
+
+pub(crate) fn print_page_from_docset_as_man(docset_name: &str,
+                                            page: &str,
+                                            fragment: Option<&String>)
+                                            -> Result<bool, String>
+{
+  let page_path = get_docset_page_path(docset_name, page)?;
+  let (output, ret) =
+    translate_docset_html_file_to_man(&page_path, Some((docset_name, page)), fragment)?;
+  print!("{}", output);
+  Ok(ret)
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn html_to_man_body(html: &str) -> String
+  {
+    get_blocks(&parse_html(html.to_owned()).children).join("\n")
+  }
+
+  #[test]
+  fn test_html_to_man()
+  {
+    assert_eq!(html_to_man_body("<h2 id=\"new\">Vec::<code>new</code></h2><h3>Examples</h3>\
+                                 <p>A <b>growable</b> array.<br>.dot and \\ -x</p>"),
+               ".\\\" #new\n.SH Vec::\\fBnew\\fR\n.SS Examples\n\
+                .PP\nA \\fBgrowable\\fR array.\n.br\n\\&.dot and \\e \\-x");
+
+    assert_eq!(html_to_man_body("<pre>\nlet v = vec![1];\n.x\n</pre>"),
+               ".PP\n.RS 4\n.nf\nlet v = vec![1];\n\\&.x\n.fi\n.RE");
+
+    assert_eq!(html_to_man_body("<ul><li>one<li><p>two<ol start=\"3\"><li>nested</ol></ul>"),
+               ".IP \\(bu 2\none\n.IP \\(bu 2\ntwo\n.RS 2\n.IP 3. 4\nnested\n.RE");
+
+    assert_eq!(html_to_man_body("<table><tr><th>a</th><th>b</th></tr>\
+                                 <tr><td><i>x</i></td></tr></table>"),
+               ".TS\nallbox;\nlb lb\nl l.\nT{\na\nT}\tT{\nb\nT}\nT{\n\\fIx\\fR\nT}\tT{\nT}\n.TE");
+
+    assert_eq!(get_man_page_name("rust", "std/vec/struct.vec"), "rust.std.vec.struct.vec");
+  }
+}
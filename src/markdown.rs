@@ -0,0 +1,848 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::{
+  BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+
+use crate::common::{
+  get_absolute_url, get_docset_page_path, print_with_pager, resolve_docset_link, DocsetLink,
+};
+use crate::common::{GRAYER, RESET};
+
+pub(crate) enum Node
+{
+  Element(Element),
+  Text(String),
+}
+
+pub(crate) struct Element
+{
+  pub(crate) name: String,
+  pub(crate) attrs: Vec<(String, String)>,
+  pub(crate) children: Vec<Node>,
+}
+
+impl Element
+{
+  pub(crate) fn get_attribute(&self, name: &str) -> Option<&str>
+  {
+    self.attrs.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+  }
+}
+
+const VOID_ELEMENTS: &[&str] = &["area", "base", "br", "col", "embed", "hr", "img", "input",
+                                 "link", "meta", "source", "track", "wbr"];
+
+pub(crate) const SKIPPED_ELEMENTS: &[&str] = &["head", "script", "style", "template"];
+
+pub(crate) fn is_block_element(name: &str) -> bool
+{
+  matches!(name,
+           "address" |
+           "article" |
+           "aside" |
+           "blockquote" |
+           "body" |
+           "dd" |
+           "details" |
+           "div" |
+           "dl" |
+           "dt" |
+           "figcaption" |
+           "figure" |
+           "footer" |
+           "h1" |
+           "h2" |
+           "h3" |
+           "h4" |
+           "h5" |
+           "h6" |
+           "header" |
+           "hr" |
+           "html" |
+           "li" |
+           "main" |
+           "nav" |
+           "ol" |
+           "p" |
+           "pre" |
+           "section" |
+           "summary" |
+           "table" |
+           "tbody" |
+           "td" |
+           "tfoot" |
+           "th" |
+           "thead" |
+           "tr" |
+           "ul")
+}
+
+// -> (elements closed by an opening tag, elements which stop the search)
+fn get_implicitly_closed_elements(name: &str) -> Option<(&[&str], &[&str])>
+{
+  match name {
+    "li" => Some((&["li"], &["ul", "ol"])),
+    "dt" | "dd" => Some((&["dt", "dd"], &["dl"])),
+    "tr" => Some((&["tr", "td", "th"], &["table", "thead", "tbody", "tfoot"])),
+    "td" | "th" => Some((&["td", "th"], &["tr", "table"])),
+    "thead" | "tbody" | "tfoot" => {
+      Some((&["thead", "tbody", "tfoot", "tr", "td", "th"], &["table"]))
+    }
+    _ => None,
+  }
+}
+
+// DevDocs pages are sanitized, so a tokenizer with a few rules for implicitly
+// closed tags is enough to build a tree.
+struct TreeBuilder
+{
+  // The first element is the root, and is never closed.
+  stack: RefCell<Vec<Element>>,
+}
+
+fn close_last_element(stack: &mut Vec<Element>)
+{
+  if stack.len() > 1 {
+    let element = stack.pop().expect("stack has the root");
+    stack.last_mut().expect("stack has the root").children.push(Node::Element(element));
+  }
+}
+
+impl TokenSink for TreeBuilder
+{
+  type Handle = ();
+
+  fn process_token(&self, token: Token, _line_number: u64) -> TokenSinkResult<()>
+  {
+    let mut stack = self.stack.borrow_mut();
+
+    match token {
+      Token::TagToken(tag) if tag.kind == TagKind::StartTag => {
+        let name = tag.name.to_string();
+
+        if let Some((closed, scope)) = get_implicitly_closed_elements(&name) {
+          let position = stack.iter().rposition(|e| {
+                                       closed.contains(&e.name.as_str()) ||
+                                       scope.contains(&e.name.as_str())
+                                     });
+          if let Some(i) = position.filter(|i| closed.contains(&stack[*i].name.as_str())) {
+            while stack.len() > i {
+              close_last_element(&mut stack);
+            }
+          }
+        }
+        if is_block_element(&name) && stack.last().is_some_and(|e| e.name == "p") {
+          close_last_element(&mut stack);
+        }
+
+        let attrs =
+          tag.attrs.iter().map(|a| (a.name.local.to_string(), a.value.to_string())).collect();
+        let element = Element { name, attrs, children: vec![] };
+
+        if tag.self_closing || VOID_ELEMENTS.contains(&element.name.as_str()) {
+          stack.last_mut().expect("stack has the root").children.push(Node::Element(element));
+        } else {
+          stack.push(element);
+        }
+      }
+      Token::TagToken(tag) => {
+        if let Some(i) = stack.iter().rposition(|e| *e.name == *tag.name) {
+          while stack.len() > i {
+            close_last_element(&mut stack);
+          }
+        }
+      }
+      Token::CharacterTokens(text) => {
+        let children = &mut stack.last_mut().expect("stack has the root").children;
+        if let Some(Node::Text(last_text)) = children.last_mut() {
+          last_text.push_str(&text);
+        } else {
+          children.push(Node::Text(text.to_string()));
+        }
+      }
+      _ => {}
+    }
+
+    TokenSinkResult::Continue
+  }
+}
+
+pub(crate) fn parse_html(html: String) -> Element
+{
+  let root = Element { name: String::new(), attrs: vec![], children: vec![] };
+  let builder = TreeBuilder { stack: RefCell::new(vec![root]) };
+
+  let tokenizer = Tokenizer::new(builder, TokenizerOpts::default());
+  let input = BufferQueue::default();
+  input.push_back(StrTendril::from(html));
+  let _ = tokenizer.feed(&input);
+  tokenizer.end();
+
+  let mut stack = tokenizer.sink.stack.into_inner();
+  while stack.len() > 1 {
+    close_last_element(&mut stack);
+  }
+
+  stack.pop().expect("stack has the root")
+}
+
+fn format_anchor(id: &str) -> String
+{
+  format!("<a id=\"{}\"></a>", id.replace('"', "&quot;"))
+}
+
+pub(crate) fn collapse_whitespace(text: &str) -> String
+{
+  let mut result = String::with_capacity(text.len());
+  let mut is_previous_whitespace = false;
+
+  for c in text.chars() {
+    if c.is_whitespace() {
+      if !is_previous_whitespace {
+        result.push(' ');
+      }
+      is_previous_whitespace = true;
+    } else {
+      result.push(c);
+      is_previous_whitespace = false;
+    }
+  }
+
+  result
+}
+
+fn escape_markdown(text: &str) -> String
+{
+  let chars = text.chars().collect::<Vec<char>>();
+  let mut result = String::with_capacity(text.len());
+
+  for (i, c) in chars.iter().enumerate() {
+    let is_escaped = match c {
+      '\\' | '`' | '*' | '[' | ']' | '<' => true,
+      // Underscores inside of words can't start emphasis.
+      '_' => {
+        let is_inside_word = i > 0 &&
+                             chars[i - 1].is_alphanumeric() &&
+                             chars.get(i + 1).is_some_and(|c| c.is_alphanumeric());
+        !is_inside_word
+      }
+      _ => false,
+    };
+    if is_escaped {
+      result.push('\\');
+    }
+    result.push(*c);
+  }
+
+  result
+}
+
+// Escapes characters which would start a heading, a list or a quote.
+fn escape_line_start(line: &str) -> String
+{
+  let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+  let after_digits = &line[digits..];
+
+  let is_ordered_list = digits > 0 &&
+                        (after_digits.starts_with(". ") ||
+                         after_digits.starts_with(") ") ||
+                         after_digits == "." ||
+                         after_digits == ")");
+  let is_marker = line.starts_with(['#', '>', '=']) ||
+                  ((line.starts_with(['-', '+']) && line[1..].starts_with([' ', '-', '+'])) ||
+                   line == "-" ||
+                   line == "+");
+
+  if is_ordered_list {
+    format!("{}\\{}", &line[..digits], after_digits)
+  } else if is_marker {
+    format!("\\{line}")
+  } else {
+    line.to_owned()
+  }
+}
+
+fn format_code_span(text: &str) -> String
+{
+  let text = collapse_whitespace(text);
+
+  let mut longest_backticks = 0;
+  let mut current_backticks = 0;
+  for c in text.chars() {
+    current_backticks = if c == '`' { current_backticks + 1 } else { 0 };
+    longest_backticks = longest_backticks.max(current_backticks);
+  }
+
+  let fence = "`".repeat(longest_backticks + 1);
+  if text.starts_with('`') || text.ends_with('`') {
+    format!("{fence} {text} {fence}")
+  } else {
+    format!("{fence}{text}{fence}")
+  }
+}
+
+// Moves surrounding whitespace out of emphasis, since `** text**` is not
+// emphasized.
+fn wrap_inline(text: &str, marker: &str, output: &mut String)
+{
+  let trimmed = text.trim();
+  if trimmed.is_empty() {
+    *output += text;
+    return;
+  }
+
+  if text.starts_with(char::is_whitespace) {
+    output.push(' ');
+  }
+  *output += marker;
+  *output += trimmed;
+  *output += marker;
+  if text.ends_with(char::is_whitespace) {
+    output.push(' ');
+  }
+}
+
+// `<br>`s and blocks inside of inline content are kept as newlines, and
+// `separator` replaces them.
+pub(crate) fn join_inline_lines(inline: &str, separator: &str) -> String
+{
+  inline.lines()
+        .map(|line| collapse_whitespace(line).trim().to_owned())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+pub(crate) fn collect_raw_text(nodes: &[Node], output: &mut String)
+{
+  for node in nodes {
+    match node {
+      Node::Text(text) => *output += text,
+      Node::Element(e) if e.name == "br" => output.push('\n'),
+      Node::Element(e) if SKIPPED_ELEMENTS.contains(&e.name.as_str()) => {}
+      Node::Element(e) => collect_raw_text(&e.children, output),
+    }
+  }
+}
+
+fn get_code_language(pre: &Element) -> Option<String>
+{
+  if let Some(language) = pre.get_attribute("data-language") {
+    return Some(language.to_owned());
+  }
+
+  let code = pre.children.iter().find_map(|n| match n {
+                                  Node::Element(e) if e.name == "code" => Some(e),
+                                  _ => None,
+                                });
+
+  for element in [Some(pre), code].into_iter().flatten() {
+    let classes = element.get_attribute("class").unwrap_or_default();
+    for class in classes.split_whitespace() {
+      if let Some(language) = class.strip_prefix("language-").or(class.strip_prefix("lang-")) {
+        return Some(language.to_owned());
+      }
+    }
+  }
+
+  None
+}
+
+// -> relative path from one page to another, without extension
+fn get_relative_page_path(from_item: &str, to_item: &str) -> String
+{
+  let to_item = if to_item.is_empty() { "index" } else { to_item };
+
+  let from_components = from_item.split('/').collect::<Vec<&str>>();
+  let from_directory = &from_components[..from_components.len() - 1];
+  let to_components = to_item.split('/').collect::<Vec<&str>>();
+
+  let common_count = from_directory.iter()
+                                   .zip(&to_components)
+                                   .take_while(|(a, b)| a == b)
+                                   .count()
+                                   .min(to_components.len() - 1);
+
+  let mut components = vec![".."; from_directory.len() - common_count];
+  components.extend(&to_components[common_count..]);
+
+  components.join("/")
+}
+
+struct MarkdownWriter<'a>
+{
+  // Item of the page, when it is from a docset. Links to other pages are
+  // rewritten to relative `.md` paths, as laid out by `render --markdown`.
+  current_item: Option<&'a str>,
+}
+
+impl MarkdownWriter<'_>
+{
+  fn format_link_url(&self, href: &str) -> String
+  {
+    let url = match self.current_item.map(|item| (item, resolve_docset_link(item, href))) {
+      Some((current_item, DocsetLink::Page { item, fragment })) => {
+        let mut url = String::new();
+        if item != current_item || fragment.is_none() {
+          url += &get_relative_page_path(current_item, &item);
+          url += ".md";
+        }
+        if let Some(fragment) = fragment {
+          url.push('#');
+          url += &fragment;
+        }
+        url
+      }
+      Some((_, DocsetLink::External(url))) => get_absolute_url(url),
+      None => href.to_owned(),
+    };
+
+    if url.contains([' ', '(', ')']) {
+      format!("<{url}>")
+    } else {
+      url
+    }
+  }
+
+  fn write_inline(&self, node: &Node, output: &mut String)
+  {
+    let element = match node {
+      Node::Text(text) => {
+        *output += &escape_markdown(&collapse_whitespace(text));
+        return;
+      }
+      Node::Element(e) => e,
+    };
+
+    if SKIPPED_ELEMENTS.contains(&element.name.as_str()) {
+      return;
+    }
+    if let Some(id) = element.get_attribute("id") {
+      *output += &format_anchor(id);
+    }
+
+    let mut inner = String::new();
+
+    match element.name.as_str() {
+      "br" => output.push('\n'),
+      "code" | "kbd" | "samp" | "tt" => {
+        collect_raw_text(&element.children, &mut inner);
+        if !inner.trim().is_empty() {
+          *output += &format_code_span(&inner);
+        }
+      }
+      "a" => {
+        self.write_inline_children(&element.children, &mut inner);
+        match element.get_attribute("href") {
+          Some(href) if !inner.trim().is_empty() => {
+            let text = join_inline_lines(&inner, " ");
+            *output += &format!("[{text}]({})", self.format_link_url(href));
+          }
+          _ => {
+            if let Some(name) = element.get_attribute("name") {
+              *output += &format_anchor(name);
+            }
+            *output += &inner;
+          }
+        }
+      }
+      "img" => {
+        if let Some(src) = element.get_attribute("src") {
+          let alt = escape_markdown(element.get_attribute("alt").unwrap_or_default());
+          *output += &format!("![{alt}]({src})");
+        }
+      }
+      "strong" | "b" => {
+        self.write_inline_children(&element.children, &mut inner);
+        wrap_inline(&inner, "**", output);
+      }
+      "em" | "i" => {
+        self.write_inline_children(&element.children, &mut inner);
+        wrap_inline(&inner, "*", output);
+      }
+      "del" | "s" | "strike" => {
+        self.write_inline_children(&element.children, &mut inner);
+        wrap_inline(&inner, "~~", output);
+      }
+      name if is_block_element(name) => {
+        output.push('\n');
+        self.write_inline_children(&element.children, output);
+        output.push('\n');
+      }
+      _ => self.write_inline_children(&element.children, output),
+    }
+  }
+
+  fn write_inline_children(&self, nodes: &[Node], output: &mut String)
+  {
+    for node in nodes {
+      self.write_inline(node, output);
+    }
+  }
+
+  fn get_inline_text(&self, nodes: &[Node], separator: &str) -> String
+  {
+    let mut inline = String::new();
+    self.write_inline_children(nodes, &mut inline);
+    join_inline_lines(&inline, separator)
+  }
+
+  fn flush_paragraph(inline: &mut String, blocks: &mut Vec<String>)
+  {
+    let paragraph = join_inline_lines(inline, "\n").lines()
+                                                   .map(escape_line_start)
+                                                   .collect::<Vec<String>>()
+                                                   .join("\\\n");
+    if !paragraph.is_empty() {
+      blocks.push(paragraph);
+    }
+    inline.clear();
+  }
+
+  fn write_blocks(&self, nodes: &[Node], blocks: &mut Vec<String>)
+  {
+    let mut inline = String::new();
+
+    for node in nodes {
+      match node {
+        Node::Element(e) if is_block_element(&e.name) => {
+          Self::flush_paragraph(&mut inline, blocks);
+          self.write_block(e, blocks);
+        }
+        _ => self.write_inline(node, &mut inline),
+      }
+    }
+
+    Self::flush_paragraph(&mut inline, blocks);
+  }
+
+  fn get_blocks(&self, nodes: &[Node]) -> Vec<String>
+  {
+    let mut blocks = vec![];
+    self.write_blocks(nodes, &mut blocks);
+    blocks
+  }
+
+  fn write_block(&self, element: &Element, blocks: &mut Vec<String>)
+  {
+    if let Some(id) = element.get_attribute("id") {
+      blocks.push(format_anchor(id));
+    }
+
+    match element.name.as_str() {
+      "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+        let level = element.name[1..].parse::<usize>().expect("heading has a level");
+        let text = self.get_inline_text(&element.children, " ");
+        if !text.is_empty() {
+          blocks.push(format!("{} {text}", "#".repeat(level)));
+        }
+      }
+      "pre" => blocks.push(Self::format_code_block(element)),
+      "ul" | "ol" => {
+        if let Some(list) = self.format_list(element) {
+          blocks.push(list);
+        }
+      }
+      "blockquote" => {
+        let quote = self.get_blocks(&element.children).join("\n\n");
+        if !quote.is_empty() {
+          let lines =
+            quote.lines()
+                 .map(|line| if line.is_empty() { ">".to_owned() } else { format!("> {line}") })
+                 .collect::<Vec<String>>();
+          blocks.push(lines.join("\n"));
+        }
+      }
+      "table" => {
+        if let Some(table) = self.format_table(element) {
+          blocks.push(table);
+        }
+      }
+      "hr" => blocks.push("---".to_owned()),
+      "dt" => {
+        let text = self.get_inline_text(&element.children, " ");
+        if !text.is_empty() {
+          blocks.push(format!("**{text}**"));
+        }
+      }
+      _ => self.write_blocks(&element.children, blocks),
+    }
+  }
+
+  fn format_code_block(pre: &Element) -> String
+  {
+    let mut code = String::new();
+    collect_raw_text(&pre.children, &mut code);
+
+    // A newline right after `<pre>` is ignored by browsers.
+    let code = code.strip_prefix('\n').unwrap_or(&code).trim_end();
+
+    let mut longest_backticks = 0;
+    for line in code.lines() {
+      let backticks = line.trim_start().chars().take_while(|c| *c == '`').count();
+      longest_backticks = longest_backticks.max(backticks);
+    }
+
+    let fence = "`".repeat(longest_backticks.max(2) + 1);
+    let language = get_code_language(pre).unwrap_or_default();
+
+    format!("{fence}{language}\n{code}\n{fence}")
+  }
+
+  fn format_list(&self, list: &Element) -> Option<String>
+  {
+    let is_ordered = list.name == "ol";
+    let mut number = list.get_attribute("start").and_then(|s| s.parse::<usize>().ok()).unwrap_or(1);
+
+    let mut items: Vec<Vec<String>> = vec![];
+
+    for node in &list.children {
+      match node {
+        Node::Element(e) if e.name == "li" => {
+          let mut item = vec![];
+          if let Some(id) = e.get_attribute("id") {
+            item.push(format_anchor(id));
+          }
+          self.write_blocks(&e.children, &mut item);
+          items.push(item);
+        }
+        // Lists nested without `<li>` belong to the previous item.
+        other => {
+          let blocks = self.get_blocks(std::slice::from_ref(other));
+          if blocks.is_empty() {
+            continue;
+          }
+          if let Some(last_item) = items.last_mut() {
+            last_item.extend(blocks);
+          } else {
+            items.push(blocks);
+          }
+        }
+      }
+    }
+
+    if items.is_empty() {
+      return None;
+    }
+
+    let is_loose = items.iter().any(|item| item.len() > 1);
+    let mut formatted_items = vec![];
+
+    for item in items {
+      let marker = if is_ordered { format!("{number}. ") } else { "- ".to_owned() };
+      let indent = " ".repeat(marker.len());
+      number += 1;
+
+      let content = item.join("\n\n");
+      let mut lines = content.lines();
+
+      let mut formatted = marker.trim_end().to_owned();
+      if let Some(first_line) = lines.next() {
+        formatted = marker.clone() + first_line;
+      }
+      for line in lines {
+        formatted.push('\n');
+        if !line.is_empty() {
+          formatted += &indent;
+          formatted += line;
+        }
+      }
+
+      formatted_items.push(formatted);
+    }
+
+    Some(formatted_items.join(if is_loose { "\n\n" } else { "\n" }))
+  }
+
+  fn format_table(&self, table: &Element) -> Option<String>
+  {
+    fn collect_rows<'a>(element: &'a Element, is_header: bool, rows: &mut Vec<(&'a Element, bool)>)
+    {
+      for node in &element.children {
+        if let Node::Element(e) = node {
+          match e.name.as_str() {
+            "tr" => rows.push((e, is_header)),
+            "thead" => collect_rows(e, true, rows),
+            "tbody" | "tfoot" => collect_rows(e, false, rows),
+            _ => {}
+          }
+        }
+      }
+    }
+
+    let mut row_elements = vec![];
+    collect_rows(table, false, &mut row_elements);
+
+    let mut rows = vec![];
+    let mut has_header = false;
+
+    for (i, (row, is_header)) in row_elements.into_iter().enumerate() {
+      let cells = row.children
+                     .iter()
+                     .filter_map(|n| match n {
+                       Node::Element(e) if e.name == "td" || e.name == "th" => Some(e),
+                       _ => None,
+                     })
+                     .collect::<Vec<&Element>>();
+
+      if i == 0 {
+        has_header = is_header || (!cells.is_empty() && cells.iter().all(|c| c.name == "th"));
+      }
+
+      rows.push(cells.iter()
+                     .map(|c| self.get_inline_text(&c.children, "<br>").replace('|', "\\|"))
+                     .collect::<Vec<String>>());
+    }
+
+    let column_count = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    if column_count == 0 {
+      return None;
+    }
+
+    if !has_header {
+      rows.insert(0, vec![]);
+    }
+    for row in rows.iter_mut() {
+      row.resize(column_count, String::new());
+    }
+
+    let mut widths = vec![3; column_count];
+    for row in &rows {
+      for (width, cell) in widths.iter_mut().zip(row) {
+        *width = (*width).max(cell.chars().count());
+      }
+    }
+
+    let format_row = |row: &[String]| {
+      let cells =
+        row.iter()
+           .zip(&widths)
+           .map(|(cell, width)| format!("{cell}{}", " ".repeat(width - cell.chars().count())))
+           .collect::<Vec<String>>();
+      format!("| {} |", cells.join(" | "))
+    };
+
+    let mut lines = vec![format_row(&rows[0])];
+    let delimiters = widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<String>>();
+    lines.push(format_row(&delimiters));
+    for row in &rows[1..] {
+      lines.push(format_row(row));
+    }
+
+    Some(lines.join("\n"))
+  }
+}
+
+// -> (translated file as a string, whether specified fragment was found)
+//
+// Like the text output, a fragment shows everything until the next fragment.
+pub(crate) fn translate_docset_html_file_to_markdown(path: &Path,
+                                                     current_item: Option<&str>,
+                                                     fragment: Option<&String>,
+                                                     use_colors: bool)
+                                                     -> Result<(String, bool), String>
+{
+  let mut html = String::new();
+  File::open(path).and_then(|mut file| file.read_to_string(&mut html))
+                  .map_err(|err| format!("Could not read `{}`: {err}", path.display()))?;
+
+  let root = parse_html(html);
+  let writer = MarkdownWriter { current_item };
+  let markdown = writer.get_blocks(&root.children).join("\n\n");
+
+  let ellipsis = if use_colors { format!("{GRAYER}...{RESET}\n") } else { "...\n".to_owned() };
+
+  if let Some(fragment) = fragment {
+    // ASCII lowercase keeps byte offsets the same.
+    let lowercase_markdown = markdown.to_ascii_lowercase();
+    let anchor = format_anchor(fragment).to_ascii_lowercase();
+
+    if let Some(start) = lowercase_markdown.find(&anchor) {
+      let rest = &lowercase_markdown[start + anchor.len()..];
+      let mut output = ellipsis.clone();
+
+      if let Some(end) = rest.find("<a id=\"") {
+        output += markdown[start..start + anchor.len() + end].trim_end();
+        output += "\n";
+        output += &ellipsis;
+      } else {
+        output += &markdown[start..];
+        output += "\n";
+      }
+
+      return Ok((output, true));
+    }
+  }
+
+  Ok((markdown + "\n", false))
+}
+
+pub(crate) fn print_page_from_docset_as_markdown(docset_name: &str,
+                                                 page: &str,
+                                                 fragment: Option<&String>,
+                                                 use_pager: bool)
+                                                 -> Result<bool, String>
+{
+  let page_path = get_docset_page_path(docset_name, page)?;
+  let (output, ret) =
+    translate_docset_html_file_to_markdown(&page_path, Some(page), fragment, true)?;
+  print_with_pager(&output, use_pager)?;
+  Ok(ret)
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn html_to_markdown(html: &str, current_item: Option<&str>) -> String
+  {
+    let root = parse_html(html.to_owned());
+    MarkdownWriter { current_item }.get_blocks(&root.children).join("\n\n")
+  }
+
+  #[test]
+  fn test_html_to_markdown()
+  {
+    assert_eq!(html_to_markdown("<h2>Vec<code>&lt;T&gt;</code></h2><p>A <b>growable</b> \
+                                 array_type.<br>Use * carefully</p>",
+                                None),
+               "## Vec`<T>`\n\nA **growable** array_type.\\\nUse \\* carefully");
+
+    assert_eq!(html_to_markdown("<pre data-language=\"rust\">\nlet v = vec![1];\n</pre>", None),
+               "```rust\nlet v = vec![1];\n```");
+
+    assert_eq!(html_to_markdown("<ol start=\"2\"><li>one<li>two<ul><li>nested</ul></ol>", None),
+               "2. one\n\n3. two\n\n   - nested");
+
+    assert_eq!(html_to_markdown("<table><tr><th>a</th><th>b</th></tr>\
+                                 <tr><td>x | y</td><td>z</td></tr></table>",
+                                None),
+               "| a      | b   |\n| ------ | --- |\n| x \\| y | z   |");
+
+    assert_eq!(html_to_markdown("<p id=\"method.push\">See \
+                                 <a href=\"../vec/struct.vec#method.push\">push</a>, \
+                                 <a href=\"#new\">new</a> and \
+                                 <a href=\"https://example.com/\">this</a>.</p>",
+                                Some("std/io/struct.bufreader")),
+               "<a id=\"method.push\"></a>\n\nSee [push](../vec/struct.vec.md#method.push), \
+                [new](#new) and [this](https://example.com/).");
+  }
+
+  #[test]
+  fn test_relative_page_path()
+  {
+    assert_eq!(get_relative_page_path("std/io/struct.bufreader", "std/vec/struct.vec"),
+               "../vec/struct.vec");
+    assert_eq!(get_relative_page_path("std/io/struct.bufreader", "std/io/trait.read"),
+               "trait.read");
+    assert_eq!(get_relative_page_path("index", "std/index"), "std/index");
+    assert_eq!(get_relative_page_path("std/index", "index"), "../index");
+    assert_eq!(get_relative_page_path("std/index", ""), "../index");
+  }
+}
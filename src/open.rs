@@ -1,14 +1,21 @@
 use std::path::PathBuf;
 
+use serde::Serialize;
+
 use toiletcli::flags;
 use toiletcli::flags::*;
 
 use crate::common::{
-  deserialize_docs_json, get_flag_error, get_terminal_width, is_docs_json_exists,
-  is_docset_downloaded, print_docset_file, print_page_from_docset, split_to_item_and_fragment,
+  deserialize_docs_json, format_docset_link, get_absolute_url, get_docset_page_path,
+  get_flag_error, get_html_file_links, get_page_links_from_docset, get_terminal_width,
+  is_docs_json_exists, is_docset_downloaded, print_docset_file, print_json, print_page_from_docset,
+  print_with_pager, split_to_item_and_fragment, translate_docset_html_file_to_text,
 };
-use crate::common::{make_sure_docset_is_in_docs, ResultS};
+use crate::common::{make_sure_docset_is_in_docs, DocsetLink, ResultS};
 use crate::common::{BOLD, GREEN, PROGRAM_NAME, RESET};
+use crate::config::{get_config_bool, get_config_columns};
+use crate::man::{print_page_from_docset_as_man, translate_docset_html_file_to_man};
+use crate::markdown::{print_page_from_docset_as_markdown, translate_docset_html_file_to_markdown};
 
 fn show_open_help() -> ResultS
 {
@@ -16,7 +23,8 @@ fn show_open_help() -> ResultS
            "\
 {GREEN}USAGE{RESET}
     {BOLD}{PROGRAM_NAME} open{RESET} [-OPTIONS] <docset> <page>
-    Print a page. Pages can be searched using `search`.
+    Print a page. Pages can be searched using `search`. Links are listed
+    after the page as arguments for `open`.
 
     {BOLD}{PROGRAM_NAME} open{RESET} [-OPTIONS] --html <HTML file>
     Translate an HTML file to text.
@@ -26,24 +34,117 @@ fn show_open_help() -> ResultS
                                     and translate it to text.
     -c, --columns <number>          Make output N columns wide.
     -n, --line-numbers              Number outputted lines.
+    -f, --follow <number>           Open N-th link of the page instead. Links
+                                    outside of the docset are printed.
+    -m, --markdown                  Print the page as Markdown. Links lead to
+                                    pages from `render --markdown`. `-c` and
+                                    `-n` are ignored.
+        --man                       Print the page as a man page, to be read
+                                    with `man -l -`. `-c`, `-n` and `-m` are
+                                    ignored.
+        --no-pager                  Don't pass the page to `$PAGER` when
+                                    output is a terminal.
+        --pager                     Pass the page to `$PAGER`, even if
+                                    `open.no_pager` is set in the config.
+        --json                      Print the page, its path and its links as
+                                    JSON.
+        --no-<option>               Turn off an option that is set in the
+                                    config, like `--no-markdown`.
         --help                      Display help message."
   );
   Ok(())
 }
 
+#[derive(Serialize)]
+struct JsonPage<'a>
+{
+  // Both are `None` with `--html`.
+  docset: Option<&'a str>,
+  item: Option<&'a str>,
+  fragment: Option<&'a str>,
+  path: String,
+  markdown: bool,
+  text: String,
+  // Numbered from 1, as for `--follow`.
+  links: Vec<DocsetLink>,
+}
+
+// `docset_page` is `None` for HTML files which are not from a docset.
+pub(crate) fn print_page_as_json(path: PathBuf,
+                                 docset_page: Option<(&str, &str)>,
+                                 fragment: Option<&String>,
+                                 width: usize,
+                                 number_lines: bool,
+                                 markdown: bool)
+                                 -> ResultS
+{
+  let (text, _) = if markdown {
+    translate_docset_html_file_to_markdown(&path,
+                                           docset_page.map(|(_, item)| item),
+                                           fragment,
+                                           false)?
+  } else {
+    translate_docset_html_file_to_text(path.clone(),
+                                       docset_page,
+                                       fragment,
+                                       width,
+                                       number_lines,
+                                       false)?
+  };
+
+  let links = if let Some((docset_name, item)) = docset_page {
+    get_page_links_from_docset(docset_name, item)?
+  } else {
+    get_html_file_links(&path)?.into_iter().map(DocsetLink::External).collect()
+  };
+
+  let links = links.into_iter()
+                   .map(|link| match link {
+                     DocsetLink::External(url) => DocsetLink::External(get_absolute_url(url)),
+                     page => page,
+                   })
+                   .collect();
+
+  let page = JsonPage { docset: docset_page.map(|(docset_name, _)| docset_name),
+                        item: docset_page.map(|(_, item)| item),
+                        fragment: fragment.map(String::as_str),
+                        path: path.display().to_string(),
+                        markdown,
+                        text,
+                        links };
+
+  print_json(&page)
+}
+
 pub(crate) fn open<Args>(mut args: Args) -> ResultS
   where Args: Iterator<Item = String>
 {
   let mut flag_html;
   let mut flag_columns;
   let mut flag_number_lines;
+  let mut flag_follow;
+  let mut flag_markdown;
+  let mut flag_man;
+  let mut flag_no_pager;
+  let mut flag_json;
+  let mut flag_no_number_lines;
+  let mut flag_no_markdown;
+  let mut flag_pager;
   let mut flag_help;
 
   let mut flags = flags![
-    flag_html: BoolFlag,         ["-h", "--html"],
-    flag_columns: StringFlag,    ["-c", "--columns"],
-    flag_number_lines: BoolFlag, ["-n", "--line-numbers"],
-    flag_help: BoolFlag,         ["--help"]
+    flag_html: BoolFlag,            ["-h", "--html"],
+    flag_columns: StringFlag,       ["-c", "--columns"],
+    flag_number_lines: BoolFlag,    ["-n", "--line-numbers"],
+    flag_follow: StringFlag,        ["-f", "--follow"],
+    flag_markdown: BoolFlag,        ["-m", "--markdown"],
+    flag_man: BoolFlag,             ["--man"],
+    flag_no_pager: BoolFlag,        ["--no-pager"],
+    flag_json: BoolFlag,            ["--json"],
+    flag_no_number_lines: BoolFlag, ["--no-line-numbers"],
+    flag_no_markdown: BoolFlag,     ["--no-markdown"],
+    flag_pager: BoolFlag,           ["--pager"],
+    flag_help: BoolFlag,            ["--help"]
   ];
 
   let args = parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
@@ -51,7 +152,12 @@ pub(crate) fn open<Args>(mut args: Args) -> ResultS
     return show_open_help();
   }
 
-  let mut width = get_terminal_width();
+  flag_number_lines |= !flag_no_number_lines && get_config_bool("open.line_numbers")?;
+  flag_markdown |= !flag_no_markdown && get_config_bool("open.markdown")?;
+  flag_no_pager |= !flag_pager && get_config_bool("open.no_pager")?;
+
+  let mut width =
+    if let Some(c) = get_config_columns("open.columns")? { c } else { get_terminal_width()? };
 
   if let Ok(c) = flag_columns.parse::<usize>() {
     if c == 0 {
@@ -65,9 +171,25 @@ pub(crate) fn open<Args>(mut args: Args) -> ResultS
     return Err("Invalid number of columns.".to_string());
   }
 
+  if flag_man && flag_json {
+    return Err("`--man` can't be used with `--json`.".to_string());
+  }
+
   if flag_html {
+    if !flag_follow.is_empty() {
+      return Err("`--follow` can't be used with `--html`.".to_string());
+    }
     let path = PathBuf::from(args.join(" "));
-    print_docset_file(path, None, width, flag_number_lines)?;
+    if flag_json {
+      print_page_as_json(path, None, None, width, flag_number_lines, flag_markdown)?;
+    } else if flag_man {
+      print!("{}", translate_docset_html_file_to_man(&path, None, None)?.0);
+    } else if flag_markdown {
+      let (output, _) = translate_docset_html_file_to_markdown(&path, None, None, true)?;
+      print_with_pager(&output, !flag_no_pager)?;
+    } else {
+      print_docset_file(path, None, None, width, flag_number_lines, !flag_no_pager)?;
+    }
     return Ok(());
   }
 
@@ -97,8 +219,54 @@ pub(crate) fn open<Args>(mut args: Args) -> ResultS
     return Err("No page specified. Try `open --help` for more information.".to_string());
   }
 
-  let (item, fragment) = split_to_item_and_fragment(query)?;
-  print_page_from_docset(&docset, &item, fragment.as_ref(), width, flag_number_lines)?;
+  let (mut item, mut fragment) = split_to_item_and_fragment(query)?;
+
+  if !flag_follow.is_empty() {
+    let n = flag_follow.parse::<usize>()
+                       .ok()
+                       .filter(|n| *n > 0)
+                       .ok_or(format!("Invalid link number `{flag_follow}`"))?;
+
+    let mut links = get_page_links_from_docset(&docset, &item)?;
+    if n > links.len() {
+      return Err(format!("Page `{item}` has {} links, can't follow link {n}.", links.len()));
+    }
+
+    match links.swap_remove(n - 1) {
+      DocsetLink::Page { item: link_item, fragment: link_fragment } => {
+        item = link_item;
+        fragment = link_fragment;
+      }
+      DocsetLink::External(url) if flag_json => {
+        return print_json(&DocsetLink::External(get_absolute_url(url)));
+      }
+      external => {
+        println!("{}", format_docset_link(&docset, external));
+        return Ok(());
+      }
+    }
+  }
+
+  if flag_json {
+    let page_path = get_docset_page_path(&docset, &item)?;
+    print_page_as_json(page_path,
+                       Some((&docset, &item)),
+                       fragment.as_ref(),
+                       width,
+                       flag_number_lines,
+                       flag_markdown)?;
+  } else if flag_man {
+    print_page_from_docset_as_man(&docset, &item, fragment.as_ref())?;
+  } else if flag_markdown {
+    print_page_from_docset_as_markdown(&docset, &item, fragment.as_ref(), !flag_no_pager)?;
+  } else {
+    print_page_from_docset(&docset,
+                           &item,
+                           fragment.as_ref(),
+                           width,
+                           flag_number_lines,
+                           !flag_no_pager)?;
+  }
 
   Ok(())
 }
@@ -0,0 +1,181 @@
+use serde::Serialize;
+
+use toiletcli::flags;
+use toiletcli::flags::*;
+
+use crate::common::{
+  deserialize_docs_json, find_docset_in_docs, get_docs_json_max_age, get_docset_mtime,
+  get_flag_error, get_local_docsets, is_docs_json_exists, is_docs_json_old, is_docset_downloaded,
+  is_docset_in_docs, print_json, SearchMatch,
+};
+use crate::common::{DocsEntry, ResultS};
+use crate::common::{BOLD, GRAY, GREEN, PROGRAM_NAME, RESET, YELLOW};
+use crate::info::format_date;
+use crate::print_warning;
+
+fn show_outdated_help() -> ResultS
+{
+  println!(
+           "\
+{GREEN}USAGE{RESET}
+    {BOLD}{PROGRAM_NAME} outdated{RESET} [-OPTIONS] [docset1, docset2, ..]
+    Compare versions of local docsets with `docs.json`. Fails if any of them
+    can be updated, so it can be used in scripts. Docsets that were renamed
+    or removed from `docs.json` are reported, but don't fail.
+
+{GREEN}OPTIONS{RESET}
+        --json                      Print the status of each docset as JSON.
+        --help                      Display help message."
+  );
+  Ok(())
+}
+
+#[derive(Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum OutdatedStatus
+{
+  UpToDate,
+  Outdated,
+  // Not in `docs.json`, but it has docsets with similar names.
+  Renamed,
+  Removed,
+}
+
+#[derive(Serialize)]
+struct JsonOutdated<'a>
+{
+  docset: &'a str,
+  status: OutdatedStatus,
+  local_mtime: u64,
+  // `None` if the docset is not in `docs.json`.
+  mtime: Option<u64>,
+  // Similar names from `docs.json` for renamed docsets.
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  candidates: Vec<String>,
+}
+
+fn get_outdated_status<'a>(docset: &'a str, docs: &[DocsEntry])
+                           -> Result<JsonOutdated<'a>, String>
+{
+  let local_mtime = get_docset_mtime(docset)?;
+
+  if let Some(entry) = find_docset_in_docs(docset, docs) {
+    let status =
+      if entry.mtime > local_mtime { OutdatedStatus::Outdated } else { OutdatedStatus::UpToDate };
+    return Ok(JsonOutdated { docset,
+                             status,
+                             local_mtime,
+                             mtime: Some(entry.mtime),
+                             candidates: vec![] });
+  }
+
+  let (status, candidates) = match is_docset_in_docs(docset, docs) {
+    SearchMatch::Vague(vague_matches) => {
+      (OutdatedStatus::Renamed, vague_matches.into_iter().take(3).collect())
+    }
+    _ => (OutdatedStatus::Removed, vec![]),
+  };
+
+  Ok(JsonOutdated { docset, status, local_mtime, mtime: None, candidates })
+}
+
+// Docsets from older versions of dedoc don't know their version.
+fn format_local_date(local_mtime: u64) -> String
+{
+  if local_mtime == 0 {
+    "unknown".to_string()
+  } else {
+    format_date(local_mtime)
+  }
+}
+
+fn print_outdated_table(statuses: &[JsonOutdated])
+{
+  let width = statuses.iter().map(|status| status.docset.len()).max().unwrap_or_default();
+
+  println!("{BOLD}{:<width$}  {:<10}  Available{RESET}", "Docset", "Installed");
+
+  for status in statuses {
+    let docset = status.docset;
+    let installed = format_local_date(status.local_mtime);
+
+    match status.status {
+      OutdatedStatus::UpToDate => {
+        let available = format_date(status.mtime.unwrap_or_default());
+        println!("{GRAY}{docset:<width$}  {installed:<10}  {available}{RESET}");
+      }
+      OutdatedStatus::Outdated => {
+        let available = format_date(status.mtime.unwrap_or_default());
+        println!("{YELLOW}{docset:<width$}  {installed:<10}  {available}{RESET}");
+      }
+      OutdatedStatus::Renamed => {
+        println!("{docset:<width$}  {installed:<10}  possibly renamed to `{}`",
+                 status.candidates.join("`/`"));
+      }
+      OutdatedStatus::Removed => {
+        println!("{docset:<width$}  {installed:<10}  removed from `docs.json`");
+      }
+    }
+  }
+}
+
+pub(crate) fn outdated<Args>(mut args: Args) -> ResultS
+  where Args: Iterator<Item = String>
+{
+  let mut flag_json;
+  let mut flag_help;
+
+  let mut flags = flags![
+    flag_json: BoolFlag, ["--json"],
+    flag_help: BoolFlag, ["--help"]
+  ];
+
+  let args = parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
+  if flag_help {
+    return show_outdated_help();
+  }
+
+  if !is_docs_json_exists()? {
+    return Err(format!("The list of available documents has not yet been \
+                        downloaded. Please run `{PROGRAM_NAME} fetch` first."));
+  }
+  if is_docs_json_old()? {
+    print_warning!("Your `docs.json` was updated more than {} days ago. Run \
+                    `{PROGRAM_NAME} fetch` to retrieve a new list of \
+                    available docsets.",
+                   get_docs_json_max_age()?);
+  }
+
+  let docs = deserialize_docs_json()?;
+
+  let docsets = if args.is_empty() { get_local_docsets()? } else { args };
+  if docsets.is_empty() {
+    return Err("Nothing to do.".to_string());
+  }
+
+  let mut statuses = vec![];
+  for docset in &docsets {
+    if !is_docset_downloaded(docset)? {
+      return Err(format!("Docset `{docset}` is not downloaded. Try running \
+                          `{PROGRAM_NAME} download {docset}`."));
+    }
+    statuses.push(get_outdated_status(docset, &docs)?);
+  }
+
+  if flag_json {
+    print_json(&statuses)?;
+  } else {
+    print_outdated_table(&statuses);
+  }
+
+  let outdated_amount =
+    statuses.iter().filter(|status| status.status == OutdatedStatus::Outdated).count();
+
+  match outdated_amount {
+    0 => Ok(()),
+    1 => Err(format!("1 docset can be updated. Run `{PROGRAM_NAME} download --update-all` to \
+                      update it.")),
+    n => Err(format!("{n} docsets can be updated. Run `{PROGRAM_NAME} download --update-all` \
+                      to update them.")),
+  }
+}
@@ -0,0 +1,843 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use html2text::render::{RichAnnotation, RichDecorator, TaggedLine, TextDecorator};
+use html2text::Colour;
+
+use serde::{Deserialize, Serialize};
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::common::ResultS;
+use crate::common::{
+  get_html_translator_config, get_page_line_from_tagged_line, get_page_line_text,
+  split_off_link_footnotes, PageLine, PageLineElement, PRERENDERED_PAGE_EXTENSION,
+};
+
+// Pages are rendered once at this width, so that nothing except horizontal
+// rules is wrapped. Lines that still reach it can't be re-wrapped correctly.
+const PRERENDER_WIDTH: usize = 1 << 16;
+
+// Block prefixes end with a space. While pre-rendering, the space is replaced
+// with one of these, to tell prefixes from the text. They are as wide as a
+// space, and so don't change the layout.
+const REPEATED_PREFIX_END: char = '\x1e';
+const ITEM_PREFIX_END: char = '\x1f';
+
+#[derive(Clone)]
+struct PrerenderDecorator(RichDecorator);
+
+fn replace_prefix_end(prefix: String, end: char) -> String
+{
+  match prefix.strip_suffix(' ') {
+    Some(stripped) => format!("{stripped}{end}"),
+    None => prefix,
+  }
+}
+
+impl TextDecorator for PrerenderDecorator
+{
+  type Annotation = RichAnnotation;
+
+  fn decorate_link_start(&mut self, url: &str) -> (String, Self::Annotation)
+  {
+    self.0.decorate_link_start(url)
+  }
+
+  fn decorate_link_end(&mut self) -> String
+  {
+    self.0.decorate_link_end()
+  }
+
+  fn decorate_em_start(&self) -> (String, Self::Annotation)
+  {
+    self.0.decorate_em_start()
+  }
+
+  fn decorate_em_end(&self) -> String
+  {
+    self.0.decorate_em_end()
+  }
+
+  fn decorate_strong_start(&self) -> (String, Self::Annotation)
+  {
+    self.0.decorate_strong_start()
+  }
+
+  fn decorate_strong_end(&self) -> String
+  {
+    self.0.decorate_strong_end()
+  }
+
+  fn decorate_strikeout_start(&self) -> (String, Self::Annotation)
+  {
+    self.0.decorate_strikeout_start()
+  }
+
+  fn decorate_strikeout_end(&self) -> String
+  {
+    self.0.decorate_strikeout_end()
+  }
+
+  fn decorate_code_start(&self) -> (String, Self::Annotation)
+  {
+    self.0.decorate_code_start()
+  }
+
+  fn decorate_code_end(&self) -> String
+  {
+    self.0.decorate_code_end()
+  }
+
+  fn decorate_preformat_first(&self) -> Self::Annotation
+  {
+    self.0.decorate_preformat_first()
+  }
+
+  fn decorate_preformat_cont(&self) -> Self::Annotation
+  {
+    self.0.decorate_preformat_cont()
+  }
+
+  fn decorate_image(&mut self, src: &str, title: &str) -> (String, Self::Annotation)
+  {
+    self.0.decorate_image(src, title)
+  }
+
+  fn decorate_superscript_start(&self) -> (String, Self::Annotation)
+  {
+    self.0.decorate_superscript_start()
+  }
+
+  fn decorate_superscript_end(&self) -> String
+  {
+    self.0.decorate_superscript_end()
+  }
+
+  fn header_prefix(&self, level: usize) -> String
+  {
+    replace_prefix_end(self.0.header_prefix(level), REPEATED_PREFIX_END)
+  }
+
+  fn quote_prefix(&self) -> String
+  {
+    replace_prefix_end(self.0.quote_prefix(), REPEATED_PREFIX_END)
+  }
+
+  fn unordered_item_prefix(&self) -> String
+  {
+    replace_prefix_end(self.0.unordered_item_prefix(), ITEM_PREFIX_END)
+  }
+
+  fn ordered_item_prefix(&self, i: i64) -> String
+  {
+    replace_prefix_end(self.0.ordered_item_prefix(i), ITEM_PREFIX_END)
+  }
+
+  fn make_subblock_decorator(&self) -> Self
+  {
+    PrerenderDecorator(self.0.make_subblock_decorator())
+  }
+
+  fn push_colour(&mut self, colour: Colour) -> Option<Self::Annotation>
+  {
+    self.0.push_colour(colour)
+  }
+
+  fn pop_colour(&mut self) -> bool
+  {
+    self.0.pop_colour()
+  }
+
+  fn push_bgcolour(&mut self, colour: Colour) -> Option<Self::Annotation>
+  {
+    self.0.push_bgcolour(colour)
+  }
+
+  fn pop_bgcolour(&mut self) -> bool
+  {
+    self.0.pop_bgcolour()
+  }
+
+  fn finalise(&mut self, urls: Vec<String>) -> Vec<TaggedLine<Self::Annotation>>
+  {
+    self.0.finalise(urls)
+  }
+}
+
+// Only what matters for printing is kept from `RichAnnotation`.
+#[derive(Serialize, Deserialize)]
+enum SpanStyle
+{
+  Link,
+  Image,
+  Emphasis,
+  Strong,
+  Strikeout,
+  Code,
+  Preformat,
+  Colour(u8, u8, u8),
+  BgColour(u8, u8, u8),
+}
+
+impl SpanStyle
+{
+  fn from_annotation(annotation: &RichAnnotation) -> Option<SpanStyle>
+  {
+    match annotation {
+      RichAnnotation::Link(_) => Some(SpanStyle::Link),
+      RichAnnotation::Image(_) => Some(SpanStyle::Image),
+      RichAnnotation::Emphasis => Some(SpanStyle::Emphasis),
+      RichAnnotation::Strong => Some(SpanStyle::Strong),
+      RichAnnotation::Strikeout => Some(SpanStyle::Strikeout),
+      RichAnnotation::Code => Some(SpanStyle::Code),
+      RichAnnotation::Preformat(_) => Some(SpanStyle::Preformat),
+      RichAnnotation::Colour(Colour { r, g, b }) => Some(SpanStyle::Colour(*r, *g, *b)),
+      RichAnnotation::BgColour(Colour { r, g, b }) => Some(SpanStyle::BgColour(*r, *g, *b)),
+      _ => None,
+    }
+  }
+
+  fn to_annotation(&self) -> RichAnnotation
+  {
+    match *self {
+      SpanStyle::Link => RichAnnotation::Link(String::new()),
+      SpanStyle::Image => RichAnnotation::Image(String::new()),
+      SpanStyle::Emphasis => RichAnnotation::Emphasis,
+      SpanStyle::Strong => RichAnnotation::Strong,
+      SpanStyle::Strikeout => RichAnnotation::Strikeout,
+      SpanStyle::Code => RichAnnotation::Code,
+      SpanStyle::Preformat => RichAnnotation::Preformat(false),
+      SpanStyle::Colour(r, g, b) => RichAnnotation::Colour(Colour { r, g, b }),
+      SpanStyle::BgColour(r, g, b) => RichAnnotation::BgColour(Colour { r, g, b }),
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+enum PrerenderedElement
+{
+  Text(String, Vec<SpanStyle>),
+  Fragment(String),
+}
+
+#[derive(Serialize, Deserialize)]
+enum PrerenderedLine
+{
+  // Text that is wrapped to the page width. `prefix` comes from lists, quotes
+  // and headings and goes before the first line, `indent` before the rest.
+  Flow
+  {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    prefix: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    indent: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    preformatted: bool,
+    elements: Vec<PrerenderedElement>,
+  },
+  // Tables are printed as is, if they fit.
+  Fixed(Vec<PrerenderedElement>),
+  // Horizontal rules take all of the page width after `prefix`.
+  Rule(String),
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PrerenderedPage
+{
+  lines: Vec<PrerenderedLine>,
+  pub(crate) links: Vec<String>,
+}
+
+// Characters html2text draws tables with.
+fn is_table_border_char(c: char) -> bool
+{
+  ['─', '│', '┬', '┼', '┴'].contains(&c)
+}
+
+fn is_preformatted(tags: &[RichAnnotation]) -> bool
+{
+  tags.iter().any(|tag| matches!(tag, RichAnnotation::Preformat(_)))
+}
+
+fn to_prerendered_element(element: PageLineElement) -> PrerenderedElement
+{
+  match element {
+    PageLineElement::Text(text, tags) => {
+      let text = text.replace([REPEATED_PREFIX_END, ITEM_PREFIX_END], " ");
+      PrerenderedElement::Text(text, tags.iter().filter_map(SpanStyle::from_annotation).collect())
+    }
+    PageLineElement::Fragment(fragment) => PrerenderedElement::Fragment(fragment),
+  }
+}
+
+fn to_page_line_element(element: &PrerenderedElement) -> PageLineElement
+{
+  match element {
+    PrerenderedElement::Text(text, styles) => {
+      PageLineElement::Text(text.clone(), styles.iter().map(SpanStyle::to_annotation).collect())
+    }
+    PrerenderedElement::Fragment(fragment) => PageLineElement::Fragment(fragment.clone()),
+  }
+}
+
+// -> (prefix, indent, length of the prefix in chars)
+fn split_off_prefix(text: &str) -> (String, String, usize)
+{
+  let chars = text.chars().collect::<Vec<_>>();
+
+  let mut prefix = String::new();
+  let mut indent = String::new();
+  let mut position = 0;
+
+  while position < chars.len() {
+    if chars[position] == ' ' {
+      prefix.push(' ');
+      indent.push(' ');
+      position += 1;
+      continue;
+    }
+
+    let end =
+      chars[position..].iter()
+                       .position(|c| [' ', REPEATED_PREFIX_END, ITEM_PREFIX_END].contains(c))
+                       .map(|offset| position + offset);
+    let (end, end_char) = match end {
+      Some(end) => (end, chars[end]),
+      None => break,
+    };
+
+    let token = chars[position..end].iter().collect::<String>() + " ";
+    match end_char {
+      REPEATED_PREFIX_END => indent += &token,
+      ITEM_PREFIX_END => indent += &" ".repeat(token.width()),
+      _ => break,
+    }
+    prefix += &token;
+    position = end + 1;
+  }
+
+  (prefix, indent, position)
+}
+
+fn prerender_line(page_line: PageLine) -> Option<PrerenderedLine>
+{
+  let text = get_page_line_text(&page_line);
+
+  if text.width() >= PRERENDER_WIDTH {
+    let rule = text.trim_end_matches('─');
+    let (prefix, _, prefix_length) = split_off_prefix(rule);
+    if rule.len() < text.len() && prefix_length == rule.chars().count() {
+      return Some(PrerenderedLine::Rule(prefix));
+    }
+    return None;
+  }
+
+  if text.chars().any(is_table_border_char) {
+    return Some(PrerenderedLine::Fixed(page_line.into_iter()
+                                                .map(to_prerendered_element)
+                                                .collect()));
+  }
+
+  // Prefixes are never preformatted, while text of preformatted blocks can
+  // start with spaces.
+  let leading_text = page_line.iter()
+                              .take_while(|element| match element {
+                                PageLineElement::Text(_, tags) => !is_preformatted(tags),
+                                PageLineElement::Fragment(_) => true,
+                              })
+                              .filter_map(|element| match element {
+                                PageLineElement::Text(text, _) => Some(text.as_str()),
+                                PageLineElement::Fragment(_) => None,
+                              })
+                              .collect::<String>();
+  let (prefix, indent, mut prefix_length) = split_off_prefix(&leading_text);
+
+  let mut preformatted = false;
+  let mut elements = vec![];
+
+  for element in page_line {
+    match element {
+      PageLineElement::Text(text, tags) => {
+        preformatted |= is_preformatted(&tags);
+        let length = text.chars().count();
+        if prefix_length >= length {
+          prefix_length -= length;
+        } else {
+          let text = text.chars().skip(prefix_length).collect();
+          prefix_length = 0;
+          elements.push(to_prerendered_element(PageLineElement::Text(text, tags)));
+        }
+      }
+      fragment => elements.push(to_prerendered_element(fragment)),
+    }
+  }
+
+  Some(PrerenderedLine::Flow { prefix, indent, preformatted, elements })
+}
+
+// Returns `None` if the page can't be pre-rendered.
+pub(crate) fn prerender_html(html: &str) -> Option<PrerenderedPage>
+{
+  let translator_config = get_html_translator_config(PrerenderDecorator(RichDecorator::new()));
+  let mut page_lines = translator_config.lines_from_read(html.as_bytes(), PRERENDER_WIDTH)
+                                        .ok()?
+                                        .into_iter()
+                                        .map(get_page_line_from_tagged_line)
+                                        .collect();
+
+  let links = split_off_link_footnotes(&mut page_lines);
+  let lines = page_lines.into_iter().map(prerender_line).collect::<Option<_>>()?;
+
+  Some(PrerenderedPage { lines, links })
+}
+
+fn push_text(page_line: &mut PageLine, text: &str, tags: &[RichAnnotation])
+{
+  if text.is_empty() {
+    return;
+  }
+  if let Some(PageLineElement::Text(last_text, last_tags)) = page_line.last_mut() {
+    if last_tags == tags {
+      last_text.push_str(text);
+      return;
+    }
+  }
+  page_line.push(PageLineElement::Text(text.to_owned(), tags.to_vec()));
+}
+
+fn has_text(page_line: &PageLine) -> bool
+{
+  page_line.iter()
+           .any(|element| matches!(element, PageLineElement::Text(text, _) if !text.is_empty()))
+}
+
+// Wraps text the same way html2text does.
+struct LineWrapper
+{
+  width: usize,
+  preformatted: bool,
+  lines: Vec<PageLine>,
+  line: PageLine,
+  line_width: usize,
+  word: PageLine,
+  word_width: usize,
+  space_width: usize,
+  space_tags: Vec<RichAnnotation>,
+}
+
+impl LineWrapper
+{
+  fn new(width: usize, preformatted: bool) -> Self
+  {
+    LineWrapper { width,
+                  preformatted,
+                  lines: vec![],
+                  line: vec![],
+                  line_width: 0,
+                  word: vec![],
+                  word_width: 0,
+                  space_width: 0,
+                  space_tags: vec![] }
+  }
+
+  fn push_to_line(&mut self, element: PageLineElement)
+  {
+    match element {
+      PageLineElement::Text(text, tags) => {
+        self.line_width += text.width();
+        push_text(&mut self.line, &text, &tags);
+      }
+      fragment => self.line.push(fragment),
+    }
+  }
+
+  fn push_spaces(&mut self, amount: usize)
+  {
+    let tags = self.space_tags.clone();
+    self.push_to_line(PageLineElement::Text(" ".repeat(amount), tags));
+  }
+
+  fn flush_line(&mut self)
+  {
+    if has_text(&self.line) {
+      self.force_flush_line();
+    }
+  }
+
+  fn force_flush_line(&mut self)
+  {
+    self.lines.push(std::mem::take(&mut self.line));
+    self.line_width = 0;
+  }
+
+  fn add_text(&mut self, text: &str, tags: &[RichAnnotation])
+  {
+    for c in text.chars() {
+      if c.is_whitespace() && self.word_width > 0 {
+        self.flush_word();
+      }
+
+      if c.is_whitespace() {
+        if self.preformatted {
+          if let Some(c_width) = c.width() {
+            if self.line_width + self.space_width + c_width > self.width {
+              self.space_width = 0;
+              self.flush_line();
+            }
+            self.space_width += c_width;
+            self.space_tags = tags.to_vec();
+          }
+        } else if self.line_width > 0 && self.space_width == 0 {
+          self.space_width = 1;
+          self.space_tags = tags.to_vec();
+        }
+      } else if let Some(c_width) = c.width() {
+        self.word_width += c_width;
+        push_text(&mut self.word, c.encode_utf8(&mut [0; 4]), tags);
+      }
+    }
+  }
+
+  fn add_fragment(&mut self, fragment: &str)
+  {
+    self.word.push(PageLineElement::Fragment(fragment.to_owned()));
+  }
+
+  fn flush_word(&mut self)
+  {
+    if has_text(&self.word) {
+      let space_in_line = self.width.saturating_sub(self.line_width);
+
+      if self.space_width + self.word_width <= space_in_line {
+        if self.space_width > 0 {
+          self.push_spaces(self.space_width);
+          self.space_width = 0;
+        }
+        for element in std::mem::take(&mut self.word) {
+          self.push_to_line(element);
+        }
+      } else {
+        if !self.preformatted {
+          self.space_width = 0;
+        } else if self.space_width >= space_in_line {
+          self.space_width -= space_in_line;
+        } else if self.space_width > 0 {
+          self.push_spaces(self.space_width);
+          self.space_width = 0;
+        }
+
+        self.flush_line();
+
+        while self.space_width > 0 {
+          let amount = self.space_width.min(self.width);
+          self.push_spaces(amount);
+          if amount == self.width {
+            self.flush_line();
+          }
+          self.space_width -= amount;
+        }
+
+        self.flush_word_hard_wrap();
+      }
+    }
+    self.word_width = 0;
+  }
+
+  // Unlike html2text, this keeps fragments of words that don't fit.
+  fn flush_word_hard_wrap(&mut self)
+  {
+    let mut line_left = self.width.saturating_sub(self.line_width);
+
+    for element in std::mem::take(&mut self.word) {
+      let (text, tags) = match element {
+        PageLineElement::Text(text, tags) => (text, tags),
+        fragment => {
+          self.line.push(fragment);
+          continue;
+        }
+      };
+
+      let text_width = text.width();
+      let mut copied_width = 0;
+      let mut copied_bytes = 0;
+
+      while text_width - copied_width > line_left {
+        let mut split_index = text.len() - copied_bytes;
+        for (index, c) in text[copied_bytes..].char_indices() {
+          let c_width = c.width().unwrap_or(0);
+          if c_width <= line_left {
+            line_left -= c_width;
+            copied_width += c_width;
+          } else {
+            // Overflow if even one character doesn't fit.
+            if index == 0 && self.line_width == 0 {
+              split_index = c.len_utf8();
+              copied_width += c_width;
+            } else {
+              split_index = index;
+            }
+            break;
+          }
+        }
+        let piece = text[copied_bytes..copied_bytes + split_index].to_owned();
+        self.push_to_line(PageLineElement::Text(piece, tags.clone()));
+        copied_bytes += split_index;
+        self.force_flush_line();
+        line_left = self.width;
+      }
+
+      if copied_bytes < text.len() {
+        line_left = line_left.saturating_sub(text_width.saturating_sub(copied_width));
+        self.push_to_line(PageLineElement::Text(text[copied_bytes..].to_owned(), tags));
+      }
+    }
+  }
+
+  // Fragments that are not followed by text stay on the last line.
+  //
+  // html2text doesn't keep whitespace before the last word of a preformatted
+  // block when it wraps it, so `ends_block` should be set for the last line.
+  fn finish(mut self, ends_block: bool) -> Vec<PageLine>
+  {
+    self.preformatted &= !ends_block;
+    self.flush_word();
+    self.flush_line();
+
+    let trailing_fragments = self.line.into_iter().chain(self.word).collect::<Vec<_>>();
+    match self.lines.last_mut() {
+      Some(last_line) => last_line.extend(trailing_fragments),
+      None => self.lines.push(trailing_fragments),
+    }
+
+    self.lines
+  }
+}
+
+fn insert_prefix(page_line: &mut PageLine, prefix: &str)
+{
+  if prefix.is_empty() {
+    return;
+  }
+  if let Some(PageLineElement::Text(text, tags)) = page_line.first_mut() {
+    if tags.is_empty() {
+      text.insert_str(0, prefix);
+      return;
+    }
+  }
+  page_line.insert(0, PageLineElement::Text(prefix.to_owned(), vec![]));
+}
+
+fn get_elements_width(elements: &[PrerenderedElement]) -> usize
+{
+  elements.iter()
+          .map(|element| match element {
+            PrerenderedElement::Text(text, _) => text.width(),
+            PrerenderedElement::Fragment(_) => 0,
+          })
+          .sum()
+}
+
+impl PrerenderedPage
+{
+  // Blank lines of preformatted blocks can't be told apart from blank lines
+  // between blocks, so it's assumed that there's only one block if
+  // preformatted lines are separated only by blank lines.
+  fn is_preformatted_block_continued(&self, line_number: usize) -> bool
+  {
+    let indent = match &self.lines[line_number] {
+      PrerenderedLine::Flow { indent, .. } => indent,
+      _ => return false,
+    };
+
+    self.lines[line_number + 1..].iter()
+                                 .find(|line| {
+                                   !matches!(line, PrerenderedLine::Flow { elements, .. }
+                                                   if elements.is_empty())
+                                 })
+                                 .is_some_and(|line| {
+                                   matches!(line, PrerenderedLine::Flow { indent: next_indent,
+                                                                          preformatted: true,
+                                                                          .. }
+                                                  if next_indent == indent)
+                                 })
+  }
+
+  // Returns `None` if the page doesn't fit `width` without being rendered
+  // again.
+  pub(crate) fn wrap_lines(&self, width: usize) -> Option<Vec<PageLine>>
+  {
+    let mut page_lines = vec![];
+
+    for (line_number, line) in self.lines.iter().enumerate() {
+      match line {
+        PrerenderedLine::Flow { prefix, indent, preformatted, elements } => {
+          let text_width = width.checked_sub(prefix.width()).filter(|w| *w > 0)?;
+
+          // html2text breaks preformatted lines that are too long in its own
+          // way, so the page is rendered again instead.
+          if *preformatted && get_elements_width(elements) > text_width {
+            return None;
+          }
+
+          let mut wrapper = LineWrapper::new(text_width, *preformatted);
+
+          for element in elements {
+            match element {
+              PrerenderedElement::Text(text, styles) => {
+                let tags = styles.iter().map(SpanStyle::to_annotation).collect::<Vec<_>>();
+                wrapper.add_text(text, &tags);
+              }
+              PrerenderedElement::Fragment(fragment) => wrapper.add_fragment(fragment),
+            }
+          }
+
+          let ends_block = *preformatted && !self.is_preformatted_block_continued(line_number);
+          for (i, mut page_line) in wrapper.finish(ends_block).into_iter().enumerate() {
+            insert_prefix(&mut page_line, if i == 0 { prefix } else { indent });
+            page_lines.push(page_line);
+          }
+        }
+        PrerenderedLine::Fixed(elements) => {
+          let page_line = elements.iter().map(to_page_line_element).collect::<PageLine>();
+          if get_page_line_text(&page_line).width() > width {
+            return None;
+          }
+          page_lines.push(page_line);
+        }
+        PrerenderedLine::Rule(prefix) => {
+          let rule_width = width.checked_sub(prefix.width()).filter(|w| *w > 0)?;
+          page_lines.push(vec![PageLineElement::Text(prefix.to_owned() + &"─".repeat(rule_width),
+                                                     vec![])]);
+        }
+      }
+    }
+
+    Some(page_lines)
+  }
+}
+
+pub(crate) fn get_prerendered_page_path(html_path: &Path) -> PathBuf
+{
+  html_path.with_extension(PRERENDERED_PAGE_EXTENSION)
+}
+
+pub(crate) fn write_prerendered_page(html_path: &Path, page: &PrerenderedPage) -> ResultS
+{
+  let path = get_prerendered_page_path(html_path);
+
+  let file =
+    File::create(&path).map_err(|err| format!("Could not create `{}`: {err}", path.display()))?;
+  serde_json::to_writer(BufWriter::new(file), page).map_err(|err| {
+                                                     format!("Could not write to `{}`: {err}",
+                                                             path.display())
+                                                   })
+}
+
+// Pages that were pre-rendered by other versions may not parse, and are
+// rendered from HTML instead.
+pub(crate) fn read_prerendered_page(html_path: &Path) -> Result<Option<PrerenderedPage>, String>
+{
+  let path = get_prerendered_page_path(html_path);
+
+  let file = match File::open(&path) {
+    Ok(file) => file,
+    Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+    Err(err) => return Err(format!("Could not open `{}`: {err}", path.display())),
+  };
+
+  Ok(serde_json::from_reader(BufReader::new(file)).ok())
+}
+
+pub(crate) fn remove_prerendered_page(html_path: &Path) -> ResultS
+{
+  let path = get_prerendered_page_path(html_path);
+
+  match std::fs::remove_file(&path) {
+    Err(err) if err.kind() != ErrorKind::NotFound => {
+      Err(format!("Could not remove `{}`: {err}", path.display()))
+    }
+    _ => Ok(()),
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  // Link targets are not kept, so only text and fragments are compared.
+  fn get_text_and_fragments(page_lines: Vec<PageLine>) -> Vec<(String, Vec<String>)>
+  {
+    page_lines.into_iter()
+              .map(|page_line| {
+                let fragments =
+                  page_line.iter()
+                           .filter_map(|element| match element {
+                             PageLineElement::Fragment(fragment) => Some(fragment.to_owned()),
+                             PageLineElement::Text(..) => None,
+                           })
+                           .collect();
+                (get_page_line_text(&page_line), fragments)
+              })
+              .collect()
+  }
+
+  fn render_html(html: &str, width: usize) -> Vec<PageLine>
+  {
+    let mut page_lines =
+      get_html_translator_config(RichDecorator::new()).lines_from_read(html.as_bytes(), width)
+                                                      .unwrap()
+                                                      .into_iter()
+                                                      .map(get_page_line_from_tagged_line)
+                                                      .collect();
+    split_off_link_footnotes(&mut page_lines);
+    page_lines
+  }
+
+  #[test]
+  fn test_prerendered_page_wrapping()
+  {
+    let html = "<h2 id=\"intro\">A heading that is long enough to be wrapped</h2>\
+                <p>Some <b>bold</b>, <code>code</code> and <a href=\"x\">a link</a>, with \
+                a_very_long_word_that_does_not_fit_anywhere.</p>\
+                <ul><li>First item of a list with text<ol><li>Nested ordered item</li>\
+                <li id=\"second\">Second one</li></ol></li>\
+                <li><p>Two</p><p>paragraphs</p></li></ul>\
+                <blockquote><p>Quoted text that goes on and on</p></blockquote>\
+                <pre>fn main() {\n    println!(\"hi\");\n}</pre><hr>\
+                <table><tr><th>a</th><th>b</th></tr><tr><td>1</td><td>0123456789</td></tr></table>";
+
+    let page = prerender_html(html).unwrap();
+    assert_eq!(page.links, vec!["x"]);
+
+    for width in [24, 37, 80] {
+      assert_eq!(get_text_and_fragments(page.wrap_lines(width).unwrap()),
+                 get_text_and_fragments(render_html(html, width)),
+                 "width {width}");
+    }
+
+    // Tables that don't fit have to be rendered again.
+    assert!(page.wrap_lines(10).is_none());
+  }
+
+  #[test]
+  fn test_prerendered_page_with_long_preformatted_line()
+  {
+    let html = "<p>Some text</p><pre><code>let row = conn.query_one(\"SELECT waifu FROM anime\", \
+                &amp;[])?;\n    a_long_identifier_without_any_spaces_in_it();</code></pre>";
+
+    let page = prerender_html(html).unwrap();
+
+    for width in [12, 20, 30, 44, 80] {
+      let page_lines = page.wrap_lines(width).unwrap_or_else(|| render_html(html, width));
+      assert_eq!(get_text_and_fragments(page_lines),
+                 get_text_and_fragments(render_html(html, width)),
+                 "width {width}");
+    }
+
+    assert!(page.wrap_lines(44).is_none());
+    assert!(page.wrap_lines(80).is_some());
+  }
+}
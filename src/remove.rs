@@ -1,10 +1,16 @@
 use std::fs::remove_dir_all;
 
+use serde::Serialize;
+
 use toiletcli::flags;
 use toiletcli::flags::*;
 
+use crate::bundle::remove_local_docs_entry;
 use crate::common::ResultS;
-use crate::common::{get_docset_path, get_flag_error, get_local_docsets, is_docset_downloaded};
+use crate::common::{
+  get_docset_path, get_docset_staging_path, get_flag_error, get_local_docsets,
+  get_previous_docset_path, is_docset_downloaded, print_json, remove_dir_if_exists,
+};
 use crate::common::{BOLD, GREEN, PROGRAM_NAME, RESET};
 use crate::print_warning;
 
@@ -21,12 +27,13 @@ fn show_remove_help() -> ResultS
 
 {GREEN}OPTIONS{RESET}
         --purge-all                 Remove all installed docsets.
+        --json                      Print the status of each docset as JSON.
         --help                      Display help message."
   );
   Ok(())
 }
 
-fn is_name_allowed(docset_name: &str) -> bool
+pub(crate) fn is_name_allowed(docset_name: &str) -> bool
 {
   let has_slashes = {
     #[cfg(target_family = "windows")]
@@ -49,27 +56,63 @@ fn is_name_allowed(docset_name: &str) -> bool
   !is_bad
 }
 
+// The previous version, unfinished downloads and the entry of a local docset
+// are not useful without the docset.
+fn remove_docset_leftovers(docset_name: &str) -> ResultS
+{
+  remove_dir_if_exists(&get_previous_docset_path(docset_name)?)?;
+  remove_dir_if_exists(&get_docset_staging_path(docset_name)?)?;
+  remove_local_docs_entry(docset_name)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum RemoveStatus
+{
+  Removed,
+  NotInstalled,
+  ForbiddenName,
+}
+
+#[derive(Serialize)]
+struct JsonRemove<'a>
+{
+  docset: &'a str,
+  status: RemoveStatus,
+}
+
 pub(crate) fn remove<Args>(mut args: Args) -> ResultS
   where Args: Iterator<Item = String>
 {
   let mut flag_purge_all;
+  let mut flag_json;
   let mut flag_help;
 
   let mut flags = flags![
     flag_help: BoolFlag,      ["--help"],
-    flag_purge_all: BoolFlag, ["--purge-all"]
+    flag_purge_all: BoolFlag, ["--purge-all"],
+    flag_json: BoolFlag,      ["--json"]
   ];
 
   let args = parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
 
+  let mut statuses = vec![];
+
   if flag_purge_all {
     let local_docsets = get_local_docsets()?;
-    for docset in local_docsets {
-      let docset_path = get_docset_path(&docset)?;
-      println!("Removing `{docset}` from `{}`...", docset_path.display());
+    for docset in &local_docsets {
+      let docset_path = get_docset_path(docset)?;
+      if !flag_json {
+        println!("Removing `{docset}` from `{}`...", docset_path.display());
+      }
       remove_dir_all(&docset_path).map_err(|err| {
                                     format!("Unable to remove `{}`: {err}", docset_path.display())
                                   })?;
+      remove_docset_leftovers(docset)?;
+      statuses.push(JsonRemove { docset, status: RemoveStatus::Removed });
+    }
+    if flag_json {
+      print_json(&statuses)?;
     }
     return Ok(());
   }
@@ -81,22 +124,32 @@ pub(crate) fn remove<Args>(mut args: Args) -> ResultS
   for docset in args.iter() {
     if !is_name_allowed(docset) {
       print_warning!("`{docset}` contains forbidden characters.");
+      statuses.push(JsonRemove { docset, status: RemoveStatus::ForbiddenName });
       continue;
     }
 
     if is_docset_downloaded(docset)? {
       let docset_path = get_docset_path(docset)?;
       if docset_path.exists() {
-        println!("Removing `{docset}` from `{}`...", docset_path.display());
+        if !flag_json {
+          println!("Removing `{docset}` from `{}`...", docset_path.display());
+        }
         remove_dir_all(&docset_path).map_err(|err| {
                                       format!("Unable to remove `{}`: {err}", docset_path.display())
                                     })?;
       }
+      remove_docset_leftovers(docset)?;
+      statuses.push(JsonRemove { docset, status: RemoveStatus::Removed });
     } else {
       print_warning!("`{docset}` is not installed.");
+      statuses.push(JsonRemove { docset, status: RemoveStatus::NotInstalled });
     }
   }
 
+  if flag_json {
+    print_json(&statuses)?;
+  }
+
   Ok(())
 }
 
@@ -106,6 +159,7 @@ mod tests
   use super::*;
 
   #[test]
+  #[allow(clippy::needless_borrow)]
   fn test_sanitize_names()
   {
     let bad_name_path = "/what";
@@ -6,12 +6,15 @@ use toiletcli::flags;
 use toiletcli::flags::*;
 
 use crate::common::{
-  deserialize_docs_json, get_docset_path, get_flag_error, get_local_docsets, is_docs_json_exists,
-  is_docset_downloaded, make_sure_docset_is_in_docs, translate_docset_html_file_to_text,
-  DOC_PAGE_EXTENSION,
+  deserialize_docs_json, get_docset_pages_path, get_flag_error, get_local_docsets,
+  is_docs_json_exists, is_docset_downloaded, make_sure_docset_is_in_docs,
+  translate_docset_html_file_to_text, DOC_PAGE_EXTENSION,
 };
 use crate::common::{get_program_directory, validate_number_of_columns, ResultS, MAX_WIDTH};
 use crate::common::{BOLD, GREEN, PROGRAM_NAME, RESET};
+use crate::config::{get_config_bool, get_config_columns, get_config_value};
+use crate::man::{get_man_page_name, translate_docset_html_file_to_man, MAN_SECTION};
+use crate::markdown::translate_docset_html_file_to_markdown;
 use crate::print_warning;
 
 fn show_render_help() -> ResultS
@@ -20,13 +23,23 @@ fn show_render_help() -> ResultS
            "\
 {GREEN}USAGE{RESET}
     {BOLD}{PROGRAM_NAME} render{RESET} [-OPTIONS] <docset1> [docset2, ...]
-    Render a whole docset to text.
+    Render a whole docset to text, Markdown or man pages.
 
 {GREEN}OPTIONS{RESET}
     -c, --columns                   Change output width in columns. Default is
-                                    144.
+                                    `max_width` from the config, or 144.
     -d, --output-dir                Specify output directory. Default is
+                                    `render.output_dir` from the config, or
                                     `~/.dedoc/rendered/<docset>`.
+    -m, --markdown                  Render pages to Markdown instead of text.
+                                    Links between pages are kept. `-c` is
+                                    ignored.
+        --no-markdown               Render text, even if `render.markdown` is
+                                    set in the config.
+        --man                       Render pages to man pages in `man3`, so
+                                    the output directory can be added to
+                                    `$MANPATH`. Run `mandb` on it to find
+                                    pages with `apropos`. `-c` is ignored.
         --all                       Render all docsets. In case of `-d`, a
                                     subdirectory will be created for each
                                     docset.
@@ -35,13 +48,23 @@ fn show_render_help() -> ResultS
   Ok(())
 }
 
-fn render_docset_with_progess(docset: &str, output_dir: &Path, page_width: usize) -> ResultS
+#[derive(Clone, Copy)]
+enum RenderFormat
+{
+  // Width of the page.
+  Text(usize),
+  Markdown,
+  // Pages are not laid out as in the docset, but put into one directory.
+  Man,
+}
+
+fn render_docset_with_progess(docset: &str, output_dir: &Path, format: RenderFormat) -> ResultS
 {
   fn recurse_and_render_docset_with_progress(docset: &str,
                                              docset_path: &Path,
                                              path: &Path,
                                              output_dir: &Path,
-                                             page_width: usize,
+                                             format: RenderFormat,
                                              counter: &mut usize)
                                              -> ResultS
   {
@@ -59,24 +82,25 @@ fn render_docset_with_progess(docset: &str, output_dir: &Path, page_width: usize
 
       // Is this a directory?
       if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
-        let md_dir_path = &output_dir.join(&path_relative_to_docset);
-        if !md_dir_path.try_exists()
-                       .map_err(|err| {
-                         format!("Could not check if {} exists: {err}", docset_path.display())
-                       })?
+        let rendered_dir_path = &output_dir.join(&path_relative_to_docset);
+        if !matches!(format, RenderFormat::Man) &&
+           !rendered_dir_path.try_exists()
+                             .map_err(|err| {
+                               format!("Could not check if {} exists: {err}", docset_path.display())
+                             })?
         {
           // Create the respective path in our target directory.
-          create_dir(md_dir_path).map_err(|err| {
-                                   format!("Could not create subdirectory `{}`: {err}",
-                                           md_dir_path.display())
-                                 })?;
+          create_dir(rendered_dir_path).map_err(|err| {
+                                         format!("Could not create subdirectory `{}`: {err}",
+                                                 rendered_dir_path.display())
+                                       })?;
         }
         // Recurse into the directory.
         recurse_and_render_docset_with_progress(docset,
                                                 docset_path,
                                                 &entry.path(),
                                                 output_dir,
-                                                page_width,
+                                                format,
                                                 counter)?;
         continue;
       }
@@ -86,17 +110,44 @@ fn render_docset_with_progess(docset: &str, output_dir: &Path, page_width: usize
       }
       // Ok, this is a docset page file.
 
-      let mut md_file_path = output_dir.join(&path_relative_to_docset);
-      md_file_path.set_extension("md");
+      let item = path_relative_to_docset.with_extension("").display().to_string();
 
-      let mut file = File::create(&md_file_path).map_err(|err| {
-                                                  format!("Could not create `{}`: {}",
-                                                          md_file_path.display(),
-                                                          err)
-                                                })?;
+      let rendered_file_path = match format {
+        RenderFormat::Text(_) | RenderFormat::Markdown => {
+          output_dir.join(&path_relative_to_docset).with_extension("md")
+        }
+        RenderFormat::Man => {
+          output_dir.join(format!("man{MAN_SECTION}"))
+                    .join(format!("{}.{MAN_SECTION}", get_man_page_name(docset, &item)))
+        }
+      };
+
+      let mut file = File::create(&rendered_file_path).map_err(|err| {
+                                                        format!("Could not create `{}`: {}",
+                                                                rendered_file_path.display(),
+                                                                err)
+                                                      })?;
+
+      let (rendered_page, _) = match format {
+        RenderFormat::Text(width) => translate_docset_html_file_to_text(entry.path(),
+                                                                        Some((docset, &item)),
+                                                                        None,
+                                                                        width,
+                                                                        false,
+                                                                        false)?,
+        RenderFormat::Markdown => {
+          translate_docset_html_file_to_markdown(&entry.path(), Some(&item), None, false)?
+        }
+        RenderFormat::Man => {
+          translate_docset_html_file_to_man(&entry.path(), Some((docset, &item)), None)?
+        }
+      };
 
-      file.write(translate_docset_html_file_to_text(entry.path(), None, page_width, false, false)?.0.as_bytes())
-        .map_err(|err| format!("Could not write to `{}`: {}", md_file_path.display(), err))?;
+      file.write_all(rendered_page.as_bytes()).map_err(|err| {
+                                                 format!("Could not write to `{}`: {}",
+                                                         rendered_file_path.display(),
+                                                         err)
+                                               })?;
       let _ = file.flush();
 
       *counter += 1;
@@ -108,12 +159,20 @@ fn render_docset_with_progess(docset: &str, output_dir: &Path, page_width: usize
     Ok(())
   }
 
+  if matches!(format, RenderFormat::Man) {
+    let man_path = output_dir.join(format!("man{MAN_SECTION}"));
+    create_dir_all(&man_path).map_err(|err| {
+                               format!("Could not create subdirectory `{}`: {err}",
+                                       man_path.display())
+                             })?;
+  }
+
   let mut counter = 0;
   recurse_and_render_docset_with_progress(docset,
-                                          &get_docset_path(docset)?,
-                                          &get_docset_path(docset)?,
+                                          &get_docset_pages_path(docset)?,
+                                          &get_docset_pages_path(docset)?,
                                           output_dir,
-                                          page_width,
+                                          format,
                                           &mut counter)?;
   println!();
 
@@ -126,11 +185,17 @@ pub(crate) fn render<Args>(mut args: Args) -> ResultS
   let mut flag_all;
   let mut flag_columns;
   let mut flag_output_dir;
+  let mut flag_markdown;
+  let mut flag_man;
+  let mut flag_no_markdown;
   let mut flag_help;
 
   let mut flags = flags![
     flag_columns: StringFlag,    ["-c", "--columns"],
     flag_output_dir: StringFlag, ["-d", "--output-dir"],
+    flag_markdown: BoolFlag,     ["-m", "--markdown"],
+    flag_man: BoolFlag,          ["--man"],
+    flag_no_markdown: BoolFlag,  ["--no-markdown"],
     flag_all: BoolFlag,          ["--all"],
     flag_help: BoolFlag,         ["--help"]
   ];
@@ -140,6 +205,8 @@ pub(crate) fn render<Args>(mut args: Args) -> ResultS
     return show_render_help();
   }
 
+  flag_markdown |= !flag_no_markdown && get_config_bool("render.markdown")?;
+
   if !is_docs_json_exists()? {
     return Err(format!(
       "The list of available documents has not yet been downloaded. \
@@ -153,14 +220,26 @@ pub(crate) fn render<Args>(mut args: Args) -> ResultS
   // rendered directly into the specified directory. However, in case of `--all`
   // with `-d`, a subfolder for each docset is created inside the directory from
   // `-d`.
-  let main_output_dir = if !is_directory_changed {
-    get_program_directory()?.join("rendered")
-  } else {
+  // Directory from the config is treated the same way as the default one.
+  let main_output_dir = if is_directory_changed {
     flag_output_dir.into()
+  } else if let Some(config_output_dir) = get_config_value("render.output_dir")? {
+    config_output_dir.into()
+  } else {
+    get_program_directory()?.join("rendered")
   };
 
-  let page_width =
-    if flag_columns.is_empty() { MAX_WIDTH } else { validate_number_of_columns(&flag_columns)? };
+  let format = if flag_man {
+    RenderFormat::Man
+  } else if flag_markdown {
+    RenderFormat::Markdown
+  } else if !flag_columns.is_empty() {
+    RenderFormat::Text(validate_number_of_columns(&flag_columns)?)
+  } else if let Some(c) = get_config_columns("render.columns")? {
+    RenderFormat::Text(c)
+  } else {
+    RenderFormat::Text(get_config_columns("max_width")?.unwrap_or(MAX_WIDTH))
+  };
 
   if is_directory_changed &&
      main_output_dir.try_exists()
@@ -199,7 +278,7 @@ pub(crate) fn render<Args>(mut args: Args) -> ResultS
                                format!("Could not create subdirectory `{}`: {err}",
                                        sub_dir.display())
                              })?;
-      render_docset_with_progess(docset, sub_dir, page_width)?;
+      render_docset_with_progess(docset, sub_dir, format)?;
     }
   } else {
     for docset in args {
@@ -214,7 +293,7 @@ pub(crate) fn render<Args>(mut args: Args) -> ResultS
                                    format!("Could not create subdirectory `{}`: {err}",
                                            output_dir.display())
                                  })?;
-      render_docset_with_progess(&docset, &output_dir, page_width)?;
+      render_docset_with_progess(&docset, &output_dir, format)?;
     }
   }
 
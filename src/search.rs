@@ -1,9 +1,10 @@
 #![allow(clippy::useless_format)]
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fs::{read_dir, File};
 use std::io::{BufRead, BufReader, BufWriter};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
@@ -11,14 +12,19 @@ use toiletcli::flags;
 use toiletcli::flags::*;
 
 use crate::common::{
-  deserialize_docs_json, get_docset_path, get_flag_error, get_program_directory,
-  get_terminal_width, is_docs_json_exists, is_docset_downloaded, print_page_from_docset,
+  deserialize_docs_json, get_docset_page_path, get_docset_pages_path, get_docset_path,
+  get_flag_error, get_local_docsets, get_program_directory, get_terminal_width,
+  is_docs_json_exists, is_docset_downloaded, print_json, print_page_from_docset,
   split_to_item_and_fragment, validate_number_of_columns,
 };
 use crate::common::{make_sure_docset_is_in_docs, ResultS};
 use crate::common::{
-  BOLD, DOC_PAGE_EXTENSION, GRAY, GRAYER, GRAYEST, GREEN, LIGHT_GRAY, PROGRAM_NAME, RESET,
+  BOLD, DOC_PAGE_EXTENSION, GRAY, GRAYER, GRAYEST, GREEN, LIGHT_GRAY, PROGRAM_NAME, RESET, YELLOW,
 };
+use crate::config::{get_config_bool, get_config_columns};
+use crate::dash::{get_dash_docset_path, read_dash_index};
+use crate::index::{get_text_words, read_fulltext_index, score_page};
+use crate::open::print_page_as_json;
 use crate::print_warning;
 
 fn show_search_help() -> ResultS
@@ -32,19 +38,37 @@ fn show_search_help() -> ResultS
     {BOLD}{PROGRAM_NAME} search{RESET} [-OPTIONS] <docset> [query] --open <number>
     Open a docset page that matches the query and a page number.
 
+    {BOLD}{PROGRAM_NAME} search{RESET} [-OPTIONS] <docset1,docset2,..> [query]
+    {BOLD}{PROGRAM_NAME} search{RESET} [-OPTIONS] --all [query]
+    Search several or all local docsets at once.
+
 {GREEN}OPTIONS{RESET}
     -w, --whole                     Search for the whole sentence.
     -i, --ignore-case               Ignore character case.
-    -p, --precise                   Look inside files (like `grep`).
+    -p, --precise                   Look inside files (like `grep`) for pages
+                                    with every word of the query. Pages that
+                                    mention them more often come first.
+    -z, --fuzzy                     Match characters of the query in order,
+                                    allowing gaps and a typo, and sort results
+                                    by relevance.
+    -a, --all                       Search all local docsets.
     -o, --open <number>             Open N-th result.
         --porcelain                 Display the output in simpler format.
+        --json                      Display results, or the page with
+                                    `--open`, as JSON.
+        --no-<option>               Turn off an option that is set in the
+                                    config, like `--no-fuzzy`.
         --help                      Display help message.
 
   Options that work with `--open`:
     -f, --ignore-fragment           Ignore the fragment and open the entire
                                     page.
     -c, --columns <number>          Make output N columns wide.
-    -n, --line-numbers              Number outputted lines."
+    -n, --line-numbers              Number outputted lines.
+        --no-pager                  Don't pass the page to `$PAGER` when
+                                    output is a terminal.
+        --pager                     Pass the page to `$PAGER`, even if
+                                    `search.no_pager` is set in the config."
   );
   Ok(())
 }
@@ -52,24 +76,33 @@ fn show_search_help() -> ResultS
 #[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone)]
 struct ExactResult
 {
+  // Only set when several docsets are searched at once.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  docset: Option<String>,
   item: String,
   fragment: Option<String>,
+  // Indices of characters in `item#fragment` that matched a fuzzy query.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  highlights: Vec<usize>,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone)]
 struct VagueResult
 {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  docset: Option<String>,
   item: String,
   contexts: Vec<String>,
 }
 
 // Flags that change search result must be added here for cache to be updated.
 #[derive(Serialize, Deserialize, Default, PartialEq, Clone)]
-struct SearchOptions
+pub(crate) struct SearchOptions
 {
-  case_insensitive: bool,
-  precise: bool,
-  whole: bool,
+  pub case_insensitive: bool,
+  pub precise: bool,
+  pub whole: bool,
+  pub fuzzy: bool,
 }
 
 struct OpenOptions
@@ -80,6 +113,7 @@ struct OpenOptions
   ignore_fragment: bool,
   page_width: Option<usize>,
   line_numbers: bool,
+  no_pager: bool,
 }
 
 // Sometimes search results are big, and it's cheaper to check a small file if
@@ -89,6 +123,7 @@ struct OpenOptions
 pub(crate) struct SearchContext<'a>
 {
   query: Cow<'a, str>,
+  // Names of searched docsets, separated by commas.
   docset: Cow<'a, str>,
   options: Cow<'a, SearchOptions>,
 }
@@ -164,29 +199,39 @@ fn cache_search_results(search_options: &SearchContext, search_cache: &SearchCac
 
 #[allow(dead_code)]
 #[derive(Deserialize, Default)]
-struct IndexEntry
+pub(crate) struct IndexEntry
 {
-  name: String,
-  path: String,
+  pub name: String,
+  pub path: String,
   #[serde(skip)]
   r#type: String,
 }
 
 #[derive(Deserialize)]
-struct IndexJson
+pub(crate) struct IndexJson
 {
-  entries: Vec<IndexEntry>,
+  pub entries: Vec<IndexEntry>,
 }
 
 type ExactMatches = Vec<ExactResult>;
 type VagueMatches = Vec<VagueResult>;
+// (score, length of the path, result)
+type ScoredMatches = Vec<(i64, usize, ExactResult)>;
 
-fn search_docset_in_filenames(docset_name: &str,
-                              query: &str,
-                              case_insensitive: bool)
-                              -> Result<ExactMatches, String>
+pub(crate) fn deserialize_index_json(docset_name: &str) -> Result<IndexJson, String>
 {
   let docset_path = get_docset_path(docset_name)?;
+
+  if let Some(dash_path) = get_dash_docset_path(&docset_path)? {
+    let entries =
+      read_dash_index(&dash_path)?.into_iter()
+                                  .map(|(name, path)| IndexEntry { name,
+                                                                   path,
+                                                                   r#type: String::new() })
+                                  .collect();
+    return Ok(IndexJson { entries });
+  }
+
   let index_json_path = docset_path.join("index.json");
 
   let index_exists =
@@ -208,9 +253,18 @@ fn search_docset_in_filenames(docset_name: &str,
                                          })?;
 
   let reader = BufReader::new(file);
-  let index: IndexJson = serde_json::from_reader(reader).map_err(|err| {
-                           format!("Could not deserialize `{}`: {err}", index_json_path.display())
-                         })?;
+  serde_json::from_reader(reader).map_err(|err| {
+                                   format!("Could not deserialize `{}`: {err}",
+                                           index_json_path.display())
+                                 })
+}
+
+fn search_docset_in_filenames(docset_name: &str,
+                              query: &str,
+                              case_insensitive: bool)
+                              -> Result<ExactMatches, String>
+{
+  let index = deserialize_index_json(docset_name)?;
 
   let mut items = vec![];
 
@@ -223,7 +277,7 @@ fn search_docset_in_filenames(docset_name: &str,
 
       if lowercase_name.contains(&query) || lowercase_path.contains(&query) {
         let (item, fragment) = split_to_item_and_fragment(entry.path)?;
-        let exact_match = ExactResult { item, fragment };
+        let exact_match = ExactResult { docset: None, item, fragment, highlights: vec![] };
         items.push(exact_match);
       }
     }
@@ -231,7 +285,7 @@ fn search_docset_in_filenames(docset_name: &str,
     for entry in index.entries {
       if entry.name.contains(query) || entry.path.contains(query) {
         let (item, fragment) = split_to_item_and_fragment(entry.path)?;
-        let exact_match = ExactResult { item, fragment };
+        let exact_match = ExactResult { docset: None, item, fragment, highlights: vec![] };
         items.push(exact_match);
       }
     }
@@ -242,6 +296,171 @@ fn search_docset_in_filenames(docset_name: &str,
   Ok(items)
 }
 
+const FUZZY_MATCH_SCORE: i64 = 16;
+const FUZZY_BOUNDARY_BONUS: i64 = 8;
+const FUZZY_CAMEL_CASE_BONUS: i64 = 7;
+const FUZZY_CONSECUTIVE_BONUS: i64 = 4;
+const FUZZY_GAP_START_PENALTY: i64 = 3;
+const FUZZY_GAP_EXTENSION_PENALTY: i64 = 1;
+const FUZZY_TYPO_PENALTY: i64 = FUZZY_MATCH_SCORE * 2;
+
+// Matches characters of the query in order, preferring word boundaries and
+// consecutive runs, like `fzf` does.
+//
+// -> (score, indices of matched characters in the candidate)
+fn score_fuzzy_subsequence(query: &[char], candidate: &[char]) -> Option<(i64, Vec<usize>)>
+{
+  let (n, m) = (query.len(), candidate.len());
+  if n == 0 || n > m {
+    return None;
+  }
+
+  // Quick check to skip the expensive part for most candidates.
+  {
+    let mut query_chars = query.iter().peekable();
+    for c in candidate {
+      if query_chars.peek().is_some_and(|q| c.to_lowercase().eq(q.to_lowercase())) {
+        query_chars.next();
+      }
+    }
+    if query_chars.peek().is_some() {
+      return None;
+    }
+  }
+
+  let bonus = |j: usize| -> i64 {
+    let previous = if j == 0 { None } else { Some(candidate[j - 1]) };
+    match previous {
+      None => FUZZY_BOUNDARY_BONUS,
+      Some(p) if !p.is_alphanumeric() => FUZZY_BOUNDARY_BONUS,
+      Some(p) if p.is_lowercase() && candidate[j].is_uppercase() => FUZZY_CAMEL_CASE_BONUS,
+      _ => 0,
+    }
+  };
+
+  // scores[i][j] is the best score when i-th query character is matched with
+  // j-th candidate character. previous[i][j] is where the (i - 1)-th matched.
+  let mut scores = vec![vec![None; m]; n];
+  let mut previous = vec![vec![0; m]; n];
+
+  for i in 0..n {
+    // Best score of the previous row with the gap penalty applied, and its
+    // position.
+    let mut carry: Option<(i64, usize)> = None;
+
+    for j in 0..m {
+      if i > 0 && j > 0 {
+        if let Some((score, position)) = carry {
+          carry = Some((score - FUZZY_GAP_EXTENSION_PENALTY, position));
+        }
+        if j > 1 {
+          if let Some(score) = scores[i - 1][j - 2] {
+            let gapped = score - FUZZY_GAP_START_PENALTY;
+            if carry.map_or(true, |(s, _)| gapped > s) {
+              carry = Some((gapped, j - 2));
+            }
+          }
+        }
+      }
+
+      if !candidate[j].to_lowercase().eq(query[i].to_lowercase()) {
+        continue;
+      }
+
+      let current = FUZZY_MATCH_SCORE + bonus(j);
+
+      if i == 0 {
+        scores[i][j] = Some(current);
+        continue;
+      }
+
+      let consecutive = if j > 0 {
+        scores[i - 1][j - 1].map(|s| (s + current + FUZZY_CONSECUTIVE_BONUS, j - 1))
+      } else {
+        None
+      };
+      let gapped = carry.map(|(s, position)| (s + current, position));
+
+      let best = match (consecutive, gapped) {
+        (Some(c), Some(g)) => Some(if c.0 >= g.0 { c } else { g }),
+        (c, g) => c.or(g),
+      };
+
+      if let Some((score, position)) = best {
+        scores[i][j] = Some(score);
+        previous[i][j] = position;
+      }
+    }
+  }
+
+  let (mut j, score) = scores[n - 1].iter()
+                                    .enumerate()
+                                    .filter_map(|(j, s)| s.map(|s| (j, s)))
+                                    .max_by_key(|&(j, s)| (s, std::cmp::Reverse(j)))?;
+
+  let mut positions = vec![0; n];
+  for i in (0..n).rev() {
+    positions[i] = j;
+    j = previous[i][j];
+  }
+
+  Some((score, positions))
+}
+
+// Same as `score_fuzzy_subsequence()`, but forgives one extra or mistyped
+// character in longer queries.
+pub(crate) fn score_fuzzy_match(query: &[char], candidate: &[char]) -> Option<(i64, Vec<usize>)>
+{
+  if let Some(result) = score_fuzzy_subsequence(query, candidate) {
+    return Some(result);
+  }
+
+  if query.len() < 4 {
+    return None;
+  }
+
+  (0..query.len()).filter_map(|skipped| {
+                    let mut shorter_query = query.to_vec();
+                    shorter_query.remove(skipped);
+                    score_fuzzy_subsequence(&shorter_query, candidate)
+                  })
+                  .max_by_key(|(score, _)| *score)
+                  .map(|(score, positions)| (score - FUZZY_TYPO_PENALTY, positions))
+}
+
+fn search_docset_fuzzily(docset_name: &str, query: &str) -> Result<ScoredMatches, String>
+{
+  let index = deserialize_index_json(docset_name)?;
+
+  // Whitespace only separates words, so `vec push` matches `Vec::push`.
+  let query = query.chars().filter(|c| !c.is_whitespace()).collect::<Vec<char>>();
+
+  let mut scored_items = vec![];
+
+  for entry in index.entries {
+    let path = entry.path.chars().collect::<Vec<char>>();
+    let name = entry.name.chars().collect::<Vec<char>>();
+
+    let path_match = score_fuzzy_match(&query, &path);
+    let name_match = score_fuzzy_match(&query, &name);
+
+    // Name is not displayed, so its matches are not highlighted.
+    let (score, highlights) = match (path_match, name_match) {
+      (Some(p), Some(n)) if n.0 > p.0 => (n.0, vec![]),
+      (Some(p), _) => p,
+      (None, Some(n)) => (n.0, vec![]),
+      (None, None) => continue,
+    };
+
+    let path_length = path.len();
+    let (item, fragment) = split_to_item_and_fragment(entry.path)?;
+    let exact_match = ExactResult { docset: None, item, fragment, highlights };
+    scored_items.push((score, path_length, exact_match));
+  }
+
+  Ok(scored_items)
+}
+
 fn get_context_around_query(html_line: &str, index: usize, query_len: usize) -> String
 {
   // (80 columns - ["...".len() * 2] - [TAB.len() * 2]) / 2 sides
@@ -275,20 +494,108 @@ fn convert_path_to_item(path: PathBuf, docset_path: &PathBuf) -> Result<String,
   Ok(item)
 }
 
+// Finds lines that contain any of the needles and returns their contexts.
+//
+// -> None if some needle is not in the file.
+fn find_contexts_in_file(file_path: &Path,
+                         needles: &[String],
+                         case_insensitive: bool)
+                         -> Result<Option<Vec<String>>, String>
+{
+  let file = File::open(file_path).map_err(|err| {
+                                    format!("Could not open `{}`: {err}", file_path.display())
+                                  })?;
+
+  let mut contexts = vec![];
+  let mut found_needles = vec![false; needles.len()];
+
+  let mut reader = BufReader::new(file);
+  let mut string_buffer = String::new();
+
+  while let Ok(size) = reader.read_line(&mut string_buffer) {
+    if size == 0 {
+      break;
+    }
+
+    let display_context = if case_insensitive {
+      Cow::Owned(string_buffer.to_lowercase())
+    } else {
+      Cow::Borrowed(&string_buffer)
+    };
+
+    // The context is shown around the needle that was found first.
+    let mut first_match: Option<(usize, usize)> = None;
+
+    for (needle, found) in needles.iter().zip(found_needles.iter_mut()) {
+      if let Some(index) = display_context.find(needle.as_str()) {
+        *found = true;
+        if first_match.map_or(true, |(first_index, _)| index < first_index) {
+          first_match = Some((index, needle.len()));
+        }
+      }
+    }
+
+    if let Some((index, needle_len)) = first_match {
+      let context = get_context_around_query(&string_buffer, index, needle_len);
+      contexts.push(context);
+    }
+
+    string_buffer.clear();
+  }
+
+  if found_needles.iter().all(|&found| found) && !contexts.is_empty() {
+    Ok(Some(contexts))
+  } else {
+    Ok(None)
+  }
+}
+
+// Every word of the query has to be found on a page. With `--whole`, the query
+// already has spaces around it and is found as a whole.
+fn get_query_needles(query: &str, whole: bool) -> Vec<String>
+{
+  if whole {
+    vec![query.to_owned()]
+  } else {
+    query.split_whitespace().map(str::to_owned).collect()
+  }
+}
+
+fn is_file_name_matching(file_name: &str, needles: &[String]) -> bool
+{
+  needles.iter().all(|needle| file_name.contains(needle.as_str()))
+}
+
+// Pages are ranked by the number of occurrences of query words, which comes
+// from the full-text index. Docsets without it are scored page by page in the
+// same way, so results are the same with or without the index.
+//
+// -> (exact matches, vague matches, whether the full-text index was used)
 fn search_docset_precisely(docset_name: &str,
                            query: &str,
+                           whole: bool,
                            case_insensitive: bool)
-                           -> Result<(ExactMatches, VagueMatches), String>
+                           -> Result<(ExactMatches, VagueMatches, bool), String>
 {
-  let docset_path = get_docset_path(docset_name)?;
+  let docset_path = get_docset_pages_path(docset_name)?;
 
   let internal_query = if case_insensitive { query.to_lowercase() } else { query.to_owned() };
+  let needles = get_query_needles(&internal_query, whole);
+  let words = get_text_words(query);
+
+  let index = read_fulltext_index(docset_name)?;
+  let scores =
+    index.as_ref()
+         .map(|index| index.find_pages(&words, whole).into_iter().collect::<HashMap<_, _>>());
 
   fn visit_dir_with_query(original_path: &PathBuf,
                           path: &PathBuf,
-                          query: &String,
-                          case_insensitive: bool)
-                          -> Result<(ExactMatches, VagueMatches), String>
+                          needles: &[String],
+                          case_insensitive: bool,
+                          words: &[String],
+                          whole: bool,
+                          scores: Option<&HashMap<&str, u32>>)
+                          -> Result<(ExactMatches, Vec<(u32, VagueResult)>), String>
   {
     let mut exact_files = vec![];
     let mut vague_results = vec![];
@@ -306,8 +613,13 @@ fn search_docset_precisely(docset_name: &str,
              .map_err(|err| format!("Could not read file type of {os_file_name:?}: {err}"))?;
 
       if file_type.is_dir() {
-        let (mut exact, mut vague) =
-          visit_dir_with_query(original_path, &entry.path(), query, case_insensitive)?;
+        let (mut exact, mut vague) = visit_dir_with_query(original_path,
+                                                          &entry.path(),
+                                                          needles,
+                                                          case_insensitive,
+                                                          words,
+                                                          whole,
+                                                          scores)?;
 
         exact_files.append(&mut exact);
         vague_results.append(&mut vague);
@@ -325,49 +637,31 @@ fn search_docset_precisely(docset_name: &str,
 
       let file_path = entry.path();
 
-      if file_name == "archive" {
-        eprintln!("nigger");
-      }
-
-      if file_name.contains(query) {
+      if is_file_name_matching(&file_name, needles) {
         let item = convert_path_to_item(file_path, original_path)?;
-        let exact_match = ExactResult { item, fragment: None };
+        let exact_match = ExactResult { docset: None, item, fragment: None, highlights: vec![] };
         exact_files.push(exact_match);
-      } else {
-        let file = File::open(&file_path).map_err(|err| {
-                                           format!("Could not open `{}`: {err}",
-                                                   file_path.display())
-                                         })?;
-
-        let mut contexts = vec![];
-
-        let mut reader = BufReader::new(file);
-        let mut string_buffer = String::new();
-
-        while let Ok(size) = reader.read_line(&mut string_buffer) {
-          if size == 0 {
-            break;
-          }
-
-          let display_context = if case_insensitive {
-            Cow::Owned(string_buffer.to_lowercase())
-          } else {
-            Cow::Borrowed(&string_buffer)
-          };
-
-          if let Some(index) = display_context.find(query) {
-            let context = get_context_around_query(&string_buffer, index, query.len());
-
-            contexts.push(context);
+      } else if file_type.is_file() {
+        let item = convert_path_to_item(file_path.clone(), original_path)?;
+
+        let score = match scores {
+          Some(scores) => scores.get(item.as_str()).copied(),
+          None => {
+            let html = std::fs::read_to_string(&file_path).map_err(|err| {
+                                                            format!("Could not read `{}`: {err}",
+                                                                    file_path.display())
+                                                          })?;
+            score_page(&html, words, whole)
           }
+        };
 
-          string_buffer.clear();
-        }
+        let Some(score) = score else {
+          continue;
+        };
 
-        if !contexts.is_empty() {
-          let item = convert_path_to_item(file_path, original_path)?;
-          let vague_result = VagueResult { item, contexts };
-          vague_results.push(vague_result);
+        if let Some(contexts) = find_contexts_in_file(&file_path, needles, case_insensitive)? {
+          let vague_result = VagueResult { docset: None, item, contexts };
+          vague_results.push((score, vague_result));
         }
       }
     }
@@ -375,33 +669,62 @@ fn search_docset_precisely(docset_name: &str,
     Ok((exact_files, vague_results))
   }
 
-  let (mut exact_files, mut vague_results) =
-    visit_dir_with_query(&docset_path, &docset_path, &internal_query, case_insensitive)?;
+  let (mut exact_files, mut ranked_vague_results) = visit_dir_with_query(&docset_path,
+                                                                         &docset_path,
+                                                                         &needles,
+                                                                         case_insensitive,
+                                                                         &words,
+                                                                         whole,
+                                                                         scores.as_ref())?;
 
   exact_files.sort_unstable();
-  vague_results.sort_unstable();
+  // Most occurrences first.
+  ranked_vague_results.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
 
-  Ok((exact_files, vague_results))
+  let vague_results = ranked_vague_results.into_iter().map(|(_, result)| result).collect();
+
+  Ok((exact_files, vague_results, index.is_some()))
 }
 
 const TAB: &str = "    ";
 const HALF_TAB: &str = "  ";
 
+// Docset column is only shown when several docsets are searched at once.
+fn get_docset_column_width<'a>(docsets: impl Iterator<Item = &'a Option<String>>) -> usize
+{
+  docsets.map(|docset| docset.as_ref().map_or(0, |docset| docset.chars().count() + 2))
+         .max()
+         .unwrap_or(0)
+}
+
+fn format_docset_column(docset: &Option<String>, width: usize, is_porcelain: bool) -> String
+{
+  match docset {
+    Some(docset) if is_porcelain => format!("{docset} "),
+    Some(docset) => format!("{LIGHT_GRAY}{docset:<0$}{RESET}", width),
+    None => String::new(),
+  }
+}
+
 fn print_vague_search_results(search_results: &[VagueResult],
                               mut start_index: usize,
                               is_porcelain: bool)
                               -> ResultS
 {
+  let docset_column_width = get_docset_column_width(search_results.iter().map(|r| &r.docset));
+
   for result in search_results {
+    let docset = format_docset_column(&result.docset, docset_column_width, is_porcelain);
+
     if !is_porcelain {
-      println!("{GRAY}{start_index:>4}{RESET}{HALF_TAB}{}{RESET}", result.item);
+      println!("{GRAY}{start_index:>4}{RESET}{HALF_TAB}{docset}{}{RESET}", result.item);
       for context in &result.contexts {
         println!("{TAB}{TAB}{GRAYER}...{RESET}{LIGHT_GRAY}{}{}{RESET}{GRAYER}...{RESET}",
                  GRAYEST.bg(),
                  context);
       }
     } else {
-      println!("{GRAY}{}{RESET}", result.item);
+      println!("{docset}{GRAY}{}{RESET}", result.item);
     }
 
     start_index += 1;
@@ -410,49 +733,225 @@ fn print_vague_search_results(search_results: &[VagueResult],
   Ok(())
 }
 
+// Highlights characters whose indices, minus `offset`, are in `highlights`.
+// `style` is restored after each highlighted character.
+fn highlight_characters(s: &str, offset: usize, highlights: &[usize], style: &str) -> String
+{
+  if highlights.is_empty() {
+    return s.to_owned();
+  }
+
+  let mut buffer = String::new();
+
+  for (i, c) in s.chars().enumerate() {
+    if highlights.contains(&(i + offset)) {
+      buffer += &format!("{BOLD}{YELLOW}{c}{RESET}{style}");
+    } else {
+      buffer.push(c);
+    }
+  }
+
+  buffer
+}
+
 fn print_search_results(search_results: &[ExactResult],
                         mut start_index: usize,
                         is_porcelain: bool)
                         -> ResultS
 {
-  let mut prev_item = "";
+  let mut prev_result: Option<&ExactResult> = None;
+  let gray = GRAY.to_string();
+
+  let docset_column_width = get_docset_column_width(search_results.iter().map(|r| &r.docset));
+  let docset_column_padding = " ".repeat(docset_column_width);
 
   // Group fragments by an item.
   for result in search_results {
+    let item = highlight_characters(&result.item, 0, &result.highlights, "");
+    let docset = format_docset_column(&result.docset, docset_column_width, is_porcelain);
+
+    let is_same_item =
+      prev_result.is_some_and(|prev| prev.item == result.item && prev.docset == result.docset);
+
     if let Some(fragment) = &result.fragment {
+      let fragment_offset = result.item.chars().count() + 1;
+      let fragment = highlight_characters(fragment, fragment_offset, &result.highlights, &gray);
+
       if !is_porcelain {
-        if result.item == prev_item {
-          println!("{TAB}{HALF_TAB}{GRAYER}{start_index:>4}{HALF_TAB}{GRAY}#{}{RESET}", fragment);
+        if is_same_item {
+          println!("{TAB}{HALF_TAB}{docset_column_padding}{GRAYER}{start_index:>4}{HALF_TAB}\
+                    {GRAY}#{}{RESET}",
+                   fragment);
         } else {
-          println!("{GRAY}{start_index:>4}{RESET}{HALF_TAB}{}{GRAY}, #{}{RESET}",
-                   result.item, fragment);
+          println!("{GRAY}{start_index:>4}{RESET}{HALF_TAB}{docset}{}{GRAY}, #{}{RESET}",
+                   item, fragment);
         }
       } else {
-        println!("{}{GRAY}#{}{RESET}", result.item, fragment);
+        println!("{docset}{}{GRAY}#{}{RESET}", item, fragment);
       }
     } else if !is_porcelain {
-      println!("{GRAY}{start_index:>4}{RESET}{HALF_TAB}{}", result.item);
+      println!("{GRAY}{start_index:>4}{RESET}{HALF_TAB}{docset}{}", item);
     } else {
-      println!("{}", result.item);
+      println!("{docset}{}", item);
     }
 
-    prev_item = &result.item;
+    prev_result = Some(result);
     start_index += 1;
   }
 
   Ok(())
 }
 
+// Searches every docset and merges the results. Fuzzy matches are merged by
+// relevance, other results are listed one docset after another.
+fn search_docsets(docsets: &[&str],
+                  query: &str,
+                  options: &SearchOptions,
+                  warnings: &mut Vec<String>)
+                  -> Result<(ExactMatches, VagueMatches), String>
+{
+  let is_merged = docsets.len() > 1;
+
+  let mut exact_results = vec![];
+  let mut vague_results = vec![];
+  let mut scored_results = vec![];
+
+  for &docset in docsets {
+    let docset_column = is_merged.then(|| docset.to_owned());
+
+    if options.precise {
+      let (exact, vague, is_indexed) =
+        search_docset_precisely(docset, query, options.whole, options.case_insensitive)?;
+
+      if !is_indexed {
+        warnings.push(format!("`{docset}` does not have a full-text index, which makes \
+                               `--precise` slow. Run `{PROGRAM_NAME} index {docset}` to \
+                               build it."));
+      }
+
+      exact_results.extend(exact.into_iter()
+                                .map(|result| ExactResult { docset: docset_column.clone(),
+                                                            ..result }));
+      vague_results.extend(vague.into_iter()
+                                .map(|result| VagueResult { docset: docset_column.clone(),
+                                                            ..result }));
+    } else if options.fuzzy && !query.trim().is_empty() {
+      scored_results.extend(search_docset_fuzzily(docset, query)?.into_iter().map(
+        |(score, path_length, result)| {
+          (score, path_length, ExactResult { docset: docset_column.clone(), ..result })
+        },
+      ));
+    } else {
+      let exact = search_docset_in_filenames(docset, query, options.case_insensitive)?;
+      exact_results.extend(exact.into_iter()
+                                .map(|result| ExactResult { docset: docset_column.clone(),
+                                                            ..result }));
+    }
+  }
+
+  if options.fuzzy {
+    // Best score first, then shorter paths.
+    scored_results.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+    exact_results.extend(scored_results.into_iter().map(|(_, _, result)| result));
+  }
+
+  Ok((exact_results, vague_results))
+}
+
+#[derive(Serialize)]
+struct JsonExactResult<'a>
+{
+  number: usize,
+  docset: &'a str,
+  item: &'a str,
+  fragment: Option<&'a str>,
+  // Indices of characters in `item#fragment` that matched a fuzzy query.
+  highlights: &'a [usize],
+}
+
+#[derive(Serialize)]
+struct JsonVagueResult<'a>
+{
+  number: usize,
+  docset: &'a str,
+  item: &'a str,
+  contexts: &'a [String],
+}
+
+#[derive(Serialize)]
+struct JsonSearchResults<'a>
+{
+  query: &'a str,
+  exact: Vec<JsonExactResult<'a>>,
+  vague: Vec<JsonVagueResult<'a>>,
+}
+
+// Results are numbered as for `--open`.
+fn get_json_search_results<'a>(query: &'a str,
+                               docset: &'a str,
+                               exact_results: &'a [ExactResult],
+                               vague_results: &'a [VagueResult])
+                               -> JsonSearchResults<'a>
+{
+  let exact =
+    exact_results.iter()
+                 .enumerate()
+                 .map(|(i, result)| JsonExactResult { number: i + 1,
+                                                      docset: result.docset
+                                                                    .as_deref()
+                                                                    .unwrap_or(docset),
+                                                      item: &result.item,
+                                                      fragment: result.fragment.as_deref(),
+                                                      highlights: &result.highlights })
+                 .collect();
+
+  let vague = vague_results.iter()
+                           .enumerate()
+                           .map(|(i, result)| JsonVagueResult { number: exact_results.len() + i + 1,
+                                                                docset: result.docset
+                                                                              .as_deref()
+                                                                              .unwrap_or(docset),
+                                                                item: &result.item,
+                                                                contexts: &result.contexts })
+                           .collect();
+
+  JsonSearchResults { query, exact, vague }
+}
+
+// Same as `search --json`, but without the cache, for `serve`.
+//
+// -> (results, warnings)
+pub(crate) fn search_docsets_as_json(docsets: &[&str],
+                                     query: &str,
+                                     options: &SearchOptions)
+                                     -> Result<(String, Vec<String>), String>
+{
+  let mut warnings = vec![];
+  let (exact_results, vague_results) = search_docsets(docsets, query, options, &mut warnings)?;
+
+  let docset = docsets.join(",");
+  let results = get_json_search_results(query, &docset, &exact_results, &vague_results);
+  let json =
+    serde_json::to_string(&results).map_err(|err| format!("Could not serialize results: {err}"))?;
+
+  Ok((json, warnings))
+}
+
 fn search_impl(is_porcelain: bool,
+               is_json: bool,
                search_context: SearchContext,
                open_options: OpenOptions)
                -> Result<Vec<String>, String>
 {
   let SearchContext { ref docset, ref options, ref query } = search_context;
 
+  let docsets = docset.split(',').collect::<Vec<&str>>();
+  let searched_docsets =
+    if docsets.len() > 1 { format!("{} docsets", docsets.len()) } else { format!("`{docset}`") };
+
   let mut warnings: Vec<String> = vec![];
 
-  if open_options.open_number.is_none() && !is_porcelain {
+  if open_options.open_number.is_none() && !is_porcelain && !is_json {
     // This lets you know whether flag messed up your query
     println!("Searching for `{}`...", search_context.query);
   }
@@ -461,150 +960,144 @@ fn search_impl(is_porcelain: bool,
   if open_options.open_number.is_none() &&
      (open_options.ignore_fragment ||
       open_options.line_numbers ||
+      open_options.no_pager ||
       open_options.page_width.is_some())
   {
     warnings.push("`--open` was not specified and some flags were ignored.".to_string());
   }
 
-  let width = if let Some(w) = open_options.page_width { w } else { get_terminal_width() };
+  let width = if let Some(w) = open_options.page_width { w } else { get_terminal_width()? };
 
-  if options.precise {
-    let (exact_results, vague_results) = if let Some(cache) = try_use_cache(&search_context) {
-      (cache.exact_results, cache.vague_results)
-    } else {
-      let (exact, vague) = search_docset_precisely(docset, query, options.case_insensitive)?;
+  let (exact_results, vague_results) = if let Some(cache) = try_use_cache(&search_context) {
+    (cache.exact_results, cache.vague_results)
+  } else {
+    let (exact, vague) = search_docsets(&docsets, query, options, &mut warnings)?;
 
-      let search_cache =
-        SearchCache { exact_results: Cow::Borrowed(&exact), vague_results: Cow::Borrowed(&vague) };
+    let search_cache =
+      SearchCache { exact_results: Cow::Borrowed(&exact), vague_results: Cow::Borrowed(&vague) };
 
-      let _ = cache_search_results(&search_context, &search_cache).map_err(|err| {
-                warnings.push(format!("Could not write cache: {err}."));
-              });
+    let _ = cache_search_results(&search_context, &search_cache).map_err(|err| {
+              warnings.push(format!("Could not write cache: {err}."));
+            });
 
-      (exact.into(), vague.into())
-    };
+    (exact.into(), vague.into())
+  };
 
-    let exact_results_offset = exact_results.len();
+  let exact_results_offset = exact_results.len();
 
-    if let Some(open_number) = open_options.open_number {
-      if let Some(open_number) = open_number {
-        match open_number {
-          n if n < 1 || n > exact_results_offset + vague_results.len() => {
-            return Err(format!("`--open {n}` is out of bounds."));
-          }
-          n if n <= exact_results_offset => {
-            let result = &exact_results[n - 1];
-            let fragment =
-              if open_options.ignore_fragment { None } else { result.fragment.as_ref() };
-            print_page_from_docset(docset,
-                                   &result.item,
-                                   fragment,
-                                   width,
-                                   open_options.line_numbers)?;
-            return Ok(warnings);
-          }
-          n => {
-            let result = &vague_results[n - exact_results_offset - 1];
-            print_page_from_docset(docset, &result.item, None, width, open_options.line_numbers)?;
-            return Ok(warnings);
-          }
+  if let Some(open_number) = open_options.open_number {
+    if let Some(open_number) = open_number {
+      let (result_docset, item, fragment) = match open_number {
+        n if n < 1 || n > exact_results_offset + vague_results.len() => {
+          return Err(format!("`--open {n}` is out of bounds."));
         }
-      } else {
-        return Err(format!("`--open` requires a number."));
-      }
-    }
-    if !exact_results.is_empty() {
-      if !is_porcelain {
-        println!("{BOLD}Exact matches in `{docset}`{RESET}:");
-      }
-      print_search_results(&exact_results, 1, is_porcelain)?;
-    } else if !is_porcelain {
-      println!("{BOLD}No exact matches in `{docset}`{RESET}.");
-    }
+        n if n <= exact_results_offset => {
+          let result = &exact_results[n - 1];
+          let fragment = if open_options.ignore_fragment { None } else { result.fragment.as_ref() };
+          (&result.docset, &result.item, fragment)
+        }
+        n => {
+          let result = &vague_results[n - exact_results_offset - 1];
+          (&result.docset, &result.item, None)
+        }
+      };
 
-    if !vague_results.is_empty() {
-      if !is_porcelain {
-        println!("{BOLD}Mentions in other files from `{docset}`{RESET}:");
+      let result_docset = result_docset.as_deref().unwrap_or(docset);
+
+      if is_json {
+        print_page_as_json(get_docset_page_path(result_docset, item)?,
+                           Some((result_docset, item)),
+                           fragment,
+                           width,
+                           open_options.line_numbers,
+                           false)?;
+      } else {
+        print_page_from_docset(result_docset,
+                               item,
+                               fragment,
+                               width,
+                               open_options.line_numbers,
+                               !open_options.no_pager)?;
       }
-      print_vague_search_results(&vague_results, exact_results_offset + 1, is_porcelain)?;
-    } else if !is_porcelain {
-      println!("{BOLD}No mentions in other files from `{docset}`{RESET}.");
-    }
 
-    Ok(warnings)
-  } else {
-    let results = if let Some(cache) = try_use_cache(&search_context) {
-      cache.exact_results
+      return Ok(warnings);
     } else {
-      let exact = search_docset_in_filenames(docset, query, options.case_insensitive)?;
-
-      let search_cache =
-        SearchCache { exact_results: Cow::Borrowed(&exact), vague_results: Cow::Owned(vec![]) };
-
-      let _ = cache_search_results(&search_context, &search_cache).map_err(|err| {
-                warnings.push(format!("Could not write cache: {err}."));
-              });
+      return Err(format!("`--open` requires a number."));
+    }
+  }
 
-      exact.into()
-    };
+  if is_json {
+    print_json(&get_json_search_results(query, docset, &exact_results, &vague_results))?;
+    return Ok(warnings);
+  }
 
-    if let Some(open_number) = open_options.open_number {
-      if let Some(open_number) = open_number {
-        match open_number {
-          n if n < 1 || n > results.len() => {
-            return Err(format!("`--open {n}` is out of bounds."));
-          }
-          n => {
-            let result = &results[n - 1];
-            let fragment =
-              if open_options.ignore_fragment { None } else { result.fragment.as_ref() };
-            print_page_from_docset(docset,
-                                   &result.item,
-                                   fragment,
-                                   width,
-                                   open_options.line_numbers)?;
-            return Ok(warnings);
-          }
-        }
-      } else {
-        return Err(format!("`--open` requires a number."));
-      }
+  if !exact_results.is_empty() {
+    if !is_porcelain {
+      println!("{BOLD}Exact matches in {searched_docsets}{RESET}:");
     }
-    if !results.is_empty() {
+    print_search_results(&exact_results, 1, is_porcelain)?;
+  } else if !is_porcelain {
+    println!("{BOLD}No exact matches in {searched_docsets}{RESET}.");
+  }
+
+  if options.precise {
+    if !vague_results.is_empty() {
       if !is_porcelain {
-        println!("{BOLD}Exact matches in `{docset}`{RESET}:");
+        println!("{BOLD}Mentions in other files from {searched_docsets}{RESET}:");
       }
-      print_search_results(&results, 1, is_porcelain)?;
+      print_vague_search_results(&vague_results, exact_results_offset + 1, is_porcelain)?;
     } else if !is_porcelain {
-      println!("{BOLD}No exact matches in `{docset}`{RESET}.");
+      println!("{BOLD}No mentions in other files from {searched_docsets}{RESET}.");
     }
-
-    Ok(warnings)
   }
+
+  Ok(warnings)
 }
 
 pub(crate) fn search<Args>(mut args: Args) -> ResultS
   where Args: Iterator<Item = String>
 {
+  let mut flag_all;
   let mut flag_whole;
   let mut flag_precise;
+  let mut flag_fuzzy;
   let mut flag_open;
   let mut flag_case_insensitive;
   let mut flag_open_columns;
   let mut flag_open_ignore_fragment;
   let mut flag_open_line_numbers;
+  let mut flag_open_no_pager;
   let mut flag_porcelain;
+  let mut flag_json;
+  let mut flag_no_whole;
+  let mut flag_no_precise;
+  let mut flag_no_fuzzy;
+  let mut flag_no_case_insensitive;
+  let mut flag_no_porcelain;
+  let mut flag_no_open_line_numbers;
+  let mut flag_open_pager;
   let mut flag_help;
 
   let mut flags = flags![
+    flag_all: BoolFlag,                  ["-a", "--all"],
     flag_whole: BoolFlag,                ["-w", "--whole"],
     flag_precise: BoolFlag,              ["-p", "--precise"],
+    flag_fuzzy: BoolFlag,                ["-z", "--fuzzy"],
     flag_open: StringFlag,               ["-o", "--open"],
     flag_case_insensitive: BoolFlag,     ["-i", "--ignore-case"],
     flag_open_columns: StringFlag,       ["-c", "--columns"],
     flag_open_ignore_fragment: BoolFlag, ["-f", "--ignore-fragment"],
     flag_open_line_numbers: BoolFlag,    ["-n", "--line-numbers"],
+    flag_open_no_pager: BoolFlag,        ["--no-pager"],
     flag_porcelain: BoolFlag,            ["--porcelain"],
+    flag_json: BoolFlag,                 ["--json"],
+    flag_no_whole: BoolFlag,             ["--no-whole"],
+    flag_no_precise: BoolFlag,           ["--no-precise"],
+    flag_no_fuzzy: BoolFlag,             ["--no-fuzzy"],
+    flag_no_case_insensitive: BoolFlag,  ["--no-ignore-case"],
+    flag_no_porcelain: BoolFlag,         ["--no-porcelain"],
+    flag_no_open_line_numbers: BoolFlag, ["--no-line-numbers"],
+    flag_open_pager: BoolFlag,           ["--pager"],
     flag_help: BoolFlag,                 ["--help"]
   ];
 
@@ -613,25 +1106,60 @@ pub(crate) fn search<Args>(mut args: Args) -> ResultS
     return show_search_help();
   }
 
+  flag_whole |= !flag_no_whole && get_config_bool("search.whole")?;
+  // Explicit `--fuzzy` or `--precise` wins over the config.
+  flag_precise |= !flag_no_precise && get_config_bool("search.precise")? && !flag_fuzzy;
+  flag_fuzzy |= !flag_no_fuzzy && get_config_bool("search.fuzzy")? && !flag_precise;
+  flag_case_insensitive |= !flag_no_case_insensitive && get_config_bool("search.ignore_case")?;
+  flag_porcelain |= !flag_no_porcelain && get_config_bool("search.porcelain")?;
+  flag_open_line_numbers |=
+    !flag_no_open_line_numbers && get_config_bool("search.line_numbers")? && !flag_open.is_empty();
+  flag_open_no_pager |=
+    !flag_open_pager && get_config_bool("search.no_pager")? && !flag_open.is_empty();
+
   if !is_docs_json_exists()? {
     return Err(format!("The list of available documents has not yet been \
                         downloaded. Please run `{PROGRAM_NAME} fetch` first."));
   }
 
   let mut args = args.into_iter();
-  let docset = if let Some(docset_name) = args.next() {
-    docset_name
+  let docsets = if flag_all {
+    let local_docsets = get_local_docsets()?;
+    if local_docsets.is_empty() {
+      return Err(format!("No docsets are downloaded. Try running `{PROGRAM_NAME} download \
+                          <docset>`."));
+    }
+    local_docsets
+  } else if let Some(docset_names) = args.next() {
+    let mut docsets: Vec<String> = vec![];
+    for docset in docset_names.split(',').filter(|docset| !docset.is_empty()) {
+      if !docsets.iter().any(|d| d == docset) {
+        docsets.push(docset.to_owned());
+      }
+    }
+    docsets
   } else {
     return show_search_help();
   };
-  let docs = deserialize_docs_json()?;
 
-  if !is_docset_downloaded(&docset)? {
-    make_sure_docset_is_in_docs(&docset, &docs)?;
-    return Err(format!("Docset `{docset}` is not downloaded. Try running \
-                        `{PROGRAM_NAME} download {docset}`."));
+  if docsets.is_empty() {
+    return show_search_help();
+  }
+
+  if !flag_all {
+    let docs = deserialize_docs_json()?;
+
+    for docset in &docsets {
+      if !is_docset_downloaded(docset)? {
+        make_sure_docset_is_in_docs(docset, &docs)?;
+        return Err(format!("Docset `{docset}` is not downloaded. Try running \
+                            `{PROGRAM_NAME} download {docset}`."));
+      }
+    }
   }
 
+  let docset = docsets.join(",");
+
   let query = {
     let mut merged_args = args.collect::<Vec<String>>().join(" ");
 
@@ -644,7 +1172,14 @@ pub(crate) fn search<Args>(mut args: Args) -> ResultS
     }
   };
 
+  if flag_fuzzy && flag_precise {
+    return Err("`--fuzzy` only works with page names and cannot be combined \
+                with `--precise`."
+                                  .to_string());
+  }
+
   let search_flags = SearchOptions { precise: flag_precise,
+                                     fuzzy: flag_fuzzy,
                                      case_insensitive: flag_case_insensitive,
                                      whole: flag_whole };
 
@@ -654,21 +1189,63 @@ pub(crate) fn search<Args>(mut args: Args) -> ResultS
 
   let open_number = if flag_open.is_empty() { None } else { Some(flag_open.parse::<usize>().ok()) };
 
-  let page_width = if flag_open_columns.is_empty() {
-    None
-  } else {
+  let page_width = if !flag_open_columns.is_empty() {
     Some(validate_number_of_columns(&flag_open_columns)?)
+  } else if !flag_open.is_empty() {
+    get_config_columns("search.columns")?
+  } else {
+    None
   };
 
   let open_options = OpenOptions { open_number,
                                    ignore_fragment: flag_open_ignore_fragment,
                                    page_width,
-                                   line_numbers: flag_open_line_numbers };
+                                   line_numbers: flag_open_line_numbers,
+                                   no_pager: flag_open_no_pager };
 
   // Print warnings only after search results.
-  for warning in search_impl(flag_porcelain, search_options, open_options)? {
+  for warning in search_impl(flag_porcelain, flag_json, search_options, open_options)? {
     print_warning!("{}", warning);
   }
 
   Ok(())
 }
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn score(query: &str, candidate: &str) -> Option<(i64, Vec<usize>)>
+  {
+    score_fuzzy_match(&query.chars().collect::<Vec<char>>(),
+                      &candidate.chars().collect::<Vec<char>>())
+  }
+
+  #[test]
+  fn test_fuzzy_match()
+  {
+    let vec_push = "std/vec/struct.vec#method.push";
+
+    // Matches are placed at word boundaries and as close together as possible.
+    let (_, positions) = score("vecpush", vec_push).unwrap();
+    assert_eq!(positions, vec![15, 16, 17, 26, 27, 28, 29]);
+
+    // Case is ignored.
+    assert!(score("VECPUSH", vec_push).is_some());
+
+    // One typo is forgiven, but costs some score.
+    let (exact_score, _) = score("vecpush", vec_push).unwrap();
+    let (typo_score, _) = score("vecpusj", vec_push).unwrap();
+    assert!(typo_score < exact_score);
+
+    // Two are not.
+    assert!(score("vecpsjj", vec_push).is_none());
+    assert!(score("xyz", vec_push).is_none());
+
+    // Matches at boundaries score higher.
+    let (boundary_score, _) = score("push", vec_push).unwrap();
+    let (middle_score, _) = score("push", "std/vec/struct.vec#method.unpushed").unwrap();
+    assert!(boundary_score > middle_score);
+  }
+}
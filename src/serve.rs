@@ -0,0 +1,541 @@
+use std::fs::read;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use toiletcli::flags;
+use toiletcli::flags::*;
+
+use crate::common::{
+  decode_percent_encoding, get_docset_pages_path, get_flag_error, get_local_docsets, ResultS,
+  DOC_PAGE_EXTENSION,
+};
+use crate::common::{BOLD, GREEN, PROGRAM_NAME, RESET};
+use crate::config::get_config_number;
+use crate::print_warning;
+use crate::search::{deserialize_index_json, search_docsets_as_json, SearchOptions};
+
+fn show_serve_help() -> ResultS
+{
+  println!(
+           "\
+{GREEN}USAGE{RESET}
+    {BOLD}{PROGRAM_NAME} serve{RESET} [-OPTIONS]
+    Serve local docsets on localhost, to read them in a browser.
+
+    `/` lists docsets, and `/docs/<docset>/` lists pages of a docset.
+    `/search` finds pages like `search`, and `/api/search` returns the same
+    as `search --json`. Both take `query`, `docset` with names separated by
+    commas, and `all`, `whole`, `ignore_case`, `precise` and `fuzzy`, which
+    work as options of `search`.
+
+{GREEN}OPTIONS{RESET}
+    -p, --port <number>             Listen on this port. Default is
+                                    `serve.port` from the config, or {DEFAULT_PORT}.
+        --help                      Display help message."
+  );
+  Ok(())
+}
+
+const DEFAULT_PORT: u16 = 8080;
+const HOST: &str = "127.0.0.1";
+
+// Requests are small, so a client that sends nothing is not waited for long.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+const STYLE: &str = "\
+body { max-width: 60em; margin: 0 auto; padding: 1em; font-family: sans-serif; \
+line-height: 1.5; }
+nav { padding-bottom: 0.5em; margin-bottom: 1em; border-bottom: 1px solid #ccc; }
+pre, code { font-family: monospace; background: #f4f4f4; }
+pre { padding: 0.5em; overflow-x: auto; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.5em; }
+.path, .context { color: #777; }";
+
+struct Response
+{
+  status: &'static str,
+  content_type: &'static str,
+  body: Vec<u8>,
+}
+
+impl Response
+{
+  fn html(status: &'static str, html: String) -> Self
+  {
+    Self { status, content_type: "text/html; charset=utf-8", body: html.into_bytes() }
+  }
+
+  fn json(status: &'static str, json: String) -> Self
+  {
+    Self { status, content_type: "application/json", body: json.into_bytes() }
+  }
+
+  fn error(status: &'static str, message: &str) -> Self
+  {
+    Self::html(status, get_html_page(status, &format!("<p>{}</p>", escape_html(message))))
+  }
+}
+
+fn escape_html(text: &str) -> String
+{
+  text.replace('&', "&amp;")
+      .replace('<', "&lt;")
+      .replace('>', "&gt;")
+      .replace('"', "&quot;")
+      .replace('\'', "&#39;")
+}
+
+// Only pages and files that they use are served, not indexes of docsets.
+//
+// -> None if the file is not served.
+fn get_content_type(path: &Path) -> Option<&'static str>
+{
+  match path.extension().and_then(|extension| extension.to_str()) {
+    Some("html" | "htm") => Some("text/html; charset=utf-8"),
+    Some("css") => Some("text/css"),
+    Some("js") => Some("text/javascript"),
+    Some("svg") => Some("image/svg+xml"),
+    Some("png") => Some("image/png"),
+    Some("jpg" | "jpeg") => Some("image/jpeg"),
+    Some("gif") => Some("image/gif"),
+    Some("woff") => Some("font/woff"),
+    Some("woff2") => Some("font/woff2"),
+    _ => None,
+  }
+}
+
+// Symbolic links can still point outside of the docset.
+fn is_path_inside(path: &Path, directory: &Path) -> bool
+{
+  match (path.canonicalize(), directory.canonicalize()) {
+    (Ok(path), Ok(directory)) => path.starts_with(directory),
+    _ => false,
+  }
+}
+
+// `a=1&b=x+y` -> [(a, 1), (b, x y)]
+fn parse_query_string(query: &str) -> Vec<(String, String)>
+{
+  query.split('&')
+       .filter(|pair| !pair.is_empty())
+       .map(|pair| {
+         let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+         (decode_percent_encoding(&key.replace('+', " ")),
+          decode_percent_encoding(&value.replace('+', " ")))
+       })
+       .collect()
+}
+
+fn get_parameter<'a>(parameters: &'a [(String, String)], key: &str) -> Option<&'a str>
+{
+  parameters.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+// Checkboxes of forms send `on`.
+fn is_parameter_set(parameters: &[(String, String)], key: &str) -> bool
+{
+  get_parameter(parameters, key).is_some_and(|v| !matches!(v, "0" | "false" | "off" | "no"))
+}
+
+fn get_search_form(parameters: &[(String, String)], hidden_docset: Option<&str>) -> String
+{
+  let query = escape_html(get_parameter(parameters, "query").unwrap_or_default());
+
+  let scope = match hidden_docset {
+    Some(docset) => {
+      format!("<input type=\"hidden\" name=\"docset\" value=\"{}\">", escape_html(docset))
+    }
+    None => "<input type=\"hidden\" name=\"all\" value=\"on\">".to_string(),
+  };
+
+  let mut checkboxes = String::new();
+  for (option, label) in [("fuzzy", "Fuzzy"),
+                          ("ignore_case", "Ignore case"),
+                          ("precise", "Look inside pages"),
+                          ("whole", "Whole sentence")]
+  {
+    let checked = if is_parameter_set(parameters, option) { " checked" } else { "" };
+    checkboxes += &format!(" <label><input type=\"checkbox\" name=\"{option}\"{checked}> \
+                            {label}</label>");
+  }
+
+  format!("<form action=\"/search\">{scope}<input name=\"query\" value=\"{query}\" \
+           placeholder=\"Search\" autofocus> <button>Search</button>{checkboxes}</form>")
+}
+
+fn get_html_page(title: &str, body: &str) -> String
+{
+  format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+           <title>{}</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n\
+           <nav><a href=\"/\">{PROGRAM_NAME}</a></nav>\n{body}\n</body>\n</html>\n",
+          escape_html(title))
+}
+
+fn get_docset_link(docset: &str, path: &str) -> String
+{
+  format!("/docs/{}/{}", escape_html(docset), escape_html(path))
+}
+
+fn get_index_page(parameters: &[(String, String)]) -> Result<Response, String>
+{
+  let mut body = format!("<h1>Docsets</h1>\n{}\n<ul>\n", get_search_form(parameters, None));
+
+  let local_docsets = get_local_docsets()?;
+  for docset in &local_docsets {
+    body += &format!("<li><a href=\"{}\">{}</a></li>\n",
+                     get_docset_link(docset, ""),
+                     escape_html(docset));
+  }
+  body += "</ul>\n";
+
+  if local_docsets.is_empty() {
+    body += &format!("<p>No docsets are downloaded. Try running <code>{PROGRAM_NAME} download \
+                      &lt;docset&gt;</code>.</p>\n");
+  }
+
+  Ok(Response::html("200 OK", get_html_page("Docsets", &body)))
+}
+
+// Lists every entry of `index.json`, in the same order.
+fn get_docset_index_page(docset: &str) -> Result<Response, String>
+{
+  let index = deserialize_index_json(docset)?;
+
+  let mut body =
+    format!("<h1>{}</h1>\n{}\n<ul>\n", escape_html(docset), get_search_form(&[], Some(docset)));
+
+  for entry in &index.entries {
+    body += &format!("<li><a href=\"{}\">{}</a> <span class=\"path\">{}</span></li>\n",
+                     get_docset_link(docset, &entry.path),
+                     escape_html(&entry.name),
+                     escape_html(&entry.path));
+  }
+  body += "</ul>\n";
+
+  Ok(Response::html("200 OK", get_html_page(docset, &body)))
+}
+
+// Pages of DevDocs are parts of a document, and are put into one. Other files,
+// like pages and images of Dash docsets, are served as they are.
+fn get_docset_file(docset: &str, path: &str) -> Result<Response, String>
+{
+  let pages_path = get_docset_pages_path(docset)?;
+
+  let file_path = pages_path.join(path);
+  if let Some(content_type) = get_content_type(&file_path) {
+    if file_path.is_file() && is_path_inside(&file_path, &pages_path) {
+      let contents =
+        read(&file_path).map_err(|err| format!("Could not read `{}`: {err}", file_path.display()))?;
+      return Ok(Response { status: "200 OK", content_type, body: contents });
+    }
+  }
+
+  let page_path = pages_path.join(format!("{path}.{DOC_PAGE_EXTENSION}"));
+  if !page_path.is_file() || !is_path_inside(&page_path, &pages_path) {
+    return Ok(Response::error("404 Not Found", &format!("No page matching `{path}`.")));
+  }
+
+  let page =
+    read(&page_path).map_err(|err| format!("Could not read `{}`: {err}", page_path.display()))?;
+
+  let body = format!("<p><a href=\"{}\">{}</a> <span class=\"path\">{}</span></p>\n{}",
+                     get_docset_link(docset, ""),
+                     escape_html(docset),
+                     escape_html(path),
+                     String::from_utf8_lossy(&page));
+
+  Ok(Response::html("200 OK", get_html_page(&format!("{path} - {docset}"), &body)))
+}
+
+// -> (docsets, query, options), or an error that is client's fault.
+fn get_search_parameters(parameters: &[(String, String)])
+                         -> Result<(Vec<String>, String, SearchOptions), String>
+{
+  let local_docsets = get_local_docsets()?;
+
+  let docsets = if is_parameter_set(parameters, "all") {
+    local_docsets
+  } else {
+    let mut docsets: Vec<String> = vec![];
+    for docset in get_parameter(parameters, "docset").unwrap_or_default()
+                                                     .split(',')
+                                                     .filter(|docset| !docset.is_empty())
+    {
+      if !local_docsets.iter().any(|d| d == docset) {
+        return Err(format!("Docset `{docset}` is not downloaded."));
+      }
+      if !docsets.iter().any(|d| d == docset) {
+        docsets.push(docset.to_owned());
+      }
+    }
+    docsets
+  };
+
+  if docsets.is_empty() {
+    return Err("No docsets to search. Specify `docset` or `all`.".to_string());
+  }
+
+  let options = SearchOptions { case_insensitive: is_parameter_set(parameters, "ignore_case"),
+                                precise: is_parameter_set(parameters, "precise"),
+                                whole: is_parameter_set(parameters, "whole"),
+                                fuzzy: is_parameter_set(parameters, "fuzzy") };
+
+  if options.fuzzy && options.precise {
+    let err = "`fuzzy` only works with page names and cannot be combined with `precise`.";
+    return Err(err.to_string());
+  }
+
+  // Same as in `search --whole`.
+  let query = get_parameter(parameters, "query").unwrap_or_default();
+  let query = if options.whole { format!(" {query} ") } else { query.to_owned() };
+
+  Ok((docsets, query, options))
+}
+
+// -> Results as JSON of `search --json`, or an error that is client's fault.
+fn search_with_parameters(parameters: &[(String, String)])
+                          -> Result<Result<String, String>, String>
+{
+  let (docsets, query, options) = match get_search_parameters(parameters) {
+    Ok(search_parameters) => search_parameters,
+    Err(err) => return Ok(Err(err)),
+  };
+
+  let docsets = docsets.iter().map(String::as_str).collect::<Vec<&str>>();
+  let (results, warnings) = search_docsets_as_json(&docsets, &query, &options)?;
+
+  for warning in warnings {
+    print_warning!("{warning}");
+  }
+
+  Ok(Ok(results))
+}
+
+fn get_api_search_response(parameters: &[(String, String)]) -> Result<Response, String>
+{
+  match search_with_parameters(parameters)? {
+    Ok(results) => Ok(Response::json("200 OK", results)),
+    Err(err) => Ok(Response::json("400 Bad Request", json!({ "error": err }).to_string())),
+  }
+}
+
+fn get_search_page(parameters: &[(String, String)]) -> Result<Response, String>
+{
+  let docset = get_parameter(parameters, "docset").filter(|_| !is_parameter_set(parameters, "all"));
+  let mut body = format!("<h1>Search</h1>\n{}\n", get_search_form(parameters, docset));
+
+  if get_parameter(parameters, "query").unwrap_or_default().trim().is_empty() {
+    return Ok(Response::html("200 OK", get_html_page("Search", &body)));
+  }
+
+  let results: Value = match search_with_parameters(parameters)? {
+    Ok(results) => {
+      serde_json::from_str(&results).map_err(|err| format!("Could not parse results: {err}"))?
+    }
+    Err(err) => {
+      body += &format!("<p>{}</p>\n", escape_html(&err));
+      return Ok(Response::html("400 Bad Request", get_html_page("Search", &body)));
+    }
+  };
+
+  let exact = results["exact"].as_array().cloned().unwrap_or_default();
+  let vague = results["vague"].as_array().cloned().unwrap_or_default();
+
+  if exact.is_empty() && vague.is_empty() {
+    body += "<p>Nothing was found.</p>\n";
+  }
+
+  if !exact.is_empty() {
+    body += "<h2>Exact matches</h2>\n<ol>\n";
+    for result in &exact {
+      let docset = result["docset"].as_str().unwrap_or_default();
+      let path = match result["fragment"].as_str() {
+        Some(fragment) => format!("{}#{fragment}", result["item"].as_str().unwrap_or_default()),
+        None => result["item"].as_str().unwrap_or_default().to_owned(),
+      };
+      body += &format!("<li><a href=\"{}\">{}</a> <span class=\"path\">{}</span></li>\n",
+                       get_docset_link(docset, &path),
+                       escape_html(&path),
+                       escape_html(docset));
+    }
+    body += "</ol>\n";
+  }
+
+  if !vague.is_empty() {
+    body += &format!("<h2>Mentions in other pages</h2>\n<ol start=\"{}\">\n", exact.len() + 1);
+    for result in &vague {
+      let docset = result["docset"].as_str().unwrap_or_default();
+      let item = result["item"].as_str().unwrap_or_default();
+      body += &format!("<li><a href=\"{}\">{}</a> <span class=\"path\">{}</span>",
+                       get_docset_link(docset, item),
+                       escape_html(item),
+                       escape_html(docset));
+      for context in result["contexts"].as_array().into_iter().flatten() {
+        body += &format!("<br><span class=\"context\">...{}...</span>",
+                         escape_html(context.as_str().unwrap_or_default()));
+      }
+      body += "</li>\n";
+    }
+    body += "</ol>\n";
+  }
+
+  Ok(Response::html("200 OK", get_html_page("Search", &body)))
+}
+
+fn route(path: &str, parameters: &[(String, String)]) -> Result<Response, String>
+{
+  let path = decode_percent_encoding(path);
+
+  match path.as_str() {
+    "/" => return get_index_page(parameters),
+    "/search" => return get_search_page(parameters),
+    "/api/search" => return get_api_search_response(parameters),
+    _ => {}
+  }
+
+  let Some(docset_path) = path.strip_prefix("/docs/") else {
+    return Ok(Response::error("404 Not Found", "Not found."));
+  };
+
+  let (docset, rest) = docset_path.split_once('/').unwrap_or((docset_path, ""));
+  if !get_local_docsets()?.iter().any(|d| d == docset) {
+    return Ok(Response::error("404 Not Found", &format!("Docset `{docset}` is not downloaded.")));
+  }
+
+  // Files outside of docsets are not served, so `..`, `/etc` and the like are
+  // refused, the same as paths in bundles.
+  if !Path::new(rest).components().all(|component| matches!(component, Component::Normal(_))) {
+    return Ok(Response::error("404 Not Found", "Not found."));
+  }
+
+  if rest.is_empty() {
+    get_docset_index_page(docset)
+  } else {
+    get_docset_file(docset, rest)
+  }
+}
+
+fn handle_connection(mut stream: TcpStream) -> ResultS
+{
+  stream.set_read_timeout(Some(READ_TIMEOUT))
+        .map_err(|err| format!("Could not set a timeout: {err}"))?;
+
+  let mut reader = BufReader::new(&stream);
+
+  let mut request_line = String::new();
+  reader.read_line(&mut request_line).map_err(|err| format!("Could not read a request: {err}"))?;
+
+  // Headers are not needed.
+  loop {
+    let mut header = String::new();
+    let size =
+      reader.read_line(&mut header).map_err(|err| format!("Could not read a request: {err}"))?;
+    if size == 0 || header.trim_end().is_empty() {
+      break;
+    }
+  }
+
+  let mut request = request_line.split_whitespace();
+  let method = request.next().unwrap_or_default();
+  let target = request.next().unwrap_or_default();
+
+  let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+  let response = if !matches!(method, "GET" | "HEAD") {
+    Response::error("405 Method Not Allowed", "Only `GET` is supported.")
+  } else {
+    route(path, &parse_query_string(query)).unwrap_or_else(|err| {
+                                             print_warning!("{err}");
+                                             Response::error("500 Internal Server Error", &err)
+                                           })
+  };
+
+  write!(stream,
+         "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+         response.status,
+         response.content_type,
+         response.body.len()).map_err(|err| format!("Could not write a response: {err}"))?;
+
+  if method != "HEAD" {
+    stream.write_all(&response.body).map_err(|err| format!("Could not write a response: {err}"))?;
+  }
+
+  stream.flush().map_err(|err| format!("Could not write a response: {err}"))
+}
+
+pub(crate) fn serve<Args>(mut args: Args) -> ResultS
+  where Args: Iterator<Item = String>
+{
+  let mut flag_port;
+  let mut flag_help;
+
+  let mut flags = flags![
+    flag_port: StringFlag, ["-p", "--port"],
+    flag_help: BoolFlag,   ["--help"]
+  ];
+
+  parse_flags(&mut args, &mut flags).map_err(|err| get_flag_error(&err))?;
+  if flag_help {
+    return show_serve_help();
+  }
+
+  let port = if !flag_port.is_empty() {
+    flag_port.parse::<u16>().map_err(|err| format!("Invalid port `{flag_port}`: {err}"))?
+  } else if let Some(port) = get_config_number("serve.port")? {
+    u16::try_from(port).map_err(|_| format!("Invalid `serve.port` in the config: {port}"))?
+  } else {
+    DEFAULT_PORT
+  };
+
+  let listener =
+    TcpListener::bind((HOST, port)).map_err(|err| {
+                                     format!("Could not listen on `{HOST}:{port}`: {err}")
+                                   })?;
+
+  let address =
+    listener.local_addr().map_err(|err| format!("Could not get the address of server: {err}"))?;
+
+  println!("Serving {} docsets at {BOLD}http://{address}/{RESET}. Press Ctrl-C to stop.",
+           get_local_docsets()?.len());
+
+  for stream in listener.incoming() {
+    match stream {
+      Ok(stream) => {
+        thread::spawn(move || {
+          if let Err(err) = handle_connection(stream) {
+            print_warning!("{err}");
+          }
+        });
+      }
+      Err(err) => print_warning!("Could not accept a connection: {err}"),
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_parse_query_string()
+  {
+    let parameters = parse_query_string("docset=rust%2Cc&query=vec+push&fuzzy=on&all=0&&whole");
+    assert_eq!(get_parameter(&parameters, "docset"), Some("rust,c"));
+    assert_eq!(get_parameter(&parameters, "query"), Some("vec push"));
+    assert!(is_parameter_set(&parameters, "fuzzy"));
+    assert!(is_parameter_set(&parameters, "whole"));
+    assert!(!is_parameter_set(&parameters, "all"));
+    assert!(!is_parameter_set(&parameters, "precise"));
+
+    assert_eq!(escape_html("<a href=\"x\">&</a>"), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+  }
+}